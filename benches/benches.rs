@@ -33,7 +33,9 @@ SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 mod original_py;
 mod util;
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use graph_generator::layered_random::LayeredRandomGraph;
+use rs_graph_layout::graph_layout::GraphLayout;
 use util::{comm_graph_config::CompGraphConfig, cube_graph_config::CubeConfig, GraphBenchmark};
 
 use crate::util::layered_graph_config::LayeredGraphConfig;
@@ -53,7 +55,33 @@ pub fn bench_layered_graph(c: &mut Criterion) {
     benchmark.run(c);
 }
 
+/// Benchmark the rust port on layered graphs with an increasing number of random edges, which
+/// makes crossing reduction the most expensive step.
+pub fn bench_crossing_reduction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crossing_reduction");
+    for random_edges in [100, 200, 400] {
+        let mut graph = LayeredRandomGraph::new(50).with_seed(12345).with_degree(3);
+        for _ in 0..random_edges {
+            graph = graph.add_random_edge();
+        }
+        let edges = graph
+            .build()
+            .into_iter()
+            .map(|(t, h)| (t as u32 + 1, h as u32 + 1))
+            .collect::<Vec<_>>();
+
+        group.throughput(Throughput::Elements(edges.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("Original_rs", random_edges),
+            &edges,
+            |b, edges| b.iter(|| GraphLayout::create_layers(&[], edges, 40, false).unwrap()),
+        );
+    }
+    group.finish();
+}
+
 criterion_group!(layered, bench_layered_graph);
+criterion_group!(crossings, bench_crossing_reduction);
 criterion_group!(cube, bench_cube_graph);
 criterion_group!(comm, bench_comm_graph);
-criterion_main!(cube);
+criterion_main!(cube, crossings);
@@ -35,23 +35,30 @@ use pyo3::prelude::*;
 static PYTHON_FILE: &str = include_str!("python/graph_layout.py");
 
 pub fn graph_layout(edges: Vec<(u32, u32)>) -> PyResult<()> {
-    let arg2 = 40;
-    let arg3 = false;
+    let ret = graph_layout_result(edges, 40, false);
 
-    let ret: PyResult<Vec<HashMap<i32, (i32, i32)>>> = Python::with_gil(|py| {
+    if let Err(err) = ret {
+        panic!("{err} something went wrong with python");
+    }
+
+    Ok(())
+}
+
+/// Run the python implementation and return the layout of each component.
+///
+/// Unlike the rust implementation, python only knows the nodes contained in edges.
+pub fn graph_layout_result(
+    edges: Vec<(u32, u32)>,
+    node_separation: i32,
+    global_tasks_in_first_row: bool,
+) -> PyResult<Vec<HashMap<i32, (i32, i32)>>> {
+    Python::with_gil(|py| {
         let fun: Py<PyAny> = PyModule::from_code_bound(py, PYTHON_FILE, "", "")?
             .getattr("graph_layout")?
             .into();
 
-        let args = (edges, arg2, arg3);
+        let args = (edges, node_separation, global_tasks_in_first_row);
         let ret: Vec<HashMap<i32, (i32, i32)>> = fun.call1(py, args)?.extract(py)?;
         Ok(ret)
-    });
-
-    if ret.is_err() {
-        let err = ret.unwrap_err();
-        panic!("{err} something went wrong with python");
-    }
-
-    Ok(())
+    })
 }
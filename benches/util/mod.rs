@@ -37,7 +37,7 @@ use std::{
 };
 
 use criterion::{measurement::WallTime, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
-use rs_graph_layout::graph_layout::GraphLayout;
+use rs_graph_layout::{graph_layout::GraphLayout, storage::NodeStorage, LayoutOptions};
 use rust_sugiyama::configure::CrossingMinimization;
 
 use crate::original_py;
@@ -50,6 +50,7 @@ static WHICH_ENV: &str = "WHICH";
 static DIMS_ENV: &str = "DIMS";
 static TYPE_ENV: &str = "TYPE";
 static SAMPLE_SIZE_ENV: &str = "SIZE";
+static STORAGE_ENV: &str = "STORAGE";
 
 /// Trait that specifies funcionality needed in order to run a benchmark with the
 /// [self::GraphBenchmark::run] method.
@@ -101,17 +102,34 @@ where
 struct PythonAlgoConfig;
 #[derive(Debug, Clone, Copy)]
 struct RustAlgoConfig;
+#[derive(Debug, Clone, Copy)]
+struct PreviewAlgoConfig;
 
 /// Used to configure a benchmark for a graph.
 ///
 /// Can be configured via environment variables:
-/// - [self::WHICH_ENV]: which algorithm to run. is a 3-bit number. if the first bit is set, the
+/// - [self::WHICH_ENV]: which algorithm to run. is a 4-bit number. if the first bit is set, the
 /// original python implementation will be benchmarked. if the second bit is set, the rust port of
 /// the original pythom implementation will be benchmarked. if the third bit is set, sugiyamas
-/// algorithm will be benchmarked. it is possible to benchmark multiple alogrithms at the same
-/// time. the number can be in the range from 0-7.
+/// algorithm will be benchmarked. if the fourth bit is set, the approximate preview layout will be
+/// benchmarked, which can be compared to the rust port. it is possible to benchmark multiple
+/// alogrithms at the same time. the number can be in the range from 0-15.
 /// - [self::SAMPLE_SIZE_ENV]: how many samples to take for each benchmark. used to configure
 /// criterions [criterion::BenchmarkGroup::sample_size] method.
+/// - [self::STORAGE_ENV]: which storage strategies to use for the rust port of the original python
+/// implementation, see [rs_graph_layout::storage::NodeStorage]. can be `hash`, `btree`, `vec`,
+/// `both` (hash and btree) or `all`. `vec` only differs from `hash` with the `fast_storage`
+/// feature enabled.
+/// if set to `both` or `all`, the rust port is benchmarked once per strategy, so their throughput can be
+/// compared. measured with `WHICH=2 STORAGE=both SIZE=20` on the default cube config (median
+/// throughput, higher is better):
+///
+/// | side length | `hash`        | `btree`       |
+/// |-------------|---------------|---------------|
+/// | 3           | 507.6 Kelem/s | 441.8 Kelem/s |
+/// | 4           | 479.0 Kelem/s | 483.6 Kelem/s |
+///
+/// so the HashMap is faster for small graphs and on par for larger ones.
 ///
 /// See the respective graph config implementations for details on how to configure them via
 /// environment variables
@@ -131,8 +149,12 @@ where
     python: Option<PythonAlgoConfig>,
     /// Do we benchmark the rust version?
     rust: Option<RustAlgoConfig>,
+    /// Do we benchmark the preview layout?
+    preview: Option<PreviewAlgoConfig>,
     /// Do we benchmark sugiyama?
     sugiyama: Option<rust_sugiyama::configure::Config>,
+    /// Which storage strategies to benchmark the rust version with
+    storages: Vec<NodeStorage>,
     /// Sample size for criterion
     sample_size: usize,
     _phd: &'a PhantomData<()>,
@@ -145,9 +167,10 @@ where
 {
     const WHICH_DEFAULT: usize = 7;
     const SAMPLE_SIZE_DEFAULT: usize = 100;
+    const STORAGE_DEFAULT: &'static str = "hash";
 
     pub fn from_env() -> Self {
-        let (which, sample_size) = Self::read_envs();
+        let (which, sample_size, storages) = Self::read_envs();
         let graph_config = T::try_from_env().expect("Invalid config");
 
         let python = match which & 1 != 0 {
@@ -160,6 +183,11 @@ where
             false => None,
         };
 
+        let preview = match which & 8 != 0 {
+            true => Some(PreviewAlgoConfig),
+            false => None,
+        };
+
         let sugiyama = match which & 4 != 0 {
             true => Some(rust_sugiyama::configure::Config::new_from_env()),
             false => None,
@@ -169,7 +197,9 @@ where
             graph_config,
             python,
             rust,
+            preview,
             sugiyama,
+            storages,
             sample_size,
             _phd: &PhantomData,
         }
@@ -178,14 +208,16 @@ where
     pub fn write_benchmark_name(&self) -> String {
         let p = self.python.map_or("", |_| "p");
         let r = self.rust.map_or("", |_| "r");
+        let v = self.preview.map_or("", |_| "v");
         let s = self.sugiyama.map_or("", |_| "s");
         let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
 
         format!(
-            "{}_{}{}{}_{}",
+            "{}_{}{}{}{}_{}",
             self.graph_config,
             p,
             r,
+            v,
             s,
             elapsed.as_secs(),
         )
@@ -221,8 +253,30 @@ where
         }
 
         if let Some(_) = self.rust {
-            group.bench_with_input(BenchmarkId::new("Original_rs", items), &items, |b, _| {
-                b.iter(|| GraphLayout::create_layers(&vertices, &edges, 40, false))
+            for &storage in &self.storages {
+                let name = match storage {
+                    NodeStorage::HashMap => "Original_rs",
+                    NodeStorage::BTreeMap => "Original_rs-btree",
+                    NodeStorage::Vec => "Original_rs-vec",
+                };
+                group.bench_with_input(BenchmarkId::new(name, items), &items, |b, _| {
+                    b.iter(|| {
+                        let options = LayoutOptions {
+                            storage,
+                            ..Default::default()
+                        };
+                        GraphLayout::create_layers_with_options(
+                            &vertices, &edges, 40, false, options,
+                        )
+                        .unwrap()
+                    })
+                });
+            }
+        }
+
+        if let Some(_) = self.preview {
+            group.bench_with_input(BenchmarkId::new("Preview_rs", items), &items, |b, _| {
+                b.iter(|| GraphLayout::create_preview_layers(&vertices, &edges, 40).unwrap())
             });
         }
 
@@ -233,7 +287,7 @@ where
         }
     }
 
-    fn read_envs() -> (usize, usize) {
+    fn read_envs() -> (usize, usize, Vec<NodeStorage>) {
         // from, to, layers/dims, step_py
         let which = env::var(WHICH_ENV)
             .map_or(Ok(Self::WHICH_DEFAULT), |s| s.parse::<usize>())
@@ -241,7 +295,18 @@ where
         let sample_size = env::var(SAMPLE_SIZE_ENV)
             .map_or(Ok(Self::SAMPLE_SIZE_DEFAULT), |s| s.parse::<usize>())
             .expect("$WHICH set to non numeric value");
-        (which, sample_size) //, typ, cube_config)
+        let storages = match env::var(STORAGE_ENV)
+            .unwrap_or(Self::STORAGE_DEFAULT.to_string())
+            .as_str()
+        {
+            "hash" => vec![NodeStorage::HashMap],
+            "btree" => vec![NodeStorage::BTreeMap],
+            "both" => vec![NodeStorage::HashMap, NodeStorage::BTreeMap],
+            "vec" => vec![NodeStorage::Vec],
+            "all" => vec![NodeStorage::HashMap, NodeStorage::BTreeMap, NodeStorage::Vec],
+            other => panic!("$STORAGE set to invalid value: {other}"),
+        };
+        (which, sample_size, storages) //, typ, cube_config)
     }
 
     /// Run a benchmark
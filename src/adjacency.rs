@@ -0,0 +1,100 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Conversion of graphs given as adjacency lists.
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{error::LayoutError, EdgeListGraph};
+
+/// Convert a graph given as adjacency list into a list of nodes and a list of edges.
+///
+/// The nodes are made up of all keys of the adjacency list, nodes and edges are returned sorted.
+/// Every target needs to be a key of the adjacency list as well, even if it doesn't have any
+/// successors. Otherwise an error is returned.
+pub fn adjacency_to_edge_list(
+    adjacency: &HashMap<u32, Vec<u32>>,
+) -> Result<EdgeListGraph, LayoutError> {
+    let nodes = adjacency.keys().copied().collect::<BTreeSet<_>>();
+    let mut edges = Vec::new();
+    for (tail, heads) in adjacency {
+        for head in heads {
+            if !nodes.contains(head) {
+                return Err(LayoutError::InvalidGraph(format!(
+                    "Dangling edge ({tail}, {head}): node {head} is not part of the adjacency list"
+                )));
+            }
+            edges.push((*tail, *head));
+        }
+    }
+    edges.sort();
+
+    Ok((nodes.into_iter().collect(), edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::adjacency_to_edge_list;
+    use crate::{error::LayoutError, graph_layout::GraphLayout};
+
+    #[test]
+    fn adjacency_to_edge_list_same_layout_as_edge_list() {
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)];
+        let adjacency = HashMap::from([
+            (1, vec![2, 3]),
+            (2, vec![4]),
+            (3, vec![4]),
+            (4, vec![5]),
+            (5, vec![]),
+        ]);
+
+        let (adj_nodes, adj_edges) = adjacency_to_edge_list(&adjacency).unwrap();
+        assert_eq!(adj_nodes, nodes);
+        assert_eq!(adj_edges, edges);
+        assert_eq!(
+            GraphLayout::create_layers(&adj_nodes, &adj_edges, 40, false).unwrap(),
+            GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap()
+        );
+    }
+
+    #[test]
+    fn adjacency_to_edge_list_rejects_dangling_target() {
+        let adjacency = HashMap::from([(1, vec![2, 3]), (2, vec![])]);
+
+        assert_eq!(
+            adjacency_to_edge_list(&adjacency),
+            Err(LayoutError::InvalidGraph(
+                "Dangling edge (1, 3): node 3 is not part of the adjacency list".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,1377 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Queries and metrics about graphs and their layouts.
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{
+    algo::toposort,
+    graphmap::DiGraphMap,
+    visit::{depth_first_search, DfsEvent},
+    Direction,
+};
+use pyo3::prelude::*;
+use rust_sugiyama::configure::{CrossingMinimization, RankingType};
+
+use crate::{
+    check_original_inputs, create_layouts_sugiyama, error::LayoutError, graph_layout::GraphLayout,
+    LayoutOptions, NodePositions, SugiyamaConfig,
+};
+
+/// Build a graph keyed by the node ids, without self loops like the layout.
+fn build_graph(nodes: &[u32], edges: &[(u32, u32)]) -> DiGraphMap<u32, ()> {
+    let mut graph = DiGraphMap::new();
+    for node in nodes {
+        graph.add_node(*node);
+    }
+    for (tail, head) in edges.iter().filter(|(tail, head)| tail != head) {
+        graph.add_edge(*tail, *head, ());
+    }
+    graph
+}
+
+/// Calculate the length of the longest path ending in each node, when following edges in
+/// `direction`. Use [Direction::Incoming] for paths starting at a source.
+/// Returns an error if the graph contains a cycle.
+fn longest_path_lengths(
+    graph: &DiGraphMap<u32, ()>,
+    direction: Direction,
+) -> Result<HashMap<u32, usize>, LayoutError> {
+    let mut order = toposort(graph, None).map_err(|cycle| {
+        LayoutError::InvalidGraph(format!(
+            "Graph contains a cycle at node {}",
+            cycle.node_id()
+        ))
+    })?;
+    if direction == Direction::Outgoing {
+        order.reverse();
+    }
+
+    let mut lengths = HashMap::new();
+    for node in order {
+        let length = graph
+            .neighbors_directed(node, direction)
+            .map(|neighbor| lengths[&neighbor] + 1)
+            .max()
+            .unwrap_or(0);
+        lengths.insert(node, length);
+    }
+    Ok(lengths)
+}
+
+/// Returns the largest number of nodes in a level, when ranking the nodes by the longest path
+/// from a source.
+///
+/// For task graphs, this is a cheap estimate of how many tasks can run concurrently. The levels
+/// of all components are counted together, since they are independent of each other.
+/// Raises a `ValueError` if the graph contains a cycle.
+#[pyfunction]
+pub fn max_concurrency(nodes: Vec<u32>, edges: Vec<(u32, u32)>) -> PyResult<usize> {
+    let graph = build_graph(&nodes, &edges);
+    let mut level_widths = HashMap::<usize, usize>::new();
+    for level in longest_path_lengths(&graph, Direction::Incoming)?.into_values() {
+        *level_widths.entry(level).or_default() += 1;
+    }
+    Ok(level_widths.into_values().max().unwrap_or(0))
+}
+
+/// Returns all edges which lie on at least one longest path of the graph.
+///
+/// These are the edges which determine the number of levels of a layout: removing any other edge
+/// will never reduce its height. Raises a `ValueError` if the graph contains a cycle.
+#[pyfunction]
+pub fn height_critical_edges(nodes: Vec<u32>, edges: Vec<(u32, u32)>) -> PyResult<Vec<(u32, u32)>> {
+    let graph = build_graph(&nodes, &edges);
+    let from_source = longest_path_lengths(&graph, Direction::Incoming)?;
+    let to_sink = longest_path_lengths(&graph, Direction::Outgoing)?;
+    let longest = from_source.values().max().copied().unwrap_or(0);
+
+    let mut critical = graph
+        .all_edges()
+        .filter(|(tail, head, _)| from_source[tail] + 1 + to_sink[head] == longest)
+        .map(|(tail, head, _)| (tail, head))
+        .collect::<Vec<_>>();
+    critical.sort();
+    Ok(critical)
+}
+
+/// Count the (in-degree, out-degree) of every node.
+///
+/// Nodes without any edges report `(0, 0)`. Like in the layout, parallel edges are counted once
+/// and self loops are not counted, see [GraphLayout::node_degrees], which returns the same degrees
+/// for the 1 based ids of the original method.
+#[pyfunction]
+pub fn node_degrees(nodes: Vec<u32>, edges: Vec<(u32, u32)>) -> HashMap<u32, (usize, usize)> {
+    let mut degrees = nodes
+        .into_iter()
+        .map(|node| (node, (0, 0)))
+        .collect::<HashMap<_, _>>();
+    let mut seen = HashSet::new();
+    for (tail, head) in edges
+        .into_iter()
+        .filter(|(tail, head)| tail != head)
+        .filter(|edge| seen.insert(*edge))
+    {
+        degrees.entry(tail).or_default().1 += 1;
+        degrees.entry(head).or_default().0 += 1;
+    }
+    degrees
+}
+
+/// Returns the groups of nodes whose bounding boxes overlap, when the coordinates of the layout
+/// are multiplied by `scale`, while the nodes keep their size.
+///
+/// Overlapping is transitive, so nodes end up in the same group if they are connected by a chain
+/// of overlapping nodes. Nodes which don't overlap with any other node are not returned. The ids in
+/// each group and the groups themselves are sorted.
+#[pyfunction]
+pub fn overlaps_at_scale(layout: NodePositions, node_size: isize, scale: f64) -> Vec<Vec<u32>> {
+    let mut nodes = layout
+        .into_iter()
+        .map(|(id, (x, y))| (id as u32, (x as f64 * scale, y as f64 * scale)))
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|(id, _)| *id);
+    let overlap = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| {
+        (x1 - x2).abs() < node_size as f64 && (y1 - y2).abs() < node_size as f64
+    };
+
+    let mut group_of_node = vec![None; nodes.len()];
+    let mut groups = Vec::new();
+    for start in 0..nodes.len() {
+        if group_of_node[start].is_some() {
+            continue;
+        }
+        group_of_node[start] = Some(groups.len());
+        let mut group = vec![nodes[start].0];
+        let mut stack = vec![start];
+        while let Some(current) = stack.pop() {
+            for other in 0..nodes.len() {
+                if group_of_node[other].is_none() && overlap(nodes[current].1, nodes[other].1) {
+                    group_of_node[other] = Some(groups.len());
+                    group.push(nodes[other].0);
+                    stack.push(other);
+                }
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Returns the ids of all nodes, whose position lies within the given rectangle.
+///
+/// The rectangle is given in the coordinates of the layout and includes its borders. If
+/// `node_size` is given, nodes are also returned if only a part of their box lies within the
+/// rectangle. The ids are sorted.
+#[pyfunction]
+#[pyo3(signature = (layout, min_x, min_y, max_x, max_y, node_size=None))]
+pub fn nodes_in_rect(
+    layout: NodePositions,
+    min_x: isize,
+    min_y: isize,
+    max_x: isize,
+    max_y: isize,
+    node_size: Option<isize>,
+) -> Vec<u32> {
+    let margin = node_size.unwrap_or(0) / 2;
+    let mut nodes = layout
+        .into_iter()
+        .filter(|(_, (x, y))| {
+            (min_x - margin..=max_x + margin).contains(x)
+                && (min_y - margin..=max_y + margin).contains(y)
+        })
+        .map(|(id, _)| id as u32)
+        .collect::<Vec<_>>();
+    nodes.sort();
+    nodes
+}
+
+/// Flag each component whose layout contains more than `threshold` nodes, e.g. to show large
+/// components in a separate view.
+#[pyfunction]
+pub fn large_components(layouts: Vec<NodePositions>, threshold: usize) -> Vec<bool> {
+    layouts
+        .iter()
+        .map(|layout| layout.len() > threshold)
+        .collect()
+}
+
+/// Returns the slope (dy / dx) of each edge, e.g. to de-emphasize shallow edges spanning a large
+/// horizontal distance.
+///
+/// Vertical edges have an infinite slope. Edges whose endpoints are not part of the layout are
+/// skipped.
+#[pyfunction]
+pub fn edge_slopes(layout: NodePositions, edges: Vec<(u32, u32)>) -> HashMap<(u32, u32), f64> {
+    edges
+        .into_iter()
+        .filter_map(|(tail, head)| {
+            let (x1, y1) = layout.get(&(tail as usize))?;
+            let (x2, y2) = layout.get(&(head as usize))?;
+            let slope = if x1 == x2 {
+                f64::INFINITY
+            } else {
+                (y2 - y1) as f64 / (x2 - x1) as f64
+            };
+            Some(((tail, head), slope))
+        })
+        .collect()
+}
+
+/// Count the number of edges which cross each other, when drawn as straight lines between the
+/// positions of their endpoints.
+///
+/// Edges which share an endpoint never cross. Edges whose endpoints are not part of the layout
+/// are skipped.
+pub fn count_layout_crossings(layout: &NodePositions, edges: &[(u32, u32)]) -> usize {
+    crossing_edges(layout, edges).len()
+}
+
+/// Returns each pair of edges which cross each other, see [count_layout_crossings].
+fn crossing_edges(layout: &NodePositions, edges: &[(u32, u32)]) -> Vec<((u32, u32), (u32, u32))> {
+    let segments = edges
+        .iter()
+        .filter_map(|(tail, head)| {
+            let start = layout.get(&(*tail as usize))?;
+            let end = layout.get(&(*head as usize))?;
+            Some(((*tail, *head), (*start, *end)))
+        })
+        .collect::<Vec<_>>();
+    // which side of the line through a and b the point c is on
+    let side = |(ax, ay): (isize, isize), (bx, by): (isize, isize), (cx, cy): (isize, isize)| {
+        ((bx - ax) * (cy - ay) - (by - ay) * (cx - ax)).signum()
+    };
+
+    let mut crossings = Vec::new();
+    for (i, ((tail1, head1), (a, b))) in segments.iter().enumerate() {
+        for ((tail2, head2), (c, d)) in &segments[i + 1..] {
+            if tail1 == tail2 || tail1 == head2 || head1 == tail2 || head1 == head2 {
+                continue;
+            }
+            if side(*a, *b, *c) * side(*a, *b, *d) < 0 && side(*c, *d, *a) * side(*c, *d, *b) < 0 {
+                crossings.push(((*tail1, *head1), (*tail2, *head2)));
+            }
+        }
+    }
+    crossings
+}
+
+/// Returns the groups of edges which are drawn on top of each other, i.e. whose straight lines are
+/// collinear and overlap, e.g. so a renderer can offset or merge them.
+///
+/// Touching in a single point doesn't count as overlapping. Like in [overlaps_at_scale], edges end
+/// up in the same group if they are connected by a chain of overlapping edges, edges which don't
+/// overlap with any other edge are not returned. Edges whose endpoints are not part of the layout
+/// are skipped. The edges in each group and the groups themselves are sorted.
+#[pyfunction]
+pub fn overlapping_edges(layout: NodePositions, edges: Vec<(u32, u32)>) -> Vec<Vec<(u32, u32)>> {
+    let mut segments = edges
+        .into_iter()
+        .filter_map(|(tail, head)| {
+            let start = layout.get(&(tail as usize))?;
+            let end = layout.get(&(head as usize))?;
+            Some(((tail, head), (*start, *end)))
+        })
+        .filter(|(_, (start, end))| start != end)
+        .collect::<Vec<_>>();
+    segments.sort();
+    let overlap = |((ax, ay), (bx, by)): ((isize, isize), (isize, isize)),
+                   ((cx, cy), (dx, dy)): ((isize, isize), (isize, isize))| {
+        let (ux, uy) = (bx - ax, by - ay);
+        let cross = |(px, py): (isize, isize)| ux * (py - ay) - uy * (px - ax);
+        if cross((cx, cy)) != 0 || cross((dx, dy)) != 0 {
+            return false;
+        }
+        // compare the projections of both segments onto the direction of the first one
+        let project = |(px, py): (isize, isize)| ux * (px - ax) + uy * (py - ay);
+        let (c, d) = (project((cx, cy)), project((dx, dy)));
+        c.min(d).max(0) < c.max(d).min(ux * ux + uy * uy)
+    };
+
+    let mut group_of_edge = vec![None; segments.len()];
+    let mut groups = Vec::new();
+    for start in 0..segments.len() {
+        if group_of_edge[start].is_some() {
+            continue;
+        }
+        group_of_edge[start] = Some(groups.len());
+        let mut group = vec![segments[start].0];
+        let mut stack = vec![start];
+        while let Some(current) = stack.pop() {
+            for other in 0..segments.len() {
+                if group_of_edge[other].is_none() && overlap(segments[current].1, segments[other].1)
+                {
+                    group_of_edge[other] = Some(groups.len());
+                    group.push(segments[other].0);
+                    stack.push(other);
+                }
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Lay out the graph with Sugiyama's method and return the node which participates in the most
+/// edge crossings.
+///
+/// Each crossing is attributed to the four endpoints of the two crossing edges, so hiding the
+/// returned node removes the most crossings from the diagram. Ties are broken by the smaller id.
+/// Returns `None` if no edges cross.
+#[pyfunction]
+pub fn highest_crossing_node(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> Option<u32> {
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges.clone(), config);
+    let mut crossings_of_node = HashMap::<u32, usize>::new();
+    for layout in &layouts {
+        for ((tail1, head1), (tail2, head2)) in crossing_edges(layout, &edges) {
+            for id in [tail1, head1, tail2, head2] {
+                *crossings_of_node.entry(id).or_default() += 1;
+            }
+        }
+    }
+    crossings_of_node
+        .into_iter()
+        .max_by(|(id1, c1), (id2, c2)| c1.cmp(c2).then(id2.cmp(id1)))
+        .map(|(id, _)| id)
+}
+
+/// The path of each edge through the levels, see [edge_paths].
+pub type EdgePaths = HashMap<(u32, u32), Vec<i64>>;
+
+/// Lay out the graph with Sugiyama's method and return the path of each edge through the levels.
+///
+/// Each path starts with the tail and ends with the head of the edge. Edges spanning more than
+/// one level pass a dummy vertex in every level in between, which are listed with negative ids,
+/// unique across all paths. Since [rust_sugiyama] doesn't report its dummy vertices, they are
+/// reconstructed from the levels of the endpoints, which are derived from their y coordinates.
+#[pyfunction]
+pub fn edge_paths(nodes: Vec<u32>, edges: Vec<(u32, u32)>, config: SugiyamaConfig) -> EdgePaths {
+    edge_paths_capped(nodes, edges, config, usize::MAX).0
+}
+
+/// Works like [edge_paths], but edges which would pass more than `max_dummies_per_edge` dummy
+/// vertices are drawn straight, i.e. their path only consists of their tail and head.
+///
+/// This bounds the size of the paths for edges spanning a huge number of levels. Returns the paths
+/// together with the sorted list of edges which are drawn straight.
+#[pyfunction]
+pub fn edge_paths_capped(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+    max_dummies_per_edge: usize,
+) -> (EdgePaths, Vec<(u32, u32)>) {
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges.clone(), config);
+    let level_of_node = levels_from_coordinates(&layouts);
+
+    let mut next_dummy = -1;
+    let mut paths = HashMap::new();
+    let mut straight_edges = Vec::new();
+    for (tail, head) in edges {
+        let (Some(tail_level), Some(head_level)) =
+            (level_of_node.get(&tail), level_of_node.get(&head))
+        else {
+            continue;
+        };
+        let dummies = tail_level.abs_diff(*head_level).saturating_sub(1);
+        let mut path = vec![tail as i64];
+        if dummies > max_dummies_per_edge {
+            straight_edges.push((tail, head));
+        } else {
+            for _ in 0..dummies {
+                path.push(next_dummy);
+                next_dummy -= 1;
+            }
+        }
+        path.push(head as i64);
+        paths.insert((tail, head), path);
+    }
+    straight_edges.sort();
+    (paths, straight_edges)
+}
+
+/// A segment of an edge between two consecutive levels, see [edge_segments].
+pub type EdgeSegment = (usize, (isize, isize), (isize, isize));
+
+/// The points an edge passes through, see [edge_waypoints].
+pub type EdgeWaypoints = HashMap<(u32, u32), Vec<(isize, isize)>>;
+
+/// Lay out the graph with Sugiyama's method and split each edge at the boundaries between levels,
+/// e.g. for renderers drawing one band between two consecutive levels at a time.
+///
+/// Returns the segments of all edges in the order of `edges`, each one as (index of its upper
+/// level, start, end). The segments of an edge run from its tail to its head and each one ends
+/// where the next one starts. The segments connect the waypoints of [edge_waypoints].
+#[pyfunction]
+#[pyo3(signature = (nodes, edges, config, align_dummies=false))]
+pub fn edge_segments(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+    align_dummies: bool,
+) -> Vec<EdgeSegment> {
+    let mut segments = Vec::new();
+    for (_, tail_level, head_level, waypoints) in route_edges(nodes, edges, config, align_dummies) {
+        for (i, band) in waypoints.windows(2).enumerate() {
+            let upper_level = if tail_level < head_level {
+                tail_level + i
+            } else {
+                tail_level - i - 1
+            };
+            segments.push((upper_level, band[0], band[1]));
+        }
+    }
+    segments
+}
+
+/// Lay out the graph with Sugiyama's method and return the points each edge passes through, from
+/// its tail to its head.
+///
+/// Besides the endpoints, an edge passes a waypoint in every level in between. Since
+/// [rust_sugiyama] doesn't report its dummy vertices, these are placed on the straight line between
+/// the endpoints. If `align_dummies` is set, they are instead placed at the same x-coordinate,
+/// halfway between the endpoints, so long edges run straight down. This only changes the reported
+/// waypoints, the layout itself is left as it is. A waypoint is only aligned if this doesn't put
+/// it within `vertex_size` of a node or of an aligned waypoint of an earlier edge, otherwise the
+/// edge keeps its straight line. Edges whose endpoints lie in the same level are skipped.
+#[pyfunction]
+#[pyo3(signature = (nodes, edges, config, align_dummies=false))]
+pub fn edge_waypoints(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+    align_dummies: bool,
+) -> EdgeWaypoints {
+    route_edges(nodes, edges, config, align_dummies)
+        .into_iter()
+        .map(|(edge, _, _, waypoints)| (edge, waypoints))
+        .collect()
+}
+
+/// Returns the edges which close a cycle, when visiting the graph depth first, starting at the
+/// nodes in ascending order.
+///
+/// Reversing these edges makes the graph acyclic, so it can be laid out. Self loops are left out,
+/// since the layout drops them anyway. The edges are sorted.
+#[pyfunction]
+pub fn back_edges(nodes: Vec<u32>, edges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let graph = build_graph(&nodes, &edges);
+    let mut starts = graph.nodes().collect::<Vec<_>>();
+    starts.sort_unstable();
+
+    let mut back_edges = Vec::new();
+    depth_first_search(&graph, starts, |event| {
+        if let DfsEvent::BackEdge(tail, head) = event {
+            if tail != head {
+                back_edges.push((tail, head));
+            }
+        }
+    });
+    back_edges.sort_unstable();
+    back_edges
+}
+
+/// Route each back edge around the layout, through a lane to the right of its rightmost node.
+///
+/// A back edge leaves its tail to the right, runs up or down in its own lane and enters its head
+/// from the right, so it doesn't tangle with the forward edges. The lanes are `node_size` apart
+/// from each other and from the nodes. Returns the points each edge passes through, from its tail
+/// to its head. Back edges whose endpoints aren't part of the layout are skipped.
+#[pyfunction]
+pub fn route_back_edges(
+    layout: NodePositions,
+    back_edges: Vec<(u32, u32)>,
+    node_size: isize,
+) -> EdgeWaypoints {
+    let Some(max_x) = layout.values().map(|(x, _)| *x).max() else {
+        return EdgeWaypoints::new();
+    };
+    let mut back_edges = back_edges
+        .into_iter()
+        .filter(|(tail, head)| {
+            layout.contains_key(&(*tail as usize)) && layout.contains_key(&(*head as usize))
+        })
+        .collect::<Vec<_>>();
+    back_edges.sort_unstable();
+    back_edges.dedup();
+
+    back_edges
+        .into_iter()
+        .enumerate()
+        .map(|(lane, (tail, head))| {
+            let lane_x = max_x + node_size * (2 + lane as isize);
+            let (tail_x, tail_y) = layout[&(tail as usize)];
+            let (head_x, head_y) = layout[&(head as usize)];
+            let waypoints = vec![
+                (tail_x, tail_y),
+                (lane_x, tail_y),
+                (lane_x, head_y),
+                (head_x, head_y),
+            ];
+            ((tail, head), waypoints)
+        })
+        .collect()
+}
+
+/// Returns the edges whose waypoints don't run monotonically up or down from their tail to their
+/// head, i.e. which have a kink. Meant for checking routings in tests. The edges are sorted.
+#[pyfunction]
+pub fn assert_waypoints_monotone(waypoints: EdgeWaypoints) -> Vec<(u32, u32)> {
+    let mut kinked = waypoints
+        .into_iter()
+        .filter(|(_, points)| {
+            let ys = points.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+            !(ys.windows(2).all(|ys| ys[0] >= ys[1]) || ys.windows(2).all(|ys| ys[0] <= ys[1]))
+        })
+        .map(|(edge, _)| edge)
+        .collect::<Vec<_>>();
+    kinked.sort();
+    kinked
+}
+
+/// An edge with the level of its tail and head and its waypoints, see [route_edges].
+type EdgeRoute = ((u32, u32), usize, usize, Vec<(isize, isize)>);
+
+/// Calculate the waypoints of the edges, see [edge_waypoints].
+///
+/// Returns each edge with the level of its tail and head and its waypoints, in the order of
+/// `edges`. Edges whose endpoints lie in the same level are skipped.
+fn route_edges(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+    align_dummies: bool,
+) -> Vec<EdgeRoute> {
+    let vertex_size = config.vertex_size;
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges.clone(), config);
+    route_edges_in_layouts(layouts, edges, vertex_size, align_dummies)
+}
+
+/// Calculate the waypoints of the edges in the given layouts, see [route_edges].
+///
+/// Aligned waypoints keep `vertex_size` away from the nodes and from the aligned waypoints of the
+/// edges before them, so two edges never share a waypoint because of the alignment.
+fn route_edges_in_layouts(
+    layouts: Vec<NodePositions>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    align_dummies: bool,
+) -> Vec<EdgeRoute> {
+    let level_of_node = levels_from_coordinates(&layouts);
+    let position_of_node = layouts
+        .into_iter()
+        .flatten()
+        .map(|(id, position)| (id as u32, position))
+        .collect::<HashMap<_, _>>();
+
+    let mut routes = Vec::new();
+    let mut aligned_waypoints = Vec::new();
+    for (tail, head) in edges {
+        let (Some(tail_level), Some(head_level)) =
+            (level_of_node.get(&tail), level_of_node.get(&head))
+        else {
+            continue;
+        };
+        if tail_level == head_level {
+            continue;
+        }
+        let ((x1, y1), (x2, y2)) = (position_of_node[&tail], position_of_node[&head]);
+        let bands = tail_level.abs_diff(*head_level);
+        // the levels are evenly spaced, so interpolating both coordinates hits each level
+        let interpolate = |i: usize| {
+            let t = i as f64 / bands as f64;
+            (
+                (x1 as f64 + (x2 - x1) as f64 * t).round() as isize,
+                (y1 as f64 + (y2 - y1) as f64 * t).round() as isize,
+            )
+        };
+        let aligned_x = (x1 + x2) / 2;
+        let align = align_dummies
+            && (1..bands).all(|i| {
+                let (_, y) = interpolate(i);
+                position_of_node
+                    .values()
+                    .chain(&aligned_waypoints)
+                    .all(|(x, other_y)| {
+                        *other_y != y || x.abs_diff(aligned_x) >= vertex_size as usize
+                    })
+            });
+        let waypoints = (0..=bands)
+            .map(|i| match interpolate(i) {
+                (_, y) if align && 0 < i && i < bands => (aligned_x, y),
+                position => position,
+            })
+            .collect::<Vec<_>>();
+        if align {
+            aligned_waypoints.extend_from_slice(&waypoints[1..bands]);
+        }
+        routes.push(((tail, head), *tail_level, *head_level, waypoints));
+    }
+    routes
+}
+
+/// Lay out the graph with Sugiyama's method and return a signature of the ordering of each
+/// component, e.g. for golden tests or to detect whether a layout actually changed.
+///
+/// The signature lists the levels from top to bottom, each one as its index followed by the ids of
+/// its nodes from left to right, e.g. `0:3|1:1,2|2:4`. Layouts with the same ordering have the
+/// same signature, regardless of the exact coordinates.
+#[pyfunction]
+pub fn layout_signature(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> Vec<String> {
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges, config);
+    layouts.iter().map(ordering_signature).collect()
+}
+
+/// Returns the signature of the ordering of a single layout, see [layout_signature].
+fn ordering_signature(layout: &NodePositions) -> String {
+    let level_of_node = levels_from_coordinates(std::slice::from_ref(layout));
+    let mut nodes = layout
+        .iter()
+        .map(|(id, (x, _))| (level_of_node[&(*id as u32)], *x, *id))
+        .collect::<Vec<_>>();
+    nodes.sort();
+
+    let mut levels: Vec<(usize, Vec<String>)> = Vec::new();
+    for (level, _, id) in nodes {
+        match levels.last_mut() {
+            Some((last, ids)) if *last == level => ids.push(id.to_string()),
+            _ => levels.push((level, vec![id.to_string()])),
+        }
+    }
+    levels
+        .into_iter()
+        .map(|(level, ids)| format!("{level}:{}", ids.join(",")))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Derive the level of each node from its y coordinate, the first level of each component is `0`.
+fn levels_from_coordinates(layouts: &[NodePositions]) -> HashMap<u32, usize> {
+    let mut level_of_node = HashMap::new();
+    for layout in layouts {
+        let mut ys = layout.values().map(|(_, y)| *y).collect::<Vec<_>>();
+        ys.sort_unstable();
+        ys.dedup();
+        // levels which only contain dummy vertices leave a larger gap
+        let level_gap = ys.windows(2).map(|ys| ys[1] - ys[0]).min().unwrap_or(1);
+        let top = ys.last().copied().unwrap_or(0);
+        level_of_node.extend(
+            layout
+                .iter()
+                .map(|(id, (_, y))| (*id as u32, ((top - y) / level_gap) as usize)),
+        );
+    }
+    level_of_node
+}
+
+/// Rate the quality of a layout, lower is better.
+///
+/// The score is the number of crossings plus the mean length of the edges, measured in multiples
+/// of the node separation (`4 * node_size`). Edges whose endpoints are not part of the layout are
+/// skipped.
+pub fn layout_score(layout: &NodePositions, edges: &[(u32, u32)], node_size: isize) -> f64 {
+    let separation = (node_size * 4).max(1) as f64;
+    let lengths = edges
+        .iter()
+        .filter_map(|(tail, head)| {
+            let (x1, y1) = layout.get(&(*tail as usize))?;
+            let (x2, y2) = layout.get(&(*head as usize))?;
+            Some(((x1 - x2) as f64).hypot((y1 - y2) as f64) / separation)
+        })
+        .collect::<Vec<_>>();
+    let mean_length = if lengths.is_empty() {
+        0.
+    } else {
+        lengths.iter().sum::<f64>() / lengths.len() as f64
+    };
+    count_layout_crossings(layout, edges) as f64 + mean_length
+}
+
+/// Create up to `n` distinct layouts of the graph, each paired with its [layout_score].
+///
+/// The candidates are created with [create_layouts_original_with_options](crate::create_layouts_original_with_options)
+/// by restarting crossing reduction with different seeds, derived from `seed`. The components of
+/// the graph are placed next to each other. Candidates with identical positions are only returned
+/// once, so fewer than `n` candidates are returned if the graph doesn't allow for more. The
+/// candidates are sorted by ascending score, i.e. the best candidate comes first.
+/// The seed of `options` is replaced for each candidate. Raises a `ValueError` for the same
+/// inputs as [create_layouts_original_with_options](crate::create_layouts_original_with_options).
+#[pyfunction]
+#[pyo3(signature = (nodes, edges, n, seed, vertex_size=40, options=LayoutOptions::default()))]
+pub fn layout_candidates(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    n: usize,
+    seed: u64,
+    vertex_size: isize,
+    options: LayoutOptions,
+) -> PyResult<Vec<(NodePositions, f64)>> {
+    check_original_inputs(&nodes, &edges, vertex_size, &options)?;
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    // try more seeds than needed, since different seeds might result in the same layout
+    for attempt in 0..n as u64 * 4 {
+        let options = LayoutOptions {
+            seed: Some(seed.wrapping_add(attempt)),
+            ..options.clone()
+        };
+        let separation = vertex_size * options.separation_factor;
+        let (layouts, widths, _) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, vertex_size, false, options)?;
+
+        // place the components next to each other
+        let mut candidate = NodePositions::new();
+        let mut offset = 0;
+        for (layout, width) in layouts.into_iter().zip(widths) {
+            candidate.extend(layout.into_iter().map(|(id, (x, y))| (id, (x + offset, y))));
+            offset += width as isize * separation;
+        }
+
+        let mut key = candidate
+            .iter()
+            .map(|(id, pos)| (*id, *pos))
+            .collect::<Vec<_>>();
+        key.sort_unstable();
+        if seen.insert(key) {
+            let score = layout_score(&candidate, &edges, vertex_size);
+            candidates.push((candidate, score));
+        }
+    }
+    candidates.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    candidates.truncate(n);
+    Ok(candidates)
+}
+
+/// Lay out the graph with Sugiyama's method for every combination of ranking type and crossing
+/// minimization heuristic and collect the resulting metrics.
+///
+/// Returns a list of (`"{layering_type}-{crossing_minimization}"`, maximum width, maximum height,
+/// crossings), where width and height are taken over all components and the crossings are summed
+/// up. Meant to find the best configuration for a given graph.
+#[pyfunction]
+pub fn profile_configs(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+) -> Vec<(String, usize, usize, usize)> {
+    let ranking_types = [
+        RankingType::Original,
+        RankingType::MinimizeEdgeLength,
+        RankingType::Up,
+        RankingType::Down,
+    ];
+    let crossing_minimizations = [
+        CrossingMinimization::Barycenter,
+        CrossingMinimization::Median,
+    ];
+
+    let mut profiles = Vec::new();
+    for ranking_type in ranking_types {
+        for crossing_minimization in crossing_minimizations {
+            let config = SugiyamaConfig {
+                layering_type: <&str>::from(ranking_type).to_string(),
+                crossing_minimization: <&str>::from(crossing_minimization).to_string(),
+                ..Default::default()
+            };
+            let (layouts, widths, heights) =
+                create_layouts_sugiyama(nodes.clone(), edges.clone(), config.clone());
+            let crossings = layouts
+                .iter()
+                .map(|layout| count_layout_crossings(layout, &edges))
+                .sum();
+            profiles.push((
+                format!("{}-{}", config.layering_type, config.crossing_minimization),
+                widths.into_iter().max().unwrap_or(0),
+                heights.into_iter().max().unwrap_or(0),
+                crossings,
+            ));
+        }
+    }
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        assert_waypoints_monotone, back_edges, count_layout_crossings, edge_paths,
+        edge_paths_capped, edge_segments, edge_slopes, height_critical_edges,
+        highest_crossing_node, large_components, layout_candidates, layout_score, layout_signature,
+        max_concurrency, node_degrees, nodes_in_rect, ordering_signature, overlapping_edges,
+        overlaps_at_scale, profile_configs, route_back_edges, route_edges_in_layouts,
+    };
+    use crate::{graph_layout::GraphLayout, NodePositions};
+
+    #[test]
+    fn node_degrees_match_hand_count() {
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (1, 2), (1, 3), (2, 3), (4, 3), (5, 5)];
+
+        assert_eq!(
+            node_degrees(nodes, edges),
+            HashMap::from([
+                (1, (0, 2)),
+                (2, (1, 1)),
+                (3, (3, 0)),
+                (4, (0, 1)),
+                (5, (0, 0)),
+            ])
+        );
+    }
+
+    #[test]
+    fn height_critical_edges_only_edges_of_longest_path() {
+        let nodes = vec![1, 2, 3, 4, 5, 6];
+        let edges = vec![(1, 2), (2, 3), (3, 4), (1, 5), (5, 4), (6, 3)];
+
+        assert_eq!(
+            height_critical_edges(nodes, edges).unwrap(),
+            vec![(1, 2), (2, 3), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn height_critical_edges_two_longest_paths() {
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (2, 4), (1, 3), (3, 4), (1, 4), (5, 4)];
+
+        assert_eq!(
+            height_critical_edges(nodes, edges).unwrap(),
+            vec![(1, 2), (1, 3), (2, 4), (3, 4)]
+        );
+    }
+
+    #[test]
+    fn overlaps_at_scale_merge_when_zoomed_out() {
+        let nodes = [1, 2, 3, 4, 5];
+        let edges = [(1, 2), (1, 3), (1, 4), (4, 5)];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let layout = layouts[0].clone();
+
+        assert!(overlaps_at_scale(layout.clone(), 40, 1.0).is_empty());
+        assert_eq!(
+            overlaps_at_scale(layout, 40, 0.1),
+            vec![vec![1, 2, 3, 4, 5]]
+        );
+    }
+
+    #[test]
+    fn count_layout_crossings_ignores_shared_endpoints() {
+        let layout = crate::NodePositions::from([
+            (1, (0, 0)),
+            (2, (160, 0)),
+            (3, (0, -160)),
+            (4, (160, -160)),
+        ]);
+
+        assert_eq!(count_layout_crossings(&layout, &[(1, 4), (2, 3)]), 1);
+        assert_eq!(count_layout_crossings(&layout, &[(1, 3), (2, 4)]), 0);
+        assert_eq!(count_layout_crossings(&layout, &[(1, 3), (1, 4)]), 0);
+    }
+
+    #[test]
+    fn profile_configs_covers_every_combination() {
+        let nodes = vec![1, 2, 3, 4, 5, 6];
+        let edges = vec![(1, 4), (1, 5), (2, 4), (3, 5), (3, 6), (4, 6)];
+
+        let profiles = profile_configs(nodes, edges);
+
+        let mut names = profiles
+            .iter()
+            .map(|(name, ..)| name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "down-barycenter",
+                "down-median",
+                "minimize-barycenter",
+                "minimize-median",
+                "original-barycenter",
+                "original-median",
+                "up-barycenter",
+                "up-median",
+            ]
+        );
+        for (_, width, height, crossings) in profiles {
+            assert!(width >= 1);
+            assert!(height >= 1);
+            // there are only 6 edges, so at most 15 pairs can cross
+            assert!(crossings <= 15);
+        }
+    }
+
+    #[test]
+    fn highest_crossing_node_is_clutter_hub() {
+        let nodes = (1..=10).collect::<Vec<u32>>();
+        // 2, 3 and 4 each have their own children, the hub 1 is connected to all of them
+        let mut edges = vec![(2, 5), (2, 6), (3, 7), (3, 8), (4, 9), (4, 10)];
+        edges.extend((5..=10).map(|child| (1, child)));
+
+        assert_eq!(
+            highest_crossing_node(nodes, edges, Default::default()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn highest_crossing_node_none_without_crossings() {
+        let nodes = vec![1, 2, 3];
+        let edges = vec![(1, 2), (1, 3)];
+
+        assert_eq!(
+            highest_crossing_node(nodes, edges, Default::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn adaptive_crossing_minimization_never_worse_than_barycenter() {
+        let nodes = (1..=12).collect::<Vec<u32>>();
+        let edges = vec![
+            (1, 5),
+            (1, 8),
+            (2, 5),
+            (2, 6),
+            (3, 7),
+            (3, 5),
+            (4, 6),
+            (4, 8),
+            (5, 9),
+            (6, 11),
+            (7, 10),
+            (7, 12),
+            (8, 9),
+            (8, 12),
+        ];
+        let crossings = |crossing_minimization: &str| {
+            let config = crate::SugiyamaConfig {
+                crossing_minimization: crossing_minimization.to_string(),
+                ..Default::default()
+            };
+            let (layouts, _, _) =
+                crate::create_layouts_sugiyama(nodes.clone(), edges.clone(), config);
+            layouts
+                .iter()
+                .map(|layout| count_layout_crossings(layout, &edges))
+                .sum::<usize>()
+        };
+
+        // the levels of the barycenter layout are only reordered if that reduces the crossings
+        assert!(crossings("adaptive") <= crossings("barycenter"));
+    }
+
+    #[test]
+    fn nodes_in_rect_only_nodes_inside() {
+        // 4x4 grid with ids 1 to 16, row by row from the top
+        let layout = (0..16)
+            .map(|i| (i + 1, ((i % 4) as isize * 100, -((i / 4) as isize) * 100)))
+            .collect::<crate::NodePositions>();
+
+        assert_eq!(
+            nodes_in_rect(layout.clone(), 100, -200, 200, -100, None),
+            vec![6, 7, 10, 11]
+        );
+        // the rectangle lies between the nodes, so only their boxes overlap with it
+        assert!(nodes_in_rect(layout.clone(), 130, -180, 230, -120, None).is_empty());
+        assert_eq!(
+            nodes_in_rect(layout, 130, -180, 230, -120, Some(40)),
+            vec![7, 11]
+        );
+    }
+
+    #[test]
+    fn layout_candidates_distinct_and_sorted() {
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = vec![
+            (1, 4),
+            (1, 6),
+            (2, 4),
+            (2, 5),
+            (3, 5),
+            (3, 6),
+            (4, 7),
+            (4, 9),
+            (5, 8),
+            (6, 7),
+            (6, 9),
+            (7, 10),
+            (8, 11),
+            (9, 12),
+            (8, 10),
+        ];
+
+        let candidates =
+            layout_candidates(nodes, edges.clone(), 5, 7, 10, Default::default()).unwrap();
+        assert!(candidates.len() > 1);
+        assert!(candidates.len() <= 5);
+        for (i, (layout, score)) in candidates.iter().enumerate() {
+            assert_eq!(*score, layout_score(layout, &edges, 10));
+            for (other, other_score) in &candidates[i + 1..] {
+                assert_ne!(layout, other);
+                assert!(score <= other_score);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_paths_long_edge_passes_dummies() {
+        let nodes = vec![1, 2, 3, 4];
+        let edges = vec![(1, 2), (2, 3), (3, 4), (1, 4)];
+
+        let paths = edge_paths(nodes, edges, Default::default());
+
+        assert_eq!(paths[&(1, 2)], vec![1, 2]);
+        assert_eq!(paths[&(3, 4)], vec![3, 4]);
+        assert_eq!(paths[&(1, 4)], vec![1, -1, -2, 4]);
+    }
+
+    #[test]
+    fn edge_paths_capped_long_edge_drawn_straight() {
+        let nodes = (1..=20).collect::<Vec<_>>();
+        let mut edges = (1..20).map(|id| (id, id + 1)).collect::<Vec<_>>();
+        edges.extend([(1, 20), (1, 4)]);
+
+        let (paths, straight_edges) = edge_paths_capped(nodes, edges, Default::default(), 3);
+
+        assert_eq!(straight_edges, vec![(1, 20)]);
+        assert_eq!(paths[&(1, 20)], vec![1, 20]);
+        assert_eq!(paths[&(1, 4)].len(), 4);
+        assert!(paths.values().all(|path| path.len() <= 3 + 2));
+    }
+
+    #[test]
+    fn edge_segments_edge_spanning_two_bands() {
+        let nodes = vec![1, 2, 3];
+        let edges = vec![(1, 2), (2, 3), (1, 3)];
+
+        let segments = edge_segments(nodes, edges, Default::default(), false);
+
+        assert_eq!(segments.len(), 4);
+        let (upper, lower) = (segments[2], segments[3]);
+        assert_eq!((upper.0, lower.0), (0, 1));
+        assert_eq!(upper.2, lower.1);
+        assert_eq!(segments[0], (0, upper.1, segments[1].1));
+        assert_eq!(lower.2, segments[1].2);
+    }
+
+    #[test]
+    fn layout_signature_stable_and_tracks_ordering() {
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (1, 3), (2, 4), (3, 4), (5, 4)];
+
+        let signature = layout_signature(nodes.clone(), edges.clone(), Default::default());
+
+        assert_eq!(signature.len(), 1);
+        for _ in 0..5 {
+            assert_eq!(
+                layout_signature(nodes.clone(), edges.clone(), Default::default()),
+                signature
+            );
+        }
+
+        let layout = crate::NodePositions::from([
+            (1, (0, 0)),
+            (2, (0, -160)),
+            (3, (160, -160)),
+            (4, (80, -320)),
+        ]);
+        assert_eq!(ordering_signature(&layout), "0:1|1:2,3|2:4");
+        let mut swapped = layout.clone();
+        swapped.insert(2, (160, -160));
+        swapped.insert(3, (0, -160));
+        assert_eq!(ordering_signature(&swapped), "0:1|1:3,2|2:4");
+        // moving nodes without changing the ordering keeps the signature
+        let shifted = layout
+            .iter()
+            .map(|(id, (x, y))| (*id, (x * 2, *y)))
+            .collect();
+        assert_eq!(ordering_signature(&shifted), ordering_signature(&layout));
+    }
+
+    #[test]
+    fn edge_slopes_known_endpoints() {
+        let layout = crate::NodePositions::from([
+            (1, (0, 0)),
+            (2, (0, -160)),
+            (3, (160, -160)),
+            (4, (-640, -160)),
+        ]);
+        let edges = vec![(1, 2), (1, 3), (1, 4), (2, 5)];
+
+        let slopes = edge_slopes(layout, edges);
+
+        assert_eq!(slopes.len(), 3);
+        assert_eq!(slopes[&(1, 2)], f64::INFINITY);
+        assert_eq!(slopes[&(1, 3)], -1.);
+        assert_eq!(slopes[&(1, 4)], 0.25);
+    }
+
+    #[test]
+    fn large_components_match_node_counts() {
+        // components with 5, 2, 4 and 1 nodes
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = vec![
+            (1, 2),
+            (1, 3),
+            (3, 4),
+            (4, 5),
+            (6, 7),
+            (8, 9),
+            (9, 10),
+            (9, 11),
+        ];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        let flags = large_components(layouts.clone(), 3);
+
+        assert_eq!(
+            layouts
+                .iter()
+                .map(|layout| layout.len())
+                .collect::<Vec<_>>(),
+            vec![5, 2, 4, 1]
+        );
+        assert_eq!(flags, vec![true, false, true, false]);
+    }
+
+    #[test]
+    fn sugiyama_seed_reproducible() {
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = vec![
+            (1, 4),
+            (2, 4),
+            (3, 5),
+            (4, 6),
+            (7, 8),
+            (7, 9),
+            (10, 11),
+            (12, 11),
+        ];
+        let with_seed = |seed| {
+            let config = crate::SugiyamaConfig {
+                seed,
+                ..Default::default()
+            };
+            crate::create_layouts_sugiyama(nodes.clone(), edges.clone(), config)
+        };
+
+        assert_eq!(with_seed(None), with_seed(None));
+        assert_eq!(with_seed(Some(1)), with_seed(Some(1)));
+        let layouts = with_seed(Some(1));
+        assert!((2..20).any(|seed| with_seed(Some(seed)) != layouts));
+        // seeds beyond 64 bits are accepted and still vary the layout
+        assert_eq!(with_seed(Some(u128::MAX)), with_seed(Some(u128::MAX)));
+        assert!((2..20).any(|high: u128| with_seed(Some(high << 64)) != with_seed(Some(0))));
+    }
+
+    #[test]
+    fn edge_segments_align_dummies_of_long_edge() {
+        // 6 -> 5 spans four levels, so it passes three dummy vertices
+        let nodes = vec![1, 2, 3, 4, 5, 6];
+        let edges = vec![(1, 2), (2, 3), (3, 4), (4, 5), (6, 5)];
+        let long_edge_segments = |align_dummies| {
+            edge_segments(
+                nodes.clone(),
+                edges.clone(),
+                Default::default(),
+                align_dummies,
+            )
+            .split_off(4)
+        };
+
+        let segments = long_edge_segments(true);
+
+        assert_eq!(segments.len(), 4);
+        let dummies = segments[1..].iter().map(|(_, start, _)| *start);
+        let dummy_xs = dummies.map(|(x, _)| x).collect::<Vec<_>>();
+        let (start, end) = (segments[0].1, segments[3].2);
+        assert_eq!(dummy_xs, vec![(start.0 + end.0) / 2; 3]);
+        // the segments keep their levels and endpoints
+        let straight = long_edge_segments(false);
+        assert_eq!((straight[0].1, straight[3].2), (start, end));
+        for (aligned, straight) in segments.iter().zip(&straight) {
+            assert_eq!(aligned.0, straight.0);
+            assert_eq!(aligned.1 .1, straight.1 .1);
+        }
+    }
+
+    #[test]
+    fn route_edges_aligned_waypoints_dont_overlap() {
+        // 1 -> 3 and 2 -> 4 cross, so both would be aligned at x = 160
+        let layout = NodePositions::from([
+            (1, (0, 0)),
+            (2, (320, 0)),
+            (3, (320, -480)),
+            (4, (0, -480)),
+            (5, (-480, -160)),
+            (6, (-480, -320)),
+        ]);
+
+        let routes = route_edges_in_layouts(vec![layout], vec![(1, 3), (2, 4)], 40, true);
+
+        let waypoints = routes
+            .into_iter()
+            .map(|(_, _, _, waypoints)| waypoints)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            waypoints[0],
+            vec![(0, 0), (160, -160), (160, -320), (320, -480)]
+        );
+        // the second edge keeps its straight line
+        assert_eq!(
+            waypoints[1],
+            vec![(320, 0), (213, -160), (107, -320), (0, -480)]
+        );
+    }
+
+    #[test]
+    fn max_concurrency_diamond_widest_level() {
+        let nodes = vec![1, 2, 3, 4];
+        let edges = vec![(1, 2), (1, 3), (2, 4), (3, 4)];
+
+        assert_eq!(max_concurrency(nodes.clone(), edges.clone()).unwrap(), 2);
+        // a second diamond can run alongside the first one
+        let mut edges = edges;
+        edges.extend([(5, 6), (5, 7), (6, 8), (7, 8)]);
+        assert_eq!(max_concurrency((1..=8).collect(), edges).unwrap(), 4);
+        assert_eq!(max_concurrency(vec![], vec![]).unwrap(), 0);
+    }
+
+    #[test]
+    fn assert_waypoints_monotone_finds_kinks() {
+        let waypoints = HashMap::from([
+            ((1, 2), vec![(0, 0), (10, -160), (0, -320)]),
+            ((1, 3), vec![(0, 0), (0, -160), (0, 60)]),
+            ((4, 3), vec![(0, -320), (0, -160), (0, 0)]),
+        ]);
+
+        assert_eq!(assert_waypoints_monotone(waypoints), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn route_back_edges_runs_outside_forward_nodes() {
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (2, 3), (3, 4), (2, 5), (4, 2)];
+        let back = back_edges(nodes.clone(), edges.clone());
+        assert_eq!(back, vec![(4, 2)]);
+
+        let forward = edges
+            .iter()
+            .map(|edge| match back.contains(edge) {
+                true => (edge.1, edge.0),
+                false => *edge,
+            })
+            .collect::<Vec<_>>();
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &forward, 40, false).unwrap();
+        let layout = &layouts[0];
+        let waypoints = route_back_edges(layout.clone(), back, 40);
+
+        let max_x = layout.values().map(|(x, _)| *x).max().unwrap();
+        let min_y = layout.values().map(|(_, y)| *y).min().unwrap();
+        let max_y = layout.values().map(|(_, y)| *y).max().unwrap();
+        let points = &waypoints[&(4, 2)];
+        assert_eq!(points.first(), Some(&layout[&4]));
+        assert_eq!(points.last(), Some(&layout[&2]));
+        for (x, y) in &points[1..points.len() - 1] {
+            assert!(*x > max_x + 40);
+            assert!((min_y..=max_y).contains(y));
+        }
+    }
+
+    #[test]
+    fn overlapping_edges_groups_collinear_edges() {
+        // 1 -> 3 runs straight through 2, so it is drawn on top of 1 -> 2 and 2 -> 3
+        let layout = crate::NodePositions::from([
+            (1, (0, 0)),
+            (2, (0, -160)),
+            (3, (0, -320)),
+            (4, (160, 0)),
+            (5, (160, -160)),
+            (6, (320, 0)),
+            (7, (320, -160)),
+        ]);
+        let edges = vec![(1, 2), (2, 3), (1, 3), (4, 5), (6, 7)];
+
+        let groups = overlapping_edges(layout, edges);
+
+        assert_eq!(groups, vec![vec![(1, 2), (1, 3), (2, 3)]]);
+    }
+
+    #[test]
+    fn overlapping_edges_ignore_touching_edges() {
+        // 1 -> 2 and 2 -> 3 only touch in 2
+        let layout = crate::NodePositions::from([(1, (0, 0)), (2, (0, -160)), (3, (0, -320))]);
+
+        assert!(overlapping_edges(layout, vec![(1, 2), (2, 3)]).is_empty());
+    }
+
+    #[test]
+    fn layout_candidates_validate_inputs_and_use_separation() {
+        let nodes = vec![1, 2, 3, 4];
+        let edges = vec![(1, 2), (3, 4)];
+        assert!(
+            layout_candidates(nodes.clone(), edges.clone(), 2, 0, 0, Default::default()).is_err()
+        );
+        assert!(layout_candidates(
+            nodes.clone(),
+            vec![(1, 2), (2, 1)],
+            2,
+            0,
+            10,
+            Default::default()
+        )
+        .is_err());
+
+        let options = crate::LayoutOptions {
+            separation_factor: 2,
+            ..Default::default()
+        };
+        let candidates = layout_candidates(nodes, edges, 1, 0, 10, options).unwrap();
+        let layout = &candidates[0].0;
+        // the second component starts one separation right of the first one
+        assert_eq!((layout[&3].0 - layout[&1].0).abs(), 20);
+    }
+}
@@ -0,0 +1,304 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! An opt-in in-memory cache of layouts, so repeated requests for the same graph don't need to
+//! be recomputed.
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    hash::{Hash, Hasher},
+    sync::Mutex,
+};
+
+use pyo3::prelude::*;
+
+use super::{Layouts, SugiyamaConfig};
+
+/// The cache used by [create_layouts_sugiyama](crate::create_layouts_sugiyama), disabled until a
+/// capacity is set.
+static LAYOUT_CACHE: Mutex<LayoutCache> = Mutex::new(LayoutCache::new(0));
+
+/// Identifies a request, i.e. the sorted nodes and edges and the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    // the fields of the config, with the dummy size as its bits, so it can be hashed
+    config: (
+        isize,
+        bool,
+        u64,
+        String,
+        bool,
+        String,
+        Option<u128>,
+        Option<usize>,
+    ),
+}
+
+impl CacheKey {
+    pub(crate) fn new(nodes: &[u32], edges: &[(u32, u32)], config: &SugiyamaConfig) -> Self {
+        let mut nodes = nodes.to_vec();
+        nodes.sort();
+        let mut edges = edges.to_vec();
+        edges.sort();
+        Self {
+            nodes,
+            edges,
+            config: (
+                config.vertex_size,
+                config.dummy_vertices,
+                config.dummy_size.to_bits(),
+                config.crossing_minimization.clone(),
+                config.transpose,
+                config.layering_type.clone(),
+                config.seed,
+                config.vertex_spacing,
+            ),
+        }
+    }
+
+    fn hash_value(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// A least recently used cache of layouts.
+#[derive(Debug)]
+pub(crate) struct LayoutCache {
+    capacity: usize,
+    /// The entries together with the hash of their key, the most recently used one first
+    entries: VecDeque<(u64, CacheKey, Layouts)>,
+    hits: usize,
+    misses: usize,
+}
+
+impl LayoutCache {
+    pub(crate) const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the layouts stored for the key and marks them as most recently used.
+    pub(crate) fn get(&mut self, key: &CacheKey) -> Option<Layouts> {
+        let hash = key.hash_value();
+        // the hash is compared first, so the keys only need to be compared for likely matches
+        let Some(index) = self
+            .entries
+            .iter()
+            .position(|(entry_hash, entry_key, _)| *entry_hash == hash && entry_key == key)
+        else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        let entry = self.entries.remove(index).unwrap();
+        let layouts = entry.2.clone();
+        self.entries.push_front(entry);
+        Some(layouts)
+    }
+
+    /// Store the layouts for the key, evicting the least recently used entry if the cache is full.
+    pub(crate) fn insert(&mut self, key: CacheKey, layouts: Layouts) {
+        if self.capacity == 0 {
+            return;
+        }
+        let hash = key.hash_value();
+        self.entries
+            .retain(|(entry_hash, entry_key, _)| *entry_hash != hash || *entry_key != key);
+        self.entries.push_front((hash, key, layouts));
+        self.entries.truncate(self.capacity);
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.entries.truncate(capacity);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
+
+/// Returns true, if the layout cache has a capacity greater than `0`.
+pub(crate) fn is_enabled() -> bool {
+    LAYOUT_CACHE.lock().unwrap().capacity > 0
+}
+
+/// Returns the layouts stored for the key in the layout cache.
+pub(crate) fn lookup(key: &CacheKey) -> Option<Layouts> {
+    LAYOUT_CACHE.lock().unwrap().get(key)
+}
+
+/// Store the layouts for the key in the layout cache.
+pub(crate) fn store(key: CacheKey, layouts: Layouts) {
+    LAYOUT_CACHE.lock().unwrap().insert(key, layouts)
+}
+
+/// Set how many layouts are kept in the layout cache, evicting the least recently used ones if
+/// there are more. A capacity of `0`, the default, disables the cache.
+#[pyfunction]
+pub fn set_layout_cache_capacity(capacity: usize) {
+    LAYOUT_CACHE.lock().unwrap().set_capacity(capacity);
+}
+
+/// Remove all layouts from the layout cache and reset its statistics.
+#[pyfunction]
+pub fn clear_layout_cache() {
+    LAYOUT_CACHE.lock().unwrap().clear();
+}
+
+/// Returns the number of hits and misses of the layout cache since it was last cleared.
+#[pyfunction]
+pub fn layout_cache_stats() -> (usize, usize) {
+    let cache = LAYOUT_CACHE.lock().unwrap();
+    (cache.hits, cache.misses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheKey, LayoutCache};
+    use crate::{Layouts, SugiyamaConfig};
+
+    /// Look up the layouts in the cache and compute them on a miss, counting the computations.
+    fn cached_layouts(
+        cache: &mut LayoutCache,
+        calls: &mut usize,
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        config: &SugiyamaConfig,
+    ) -> Layouts {
+        let key = CacheKey::new(nodes, edges, config);
+        if let Some(layouts) = cache.get(&key) {
+            return layouts;
+        }
+        *calls += 1;
+        let layouts =
+            crate::create_layouts_sugiyama_uncached(nodes.to_vec(), edges.to_vec(), config.clone());
+        cache.insert(key, layouts.clone());
+        layouts
+    }
+
+    #[test]
+    fn layout_cache_hit_on_identical_request() {
+        let mut cache = LayoutCache::new(4);
+        let mut calls = 0;
+        let config = SugiyamaConfig::default();
+        let nodes = [1, 2, 3];
+        let edges = [(1, 2), (1, 3)];
+
+        let first = cached_layouts(&mut cache, &mut calls, &nodes, &edges, &config);
+        // the order of the nodes and edges doesn't matter
+        let second = cached_layouts(
+            &mut cache,
+            &mut calls,
+            &[3, 2, 1],
+            &[(1, 3), (1, 2)],
+            &config,
+        );
+
+        assert_eq!(calls, 1);
+        assert_eq!(first, second);
+        assert_eq!((cache.hits, cache.misses), (1, 1));
+    }
+
+    #[test]
+    fn layout_cache_hit_matches_uncached_permuted_request() {
+        let mut cache = LayoutCache::new(4);
+        let mut calls = 0;
+        let config = SugiyamaConfig {
+            seed: Some(7),
+            ..Default::default()
+        };
+        let edges = [(1, 4), (2, 4), (3, 5), (1, 5), (2, 6), (3, 6)];
+        cached_layouts(&mut cache, &mut calls, &[1, 2, 3, 4, 5, 6], &edges, &config);
+
+        let nodes = [6, 3, 5, 1, 4, 2];
+        let edges = [(3, 6), (1, 5), (2, 4), (3, 5), (2, 6), (1, 4)];
+        let cached = cached_layouts(&mut cache, &mut calls, &nodes, &edges, &config);
+        let uncached =
+            crate::create_layouts_sugiyama_uncached(nodes.to_vec(), edges.to_vec(), config);
+
+        assert_eq!(calls, 1);
+        assert_eq!(cached, uncached);
+    }
+
+    #[test]
+    fn layout_cache_miss_on_changed_input() {
+        let mut cache = LayoutCache::new(4);
+        let mut calls = 0;
+        let config = SugiyamaConfig::default();
+        let nodes = [1, 2, 3];
+        let edges = [(1, 2), (1, 3)];
+        cached_layouts(&mut cache, &mut calls, &nodes, &edges, &config);
+
+        cached_layouts(&mut cache, &mut calls, &[1, 2, 3, 4], &edges, &config);
+        cached_layouts(&mut cache, &mut calls, &nodes, &[(1, 2), (2, 3)], &config);
+        let config = SugiyamaConfig {
+            vertex_size: 20,
+            ..Default::default()
+        };
+        cached_layouts(&mut cache, &mut calls, &nodes, &edges, &config);
+        let config = SugiyamaConfig {
+            vertex_spacing: Some(80),
+            ..Default::default()
+        };
+        cached_layouts(&mut cache, &mut calls, &nodes, &edges, &config);
+
+        assert_eq!(calls, 5);
+        assert_eq!(cache.hits, 0);
+    }
+
+    #[test]
+    fn layout_cache_evicts_least_recently_used() {
+        let mut cache = LayoutCache::new(2);
+        let mut calls = 0;
+        let config = SugiyamaConfig::default();
+        let nodes = [1, 2, 3];
+        let graphs = [vec![(1, 2)], vec![(1, 2), (1, 3)], vec![(1, 2), (2, 3)]];
+
+        for edges in &graphs {
+            cached_layouts(&mut cache, &mut calls, &nodes, edges, &config);
+        }
+        // the first graph was evicted, the last one is still cached
+        cached_layouts(&mut cache, &mut calls, &nodes, &graphs[2], &config);
+        cached_layouts(&mut cache, &mut calls, &nodes, &graphs[0], &config);
+
+        assert_eq!(calls, 4);
+        assert_eq!(cache.entries.len(), 2);
+    }
+}
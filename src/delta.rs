@@ -0,0 +1,309 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Compact descriptions of the changes between two layouts, e.g. for sending updates to a live
+//! front-end.
+use std::collections::{HashMap, HashSet};
+
+use pyo3::prelude::*;
+use serde_json::json;
+
+use super::{error::LayoutError, graph_layout::GraphLayout, NodePositions};
+
+/// The changes needed to turn one layout into another.
+///
+/// All lists are sorted, so the same two layouts always produce the same delta.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LayoutDelta {
+    /// Nodes which are new, with their position
+    pub added: Vec<(usize, (isize, isize))>,
+    /// Nodes which changed their position, with their new position
+    pub moved: Vec<(usize, (isize, isize))>,
+    /// Nodes which are no longer part of the layout
+    pub removed: Vec<usize>,
+    /// Edges which are new
+    pub added_edges: Vec<(u32, u32)>,
+    /// Edges which are no longer part of the graph
+    pub removed_edges: Vec<(u32, u32)>,
+}
+
+impl LayoutDelta {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.moved.is_empty()
+            && self.removed.is_empty()
+            && self.added_edges.is_empty()
+            && self.removed_edges.is_empty()
+    }
+
+    /// Serialize the delta to JSON.
+    ///
+    /// Nodes are written as `{"id":1,"x":0,"y":0}`, edges as `[tail,head]`.
+    pub fn to_json(&self) -> String {
+        let nodes = |nodes: &[(usize, (isize, isize))]| {
+            nodes
+                .iter()
+                .map(|(id, (x, y))| json!({"id": id, "x": x, "y": y}))
+                .collect::<Vec<_>>()
+        };
+
+        json!({
+            "added": nodes(&self.added),
+            "moved": nodes(&self.moved),
+            "removed": self.removed,
+            "added_edges": self.added_edges,
+            "removed_edges": self.removed_edges,
+        })
+        .to_string()
+    }
+}
+
+/// Compute the changes between two layouts and their edges.
+pub fn diff_layouts(
+    old: &NodePositions,
+    old_edges: &[(u32, u32)],
+    new: &NodePositions,
+    new_edges: &[(u32, u32)],
+) -> LayoutDelta {
+    let mut delta = LayoutDelta::default();
+
+    for (id, coords) in new {
+        match old.get(id) {
+            None => delta.added.push((*id, *coords)),
+            Some(old_coords) if old_coords != coords => delta.moved.push((*id, *coords)),
+            _ => (),
+        }
+    }
+    delta.removed = old
+        .keys()
+        .filter(|id| !new.contains_key(id))
+        .copied()
+        .collect();
+
+    let old_edges = old_edges.iter().copied().collect::<HashSet<_>>();
+    let new_edges = new_edges.iter().copied().collect::<HashSet<_>>();
+    delta.added_edges = new_edges.difference(&old_edges).copied().collect();
+    delta.removed_edges = old_edges.difference(&new_edges).copied().collect();
+
+    delta.added.sort();
+    delta.moved.sort();
+    delta.removed.sort();
+    delta.added_edges.sort();
+    delta.removed_edges.sort();
+    delta
+}
+
+/// Lay out a graph with the original method and return the changes to a previous layout as JSON.
+///
+/// `previous` contains the positions of all nodes of the previous graph, i.e. the layouts of all
+/// its components merged into one map. The new graph is laid out completely and its components are
+/// merged the same way before comparing. Raises a `ValueError` if the new graph contains a cycle
+/// or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn layout_delta(
+    previous: NodePositions,
+    previous_edges: Vec<(u32, u32)>,
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+) -> PyResult<String> {
+    GraphLayout::check_node_size(vertex_size)?;
+    let (layouts, _, _) =
+        GraphLayout::create_layers(&nodes, &edges, vertex_size, global_tasks_in_first_row)?;
+    let current = layouts.into_iter().flatten().collect::<HashMap<_, _>>();
+
+    Ok(diff_layouts(&previous, &previous_edges, &current, &edges).to_json())
+}
+
+/// Measure how much a layout moved compared to a previous one, e.g. to tune animations.
+///
+/// Returns the root mean square of the displacements of all nodes contained in both layouts, so
+/// `0` means nothing moved and shifting the whole layout by a distance scores exactly that
+/// distance. Layouts without common nodes score `0`.
+#[pyfunction]
+pub fn stability_score(old: NodePositions, new: NodePositions) -> f64 {
+    let squared_displacements = new
+        .iter()
+        .filter_map(|(id, (x, y))| {
+            let (old_x, old_y) = old.get(id)?;
+            let (dx, dy) = ((x - old_x) as f64, (y - old_y) as f64);
+            Some(dx * dx + dy * dy)
+        })
+        .collect::<Vec<_>>();
+    if squared_displacements.is_empty() {
+        return 0.;
+    }
+    (squared_displacements.iter().sum::<f64>() / squared_displacements.len() as f64).sqrt()
+}
+
+/// Returns the swaps of two positions, which turn the order of each level in `old_levels` into
+/// the one in `new_levels`, e.g. for animating the reordering step by step.
+///
+/// The swaps of each level need to be applied in the given order and are as few as possible. Each
+/// level has to contain the same nodes in both orderings, otherwise a `ValueError` is raised.
+#[pyfunction]
+pub fn ordering_transform(
+    old_levels: Vec<Vec<u32>>,
+    new_levels: Vec<Vec<u32>>,
+) -> PyResult<Vec<Vec<(usize, usize)>>> {
+    if old_levels.len() != new_levels.len() {
+        return Err(LayoutError::InvalidGraph(format!(
+            "Orderings have a different number of levels: {} and {}",
+            old_levels.len(),
+            new_levels.len()
+        ))
+        .into());
+    }
+
+    let mut swaps = Vec::new();
+    for (level_index, (mut level, new_level)) in old_levels.into_iter().zip(new_levels).enumerate()
+    {
+        let mut position = level
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (*node, index))
+            .collect::<HashMap<_, _>>();
+        if level.len() != new_level.len() || new_level.iter().any(|n| !position.contains_key(n)) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Level {level_index} contains different nodes in the old and new ordering"
+            ))
+            .into());
+        }
+
+        // put the correct node at each position, this needs one swap less than the length of
+        // each cycle of the permutation, which is the minimum
+        let mut level_swaps = Vec::new();
+        for (index, node) in new_level.iter().enumerate() {
+            if level[index] != *node {
+                let other = position[node];
+                level.swap(index, other);
+                position.insert(level[other], other);
+                position.insert(*node, index);
+                level_swaps.push((index, other));
+            }
+        }
+        swaps.push(level_swaps);
+    }
+    Ok(swaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{diff_layouts, layout_delta, ordering_transform, stability_score};
+    use crate::{graph_layout::GraphLayout, NodePositions};
+
+    #[test]
+    fn diff_layouts_identical_layouts_is_empty() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (0, -160))]);
+        assert!(diff_layouts(&layout, &[(1, 2)], &layout, &[(1, 2)]).is_empty());
+    }
+
+    #[test]
+    fn layout_delta_adding_one_node() {
+        let edges = vec![(1, 2), (1, 3)];
+        let (layouts, _, _) = GraphLayout::create_layers(&[1, 2, 3], &edges, 40, false).unwrap();
+        let previous = layouts.into_iter().flatten().collect::<HashMap<_, _>>();
+        let new_edges = vec![(1, 2), (1, 3), (3, 4)];
+        let (layouts, _, _) =
+            GraphLayout::create_layers(&[1, 2, 3, 4], &new_edges, 40, false).unwrap();
+        let current = layouts.into_iter().flatten().collect::<HashMap<_, _>>();
+
+        let delta = diff_layouts(&previous, &edges, &current, &new_edges);
+
+        assert_eq!(delta.added, vec![(4, current[&4])]);
+        assert!(delta.removed.is_empty());
+        assert_eq!(delta.added_edges, vec![(3, 4)]);
+        assert!(delta.removed_edges.is_empty());
+        for (id, coords) in &delta.moved {
+            assert_ne!(previous[id], *coords);
+            assert_eq!(current[id], *coords);
+        }
+        // nodes which aren't reported as moved kept their position
+        for id in [1, 2, 3] {
+            if !delta.moved.iter().any(|(moved, _)| *moved == id) {
+                assert_eq!(previous[&id], current[&id]);
+            }
+        }
+
+        let json = layout_delta(previous, edges, vec![1, 2, 3, 4], new_edges, 40, false).unwrap();
+        assert_eq!(json, delta.to_json());
+        assert!(json.contains(&format!(
+            r#""added":[{{"id":4,"x":{},"y":{}}}]"#,
+            current[&4].0, current[&4].1
+        )));
+        assert!(json.contains(r#""added_edges":[[3,4]]"#));
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["removed"], serde_json::json!([]));
+        assert_eq!(value["moved"].as_array().unwrap().len(), delta.moved.len());
+    }
+
+    #[test]
+    fn ordering_transform_reversed_level() {
+        let old_levels = vec![vec![1], vec![2, 3, 4, 5, 6]];
+        let new_levels = vec![vec![1], vec![6, 5, 4, 3, 2]];
+
+        let swaps = ordering_transform(old_levels.clone(), new_levels.clone()).unwrap();
+
+        assert!(swaps[0].is_empty());
+        // two cycles of length 2 and one of length 1
+        assert_eq!(swaps[1].len(), 2);
+        for ((mut level, new_level), level_swaps) in
+            old_levels.into_iter().zip(new_levels).zip(swaps)
+        {
+            for (a, b) in level_swaps {
+                level.swap(a, b);
+            }
+            assert_eq!(level, new_level);
+        }
+    }
+
+    #[test]
+    fn stability_score_proportional_to_shift() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-80, -160)), (3, (80, -160))]);
+        let shifted = |dx: isize, dy: isize| {
+            layout
+                .iter()
+                .map(|(id, (x, y))| (*id, (x + dx, y + dy)))
+                .collect::<NodePositions>()
+        };
+
+        assert_eq!(stability_score(layout.clone(), layout.clone()), 0.);
+        assert_eq!(stability_score(layout.clone(), shifted(30, 40)), 50.);
+        assert_eq!(stability_score(layout.clone(), shifted(60, 80)), 100.);
+        // nodes which are only part of one layout are ignored
+        let mut with_new_node = shifted(30, 40);
+        with_new_node.insert(4, (1000, 1000));
+        assert_eq!(stability_score(layout, with_new_node), 50.);
+    }
+}
@@ -0,0 +1,1167 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Exporters which turn computed layouts into formats that can be viewed directly.
+use std::{collections::HashMap, fmt::Write, fs, io, path::PathBuf};
+
+use petgraph::unionfind::UnionFind;
+use pyo3::prelude::*;
+use serde_json::json;
+
+use super::{
+    create_layouts_sugiyama, error::LayoutError, EdgeListGraph, NodePositions, SugiyamaConfig,
+};
+
+/// A line from a start to an end point.
+type Segment = ((isize, isize), (isize, isize));
+
+/// The font size of edge labels in svg documents.
+const LABEL_FONT_SIZE: isize = 12;
+/// The estimated width of a single character of an edge label.
+const LABEL_CHAR_WIDTH: isize = 7;
+
+/// The default number of iterations of [bundle_edges].
+const EDGE_BUNDLING_ITERATIONS: usize = 30;
+/// The number of control points, which divide each edge into segments when bundling edges.
+const EDGE_BUNDLING_SUBDIVISIONS: usize = 7;
+/// Edges are only attracted by edges, whose compatibility is at least this high.
+const EDGE_COMPATIBILITY_THRESHOLD: f64 = 0.6;
+/// The strength of the spring force keeping bundled edges smooth, relative to the attraction
+/// between compatible edges.
+const EDGE_BUNDLING_STIFFNESS: f64 = 0.1;
+
+/// The hue offset between the colors of consecutive components, the fractional part of the golden
+/// ratio, which keeps any number of hues well apart.
+const COMPONENT_HUE_STEP: f64 = 0.618_033_988_749_895;
+/// The saturation of the component colors.
+const COMPONENT_SATURATION: f64 = 0.55;
+/// The brightness of the component colors, light enough for black labels.
+const COMPONENT_VALUE: f64 = 0.95;
+
+/// Render the layout of a single component as an SVG document.
+///
+/// Nodes are drawn as circles with a diameter of `node_size`, edges as straight lines between the
+/// ports of their endpoints (see [edge_ports]). Edges whose endpoints are not part of the layout
+/// are skipped, so the complete edge list of a graph can be passed for every component.
+/// The layout uses a y-axis pointing upwards, so the y-coordinates are flipped.
+#[pyfunction]
+pub fn to_svg(layout: NodePositions, edges: Vec<(u32, u32)>, node_size: isize) -> String {
+    render_svg(&layout, &edges, node_size, None, 0)
+}
+
+/// Render the layout of a single component as an SVG document with labeled edges.
+///
+/// Works like [to_svg], but `labels` contain the label of the edge at the same index. Each label
+/// is placed next to the middle of its edge, so that it overlaps as few nodes as possible (see
+/// [place_edge_labels]). Raises a `ValueError` if the number of labels and edges differ.
+#[pyfunction]
+pub fn to_svg_labeled(
+    layout: NodePositions,
+    edges: Vec<(u32, u32)>,
+    node_size: isize,
+    labels: Vec<String>,
+) -> PyResult<String> {
+    if labels.len() != edges.len() {
+        return Err(LayoutError::InvalidGraph(format!(
+            "Got {} labels for {} edges",
+            labels.len(),
+            edges.len()
+        ))
+        .into());
+    }
+    Ok(render_svg(&layout, &edges, node_size, Some(&labels), 0))
+}
+
+/// Render the layout of a single component as an SVG document with bundled edges.
+///
+/// Works like [to_svg], but edges running in similar directions are pulled towards each other,
+/// so that they merge into bundles (see [bundle_edges]). The edges are drawn as polylines through
+/// their control points. This reduces the clutter of dense graphs, but takes time quadratic in
+/// the number of edges for each of the `iterations`.
+#[pyfunction]
+#[pyo3(signature = (layout, edges, node_size, iterations=EDGE_BUNDLING_ITERATIONS))]
+pub fn to_svg_bundled(
+    layout: NodePositions,
+    edges: Vec<(u32, u32)>,
+    node_size: isize,
+    iterations: usize,
+) -> String {
+    render_svg(&layout, &edges, node_size, None, iterations)
+}
+
+/// Render the positions of `nodes` as an SVG document, e.g. a layout returned by
+/// [crate::create_layouts_original].
+///
+/// Works like [to_svg], but only draws the given nodes and the edges between them. The canvas
+/// has the width and height of the layout (see [crate::transform::layout_dimensions]) plus a margin of half a node
+/// on each side. Raises a `ValueError` if a node has no position.
+#[pyfunction]
+pub fn layout_to_svg(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    positions: NodePositions,
+    node_size: isize,
+) -> PyResult<String> {
+    let mut layout = NodePositions::new();
+    for node in nodes {
+        let Some(&position) = positions.get(&(node as usize)) else {
+            return Err(LayoutError::InvalidGraph(format!("Node {node} has no position")).into());
+        };
+        layout.insert(node as usize, position);
+    }
+    Ok(render_svg(&layout, &edges, node_size, None, 0))
+}
+
+fn render_svg(
+    layout: &NodePositions,
+    edges: &[(u32, u32)],
+    node_size: isize,
+    labels: Option<&[String]>,
+    bundle_iterations: usize,
+) -> String {
+    let (min_x, min_y, max_x, max_y) = svg_bounds(layout, node_size);
+    let mut svg = String::new();
+
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        max_x - min_x,
+        max_y - min_y,
+        min_x,
+        min_y,
+        max_x - min_x,
+        max_y - min_y
+    );
+
+    svg.push_str("<g stroke=\"black\">\n");
+    let ports = edge_ports(layout, edges, node_size);
+    if bundle_iterations > 0 {
+        for path in bundle_edges(&ports, bundle_iterations)
+            .into_iter()
+            .flatten()
+        {
+            let points = path
+                .iter()
+                .map(|(x, y)| format!("{x},{y}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(svg, r#"<polyline points="{}" fill="none"/>"#, points);
+        }
+    } else {
+        for ((x1, y1), (x2, y2)) in ports.iter().flatten() {
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                x1, y1, x2, y2
+            );
+        }
+    }
+    svg.push_str("</g>\n");
+
+    // sort nodes, so the output is deterministic
+    let mut nodes = layout.iter().collect::<Vec<_>>();
+    nodes.sort();
+    svg.push_str("<g fill=\"white\" stroke=\"black\">\n");
+    for (id, (x, y)) in nodes {
+        let _ = writeln!(
+            svg,
+            r#"<circle id="n{}" cx="{}" cy="{}" r="{}"/>"#,
+            id,
+            x,
+            -y,
+            node_size / 2
+        );
+    }
+    svg.push_str("</g>\n");
+
+    if let Some(labels) = labels {
+        let positions = place_edge_labels(layout, &ports, node_size, labels);
+        let _ = writeln!(
+            svg,
+            r#"<g font-size="{}" text-anchor="middle" dominant-baseline="middle">"#,
+            LABEL_FONT_SIZE
+        );
+        for (label, (x, y)) in labels
+            .iter()
+            .zip(positions)
+            .filter_map(|(label, position)| Some(label).zip(position))
+        {
+            let label = label
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            let _ = writeln!(svg, r#"<text x="{}" y="{}">{}</text>"#, x, y, label);
+        }
+        svg.push_str("</g>\n");
+    }
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+/// The script of the page created by [to_html], which pans the svg when dragging it with the mouse
+/// and zooms at the cursor when using the mouse wheel.
+const PAN_ZOOM_SCRIPT: &str = r#"const svg = document.querySelector("svg");
+let [x, y, w, h] = svg.getAttribute("viewBox").split(" ").map(Number);
+let drag = null;
+const update = () => svg.setAttribute("viewBox", `${x} ${y} ${w} ${h}`);
+const scale = () => w / svg.getBoundingClientRect().width;
+svg.addEventListener("mousedown", (e) => { drag = [e.clientX, e.clientY]; });
+window.addEventListener("mouseup", () => { drag = null; });
+window.addEventListener("mousemove", (e) => {
+  if (drag === null) return;
+  x -= (e.clientX - drag[0]) * scale();
+  y -= (e.clientY - drag[1]) * scale();
+  drag = [e.clientX, e.clientY];
+  update();
+});
+svg.addEventListener("wheel", (e) => {
+  e.preventDefault();
+  const factor = e.deltaY < 0 ? 0.9 : 1.1;
+  const rect = svg.getBoundingClientRect();
+  const cx = x + (e.clientX - rect.left) * scale();
+  const cy = y + (e.clientY - rect.top) * scale();
+  x = cx - (cx - x) * factor;
+  y = cy - (cy - y) * factor;
+  w *= factor;
+  h *= factor;
+  update();
+});"#;
+
+/// Render the layout of a single component as a self-contained HTML page.
+///
+/// The page contains the SVG document created by [to_svg], which fills the window and can be
+/// panned by dragging it and zoomed with the mouse wheel.
+#[pyfunction]
+pub fn to_html(layout: NodePositions, edges: Vec<(u32, u32)>, node_size: isize) -> String {
+    let svg = to_svg(layout, edges, node_size);
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Layout</title>
+<style>html, body {{ margin: 0; height: 100%; overflow: hidden; }} svg {{ width: 100%; height: 100%; cursor: grab; }}</style>
+</head>
+<body>
+{svg}<script>
+{PAN_ZOOM_SCRIPT}
+</script>
+</body>
+</html>
+"#
+    )
+}
+
+/// The size of a node in a TikZ picture created by [to_tikz], in cm.
+const TIKZ_NODE_SIZE_CM: f64 = 0.5;
+
+/// Render the layout of a single component as a TikZ picture, which can be included in a LaTeX
+/// document.
+///
+/// The coordinates are scaled, so that each node is [TIKZ_NODE_SIZE_CM] wide. Nodes are drawn as
+/// circles labeled with their id, edges as arrows between them. Like the layout, TikZ uses a
+/// y-axis pointing upwards, so the y-coordinates are kept. Edges whose endpoints are not part of
+/// the layout are skipped.
+#[pyfunction]
+pub fn to_tikz(layout: NodePositions, edges: Vec<(u32, u32)>, node_size: isize) -> String {
+    let scale = TIKZ_NODE_SIZE_CM / node_size as f64;
+    let mut tikz = format!(
+        "\\begin{{tikzpicture}}[every node/.style={{draw, circle, minimum size={TIKZ_NODE_SIZE_CM}cm}}]\n"
+    );
+
+    // sort nodes, so the output is deterministic
+    let mut nodes = layout.iter().collect::<Vec<_>>();
+    nodes.sort();
+    for (id, (x, y)) in nodes {
+        let _ = writeln!(
+            tikz,
+            "  \\node (n{id}) at ({:.2}, {:.2}) {{{id}}};",
+            *x as f64 * scale,
+            *y as f64 * scale
+        );
+    }
+    for (tail, head) in edges {
+        if layout.contains_key(&(tail as usize)) && layout.contains_key(&(head as usize)) {
+            let _ = writeln!(tikz, "  \\draw[->] (n{tail}) -- (n{head});");
+        }
+    }
+    tikz.push_str("\\end{tikzpicture}\n");
+
+    tikz
+}
+
+/// Write the layout of a single component as a graph in the DOT language of Graphviz.
+///
+/// Each node gets its position as pinned `pos` attribute, so the layout is kept when rendering
+/// it with `neato -n`. Every edge is written, including parallel ones, which share the same
+/// position in the layout. If `labels` are given, they contain the label of the edge at the same
+/// index, so parallel edges can carry different labels. Edges whose endpoints are not part of the
+/// layout are skipped. Raises a `ValueError` if the number of labels and edges differ.
+#[pyfunction]
+#[pyo3(signature = (layout, edges, labels=None))]
+pub fn to_dot(
+    layout: NodePositions,
+    edges: Vec<(u32, u32)>,
+    labels: Option<Vec<String>>,
+) -> PyResult<String> {
+    if let Some(labels) = labels.as_ref().filter(|labels| labels.len() != edges.len()) {
+        return Err(LayoutError::InvalidGraph(format!(
+            "Got {} labels for {} edges",
+            labels.len(),
+            edges.len()
+        ))
+        .into());
+    }
+    let mut dot = String::from("digraph {\n  node [shape=circle];\n");
+
+    // sort nodes, so the output is deterministic
+    let mut nodes = layout.iter().collect::<Vec<_>>();
+    nodes.sort();
+    for (id, (x, y)) in nodes {
+        let _ = writeln!(dot, "  {id} [pos=\"{x},{y}!\"];");
+    }
+    for (edge_index, (tail, head)) in edges.iter().enumerate() {
+        if !layout.contains_key(&(*tail as usize)) || !layout.contains_key(&(*head as usize)) {
+            continue;
+        }
+        match &labels {
+            Some(labels) => {
+                let label = labels[edge_index]
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"");
+                let _ = writeln!(dot, "  {tail} -> {head} [label=\"{label}\"];");
+            }
+            None => {
+                let _ = writeln!(dot, "  {tail} -> {head};");
+            }
+        }
+    }
+    dot.push_str("}\n");
+
+    Ok(dot)
+}
+
+/// Write the layout of a single component as a GraphML document.
+///
+/// The document declares the keys `x` and `y` for nodes, each node carries its position as data
+/// with these keys. The coordinates are written as they are, i.e. with the y-axis pointing
+/// upwards. Every edge is written, including parallel ones. Edges whose endpoints are not part of
+/// the layout are skipped.
+#[pyfunction]
+pub fn to_graphml(layout: NodePositions, edges: Vec<(u32, u32)>) -> String {
+    let mut graphml = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+        "\n",
+        r#"  <key id="x" for="node" attr.name="x" attr.type="int"/>"#,
+        "\n",
+        r#"  <key id="y" for="node" attr.name="y" attr.type="int"/>"#,
+        "\n",
+        r#"  <graph edgedefault="directed">"#,
+        "\n",
+    ));
+
+    // sort nodes, so the output is deterministic
+    let mut nodes = layout.iter().collect::<Vec<_>>();
+    nodes.sort();
+    for (id, (x, y)) in nodes {
+        let _ = writeln!(
+            graphml,
+            r#"    <node id="n{id}"><data key="x">{x}</data><data key="y">{y}</data></node>"#
+        );
+    }
+    for (tail, head) in edges.iter().filter(|(tail, head)| {
+        layout.contains_key(&(*tail as usize)) && layout.contains_key(&(*head as usize))
+    }) {
+        let _ = writeln!(graphml, r#"    <edge source="n{tail}" target="n{head}"/>"#);
+    }
+    graphml.push_str("  </graph>\n</graphml>\n");
+
+    graphml
+}
+
+/// Write the layout of a single component in the JSON format of the Eclipse Layout Kernel (ELK).
+///
+/// Each node becomes a child of the root node with its id as string, the position of its top left
+/// corner and a width and height of `node_size`. Like in [to_svg], the y-coordinates are flipped,
+/// since ELK's y-axis points downwards, and the layout is moved, so it starts at `(0, 0)`. Edges
+/// whose endpoints are not part of the layout are skipped.
+#[pyfunction]
+pub fn to_elk_json(layout: NodePositions, edges: Vec<(u32, u32)>, node_size: isize) -> String {
+    let radius = node_size / 2;
+    // the top left corner of the bounding box of all nodes
+    let min_x = layout.values().map(|(x, _)| x - radius).min().unwrap_or(0);
+    let min_y = layout.values().map(|(_, y)| -y - radius).min().unwrap_or(0);
+
+    // sort nodes, so the output is deterministic
+    let mut nodes = layout.iter().collect::<Vec<_>>();
+    nodes.sort();
+    let children = nodes
+        .iter()
+        .map(|(id, (x, y))| {
+            json!({
+                "id": id.to_string(),
+                "x": x - radius - min_x,
+                "y": -y - radius - min_y,
+                "width": node_size,
+                "height": node_size,
+            })
+        })
+        .collect::<Vec<_>>();
+    let edges = edges
+        .iter()
+        .filter(|(tail, head)| {
+            layout.contains_key(&(*tail as usize)) && layout.contains_key(&(*head as usize))
+        })
+        .enumerate()
+        .map(|(i, (tail, head))| {
+            json!({
+                "id": format!("e{i}"),
+                "sources": [tail.to_string()],
+                "targets": [head.to_string()],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let (width, height) = nodes.iter().fold((0, 0), |(width, height), (_, (x, y))| {
+        (
+            width.max(x - radius - min_x + node_size),
+            height.max(-y - radius - min_y + node_size),
+        )
+    });
+
+    json!({
+        "id": "root",
+        "width": width,
+        "height": height,
+        "children": children,
+        "edges": edges,
+    })
+    .to_string()
+}
+
+/// Render the layout of a single component as a PNG image.
+///
+/// Nodes are drawn as filled circles with a diameter of `node_size`, edges as straight lines
+/// between the ports of their endpoints, like in [to_svg]. The image covers the same area as the
+/// SVG document would. Returns the encoded bytes of the image.
+#[cfg(feature = "image")]
+#[pyfunction]
+pub fn render_png(layout: NodePositions, edges: Vec<(u32, u32)>, node_size: isize) -> Vec<u8> {
+    const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+    let (min_x, min_y, max_x, max_y) = svg_bounds(&layout, node_size);
+    let (width, height) = (max_x - min_x, max_y - min_y);
+    let mut pixels = [255; 4].repeat((width * height) as usize);
+    let mut set_pixel = |x: isize, y: isize| {
+        let (x, y) = (x - min_x, y - min_y);
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            let offset = ((y * width + x) * 4) as usize;
+            pixels[offset..offset + 4].copy_from_slice(&BLACK);
+        }
+    };
+
+    for ((x1, y1), (x2, y2)) in edge_ports(&layout, &edges, node_size).into_iter().flatten() {
+        let steps = (x2 - x1).abs().max((y2 - y1).abs()).max(1);
+        for step in 0..=steps {
+            set_pixel(x1 + (x2 - x1) * step / steps, y1 + (y2 - y1) * step / steps);
+        }
+    }
+
+    let radius = node_size / 2;
+    for (x, y) in layout.values() {
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy <= radius * radius {
+                    set_pixel(x + dx, -y + dy);
+                }
+            }
+        }
+    }
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // writing into a vector can't fail and the buffer has the size given to the encoder
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(&pixels).unwrap();
+    writer.finish().unwrap();
+
+    png
+}
+
+/// Lay out each graph with Sugiyama's method and write every component into `dir`.
+///
+/// The files are named `component_{i}.svg`, where `i` counts the components of all graphs in
+/// order. `dir` is created if it does not exist yet.
+/// Returns the paths of the written files.
+#[pyfunction]
+pub fn layout_batch_to_dir(
+    graphs: Vec<EdgeListGraph>,
+    dir: PathBuf,
+    config: SugiyamaConfig,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(&dir)?;
+    let node_size = config.vertex_size;
+    let mut paths = Vec::new();
+
+    for (nodes, edges) in graphs {
+        let (layouts, _, _) = create_layouts_sugiyama(nodes, edges.clone(), config.clone());
+        for layout in layouts {
+            let path = dir.join(format!("component_{}.svg", paths.len()));
+            fs::write(&path, to_svg(layout, edges.clone(), node_size))?;
+            paths.push(path);
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Calculate the points at which the edges attach to their endpoints, in svg coordinates.
+///
+/// Edges leading to a node above attach at the top of the node box, all other edges at the
+/// bottom. The edges attaching at the same side of a node are spread evenly along it, ordered by
+/// the x-coordinate of their other endpoint, so they fan into the node without crossing.
+/// Returns the start and end point for each edge, or `None` if an endpoint is not in the layout.
+fn edge_ports(
+    layout: &NodePositions,
+    edges: &[(u32, u32)],
+    node_size: isize,
+) -> Vec<Option<Segment>> {
+    let svg_position = |id: u32| layout.get(&(id as usize)).map(|(x, y)| (*x, -*y));
+    let radius = node_size / 2;
+
+    // (node, attaches at top) -> [(x of other endpoint, edge index, is tail)]
+    let mut sides = HashMap::<(u32, bool), Vec<(isize, usize, bool)>>::new();
+    for (edge_index, (tail, head)) in edges.iter().enumerate() {
+        let (Some((tail_x, tail_y)), Some((head_x, head_y))) =
+            (svg_position(*tail), svg_position(*head))
+        else {
+            continue;
+        };
+        sides
+            .entry((*tail, head_y < tail_y))
+            .or_default()
+            .push((head_x, edge_index, true));
+        sides
+            .entry((*head, tail_y < head_y))
+            .or_default()
+            .push((tail_x, edge_index, false));
+    }
+
+    let mut ports = vec![(None, None); edges.len()];
+    for ((node, top), mut attached) in sides {
+        attached.sort();
+        let (x, y) = svg_position(node).unwrap();
+        let y = if top { y - radius } else { y + radius };
+        let count = attached.len() as isize;
+        for (i, (_, edge_index, is_tail)) in attached.into_iter().enumerate() {
+            let x = x - radius + (i as isize + 1) * 2 * radius / (count + 1);
+            if is_tail {
+                ports[edge_index].0 = Some((x, y));
+            } else {
+                ports[edge_index].1 = Some((x, y));
+            }
+        }
+    }
+
+    ports
+        .into_iter()
+        .map(|(start, end)| start.zip(end))
+        .collect()
+}
+
+/// Bundle the edges with force directed edge bundling, as described by Holten and van Wijk.
+///
+/// Each edge is divided into segments by [EDGE_BUNDLING_SUBDIVISIONS] control points. In every
+/// iteration, each control point is pulled towards the corresponding control points of the
+/// compatible edges, i.e. edges of similar length and direction, which are close to it, while a
+/// spring force towards its neighboring points keeps the edge smooth.
+/// Returns the points of each edge, including its endpoints, or `None` for edges without ports.
+fn bundle_edges(ports: &[Option<Segment>], iterations: usize) -> Vec<Option<Vec<(isize, isize)>>> {
+    let segments = ports.iter().flatten().collect::<Vec<_>>();
+    let vector = |((x1, y1), (x2, y2)): &Segment| ((x2 - x1) as f64, (y2 - y1) as f64);
+    let midpoint = |((x1, y1), (x2, y2)): &Segment| ((x1 + x2) as f64 / 2., (y1 + y2) as f64 / 2.);
+
+    // the compatible edges of each edge, with their compatibility
+    let compatible = segments
+        .iter()
+        .map(|p| {
+            let (px, py) = vector(p);
+            let p_length = px.hypot(py);
+            segments
+                .iter()
+                .enumerate()
+                .filter_map(|(j, q)| {
+                    let (qx, qy) = vector(q);
+                    let q_length = qx.hypot(qy);
+                    if p == q || p_length == 0. || q_length == 0. {
+                        return None;
+                    }
+                    let average_length = (p_length + q_length) / 2.;
+                    let angle = ((px * qx + py * qy) / (p_length * q_length)).abs();
+                    let scale = 2.
+                        / (average_length / p_length.min(q_length)
+                            + p_length.max(q_length) / average_length);
+                    let ((p_mid_x, p_mid_y), (q_mid_x, q_mid_y)) = (midpoint(p), midpoint(q));
+                    let position = average_length
+                        / (average_length + (p_mid_x - q_mid_x).hypot(p_mid_y - q_mid_y));
+                    let compatibility = angle * scale * position;
+                    (compatibility >= EDGE_COMPATIBILITY_THRESHOLD).then_some((j, compatibility))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut points = segments
+        .iter()
+        .map(|((x1, y1), (x2, y2))| {
+            (0..EDGE_BUNDLING_SUBDIVISIONS + 2)
+                .map(|i| {
+                    let t = i as f64 / (EDGE_BUNDLING_SUBDIVISIONS + 1) as f64;
+                    (
+                        *x1 as f64 + (x2 - x1) as f64 * t,
+                        *y1 as f64 + (y2 - y1) as f64 * t,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..iterations {
+        let previous = points.clone();
+        for (edge, compatible) in compatible.iter().enumerate() {
+            let total = compatible.iter().map(|(_, c)| c).sum::<f64>();
+            // the endpoints stay where they are
+            for i in 1..=EDGE_BUNDLING_SUBDIVISIONS {
+                let (x, y) = previous[edge][i];
+                let (before, after) = (previous[edge][i - 1], previous[edge][i + 1]);
+                let mut force = (
+                    EDGE_BUNDLING_STIFFNESS * ((before.0 + after.0) / 2. - x),
+                    EDGE_BUNDLING_STIFFNESS * ((before.1 + after.1) / 2. - y),
+                );
+                if total > 0. {
+                    for (other, compatibility) in compatible {
+                        let (other_x, other_y) = previous[*other][i];
+                        force.0 += compatibility / total * (other_x - x);
+                        force.1 += compatibility / total * (other_y - y);
+                    }
+                }
+                points[edge][i] = (x + force.0 / 2., y + force.1 / 2.);
+            }
+        }
+    }
+
+    let mut points = points.into_iter();
+    ports
+        .iter()
+        .map(|port| {
+            port.map(|_| {
+                points
+                    .next()
+                    .unwrap()
+                    .into_iter()
+                    .map(|(x, y)| (x.round() as isize, y.round() as isize))
+                    .collect()
+            })
+        })
+        .collect()
+}
+
+/// Calculate the center of the label of each edge, in svg coordinates.
+///
+/// A label is moved away from the middle of its edge, perpendicular to it, by multiples of half
+/// the node size. Of these candidates, the first one whose bounding box overlaps the fewest node
+/// boxes is chosen, alternating between both sides of the edge and starting with the closest.
+/// Returns `None` for edges without ports.
+fn place_edge_labels(
+    layout: &NodePositions,
+    ports: &[Option<Segment>],
+    node_size: isize,
+    labels: &[String],
+) -> Vec<Option<(isize, isize)>> {
+    let radius = node_size / 2;
+    let node_boxes = layout
+        .values()
+        .map(|(x, y)| (x - radius, -y - radius, x + radius, -y + radius))
+        .collect::<Vec<_>>();
+    let overlapping_nodes = |(x, y): (isize, isize), half_width: isize| {
+        let half_height = LABEL_FONT_SIZE / 2;
+        node_boxes
+            .iter()
+            .filter(|(left, top, right, bottom)| {
+                x - half_width < *right
+                    && x + half_width > *left
+                    && y - half_height < *bottom
+                    && y + half_height > *top
+            })
+            .count()
+    };
+
+    ports
+        .iter()
+        .zip(labels)
+        .map(|(port, label)| {
+            let ((x1, y1), (x2, y2)) = (*port)?;
+            let (mid_x, mid_y) = ((x1 + x2) as f64 / 2., (y1 + y2) as f64 / 2.);
+            let length = (((x2 - x1).pow(2) + (y2 - y1).pow(2)) as f64)
+                .sqrt()
+                .max(1.);
+            let (normal_x, normal_y) = (-(y2 - y1) as f64 / length, (x2 - x1) as f64 / length);
+            let half_width = label.chars().count() as isize * LABEL_CHAR_WIDTH / 2;
+
+            [1., -1., 2., -2., 3., -3.]
+                .into_iter()
+                .map(|factor| {
+                    let offset = factor * radius as f64;
+                    (
+                        (mid_x + normal_x * offset).round() as isize,
+                        (mid_y + normal_y * offset).round() as isize,
+                    )
+                })
+                .min_by_key(|position| overlapping_nodes(*position, half_width))
+        })
+        .collect()
+}
+
+/// Calculate the bounds of the layout in svg coordinates, with a margin of one node size.
+fn svg_bounds(layout: &NodePositions, node_size: isize) -> (isize, isize, isize, isize) {
+    let xs = layout.values().map(|(x, _)| *x);
+    let ys = layout.values().map(|(_, y)| -*y);
+    let min_x = xs.clone().min().unwrap_or(0) - node_size;
+    let max_x = xs.max().unwrap_or(0) + node_size;
+    let min_y = ys.clone().min().unwrap_or(0) - node_size;
+    let max_y = ys.max().unwrap_or(0) + node_size;
+    (min_x, min_y, max_x, max_y)
+}
+
+/// Assign each weakly connected component a color and return the color of each node as rgb.
+///
+/// The components are ordered by their smallest id. The hue of the `k`-th component is `k` times
+/// the golden ratio, so consecutive components get clearly different colors and the colors don't
+/// change between runs. Nodes which are only part of `edges` are colored as well.
+#[pyfunction]
+pub fn component_colors(nodes: Vec<u32>, edges: Vec<(u32, u32)>) -> HashMap<u32, (u8, u8, u8)> {
+    let mut ids = nodes
+        .into_iter()
+        .chain(edges.iter().flat_map(|(tail, head)| [*tail, *head]))
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+    let index = |id: &u32| ids.binary_search(id).unwrap();
+
+    let mut components = UnionFind::new(ids.len());
+    for (tail, head) in &edges {
+        components.union(index(tail), index(head));
+    }
+    // ids are sorted, so components are numbered by their smallest id
+    let mut component_of_root = HashMap::new();
+    ids.iter()
+        .map(|id| {
+            let root = components.find(index(id));
+            let next = component_of_root.len();
+            let component = *component_of_root.entry(root).or_insert(next);
+            let hue = (component as f64 * COMPONENT_HUE_STEP).fract();
+            (*id, hsv_to_rgb(hue, COMPONENT_SATURATION, COMPONENT_VALUE))
+        })
+        .collect()
+}
+
+/// Convert a color from hsv to rgb, all components of the hsv color are in `0..=1`.
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> (u8, u8, u8) {
+    let sector = hue * 6.;
+    let chroma = value * saturation;
+    let second = chroma * (1. - (sector % 2. - 1.).abs());
+    let (red, green, blue) = match sector as usize {
+        0 => (chroma, second, 0.),
+        1 => (second, chroma, 0.),
+        2 => (0., chroma, second),
+        3 => (0., second, chroma),
+        4 => (second, 0., chroma),
+        _ => (chroma, 0., second),
+    };
+    let channel = |component: f64| ((component + value - chroma) * 255.).round() as u8;
+    (channel(red), channel(green), channel(blue))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::{
+        component_colors, hsv_to_rgb, layout_batch_to_dir, layout_to_svg, to_dot, to_elk_json,
+        to_graphml, to_html, to_svg, to_svg_bundled, to_svg_labeled, to_tikz, LABEL_CHAR_WIDTH,
+        LABEL_FONT_SIZE,
+    };
+    use crate::{
+        graph_layout::GraphLayout, transform::layout_dimensions, NodePositions, SugiyamaConfig,
+    };
+
+    /// Parse the `<line>` elements of an svg into (x1, y1, x2, y2).
+    fn svg_lines(svg: &str) -> Vec<(isize, isize, isize, isize)> {
+        svg.lines()
+            .filter(|line| line.starts_with("<line"))
+            .map(|line| {
+                let coords = line
+                    .split('"')
+                    .skip(1)
+                    .step_by(2)
+                    .map(|n| n.parse::<isize>().unwrap())
+                    .collect::<Vec<_>>();
+                (coords[0], coords[1], coords[2], coords[3])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn to_svg_incoming_edges_attach_at_distinct_ports() {
+        let layout =
+            NodePositions::from([(1, (0, 0)), (2, (160, 0)), (3, (320, 0)), (4, (160, -160))]);
+        let edges = vec![(1, 4), (2, 4), (3, 4)];
+
+        let lines = svg_lines(&to_svg(layout, edges, 40));
+
+        assert_eq!(lines.len(), 3);
+        let mut attach_x = lines.iter().map(|(_, _, x2, _)| *x2).collect::<Vec<_>>();
+        attach_x.sort();
+        attach_x.dedup();
+        assert_eq!(attach_x, vec![150, 160, 170]);
+        // all edges attach at the top of node 4
+        assert!(lines.iter().all(|(_, _, _, y2)| *y2 == 140));
+        // and leave their tails at the bottom
+        assert!(lines.iter().all(|(_, y1, _, _)| *y1 == 20));
+    }
+
+    #[test]
+    fn layout_to_svg_upright_and_sized_from_layout() {
+        let nodes = vec![1, 2, 3];
+        let (layouts, _, _) =
+            crate::create_layouts_original(nodes.clone(), vec![(1, 2), (1, 3)], 40, false).unwrap();
+        let positions = layouts[0].clone();
+        let edges = vec![(1, 2), (1, 3), (3, 4)];
+
+        let svg = layout_to_svg(nodes, edges.clone(), positions.clone(), 40).unwrap();
+
+        let (width, height) = layout_dimensions(positions.clone(), 40);
+        assert!(svg.starts_with(&format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}""#,
+            width + 40,
+            height + 40
+        )));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        // the edge to node 4 is skipped, the others point downwards
+        let lines = svg_lines(&svg);
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|(_, y1, _, y2)| y1 < y2));
+
+        assert!(layout_to_svg(vec![1, 5], edges, positions, 40).is_err());
+    }
+
+    #[test]
+    fn layout_batch_to_dir_writes_one_file_per_component() {
+        let dir = env::temp_dir().join(format!("rs_graph_layout_batch_{}", std::process::id()));
+        let graphs = vec![
+            (vec![1, 2, 3], vec![(1, 2), (2, 3)]),
+            (vec![1, 2, 3, 4], vec![(1, 2), (3, 4)]),
+        ];
+
+        let paths = layout_batch_to_dir(graphs, dir.clone(), SugiyamaConfig::default()).unwrap();
+
+        assert_eq!(paths.len(), 3);
+        for path in &paths {
+            let svg = fs::read_to_string(path).unwrap();
+            assert!(svg.starts_with("<svg"));
+            assert!(svg.contains("<circle"));
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn render_png_dimensions_match_bounds() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (160, -160)), (3, (320, -160))]);
+        let edges = vec![(1, 2), (1, 3)];
+
+        let png = super::render_png(layout.clone(), edges, 40);
+
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        let (min_x, min_y, max_x, max_y) = super::svg_bounds(&layout, 40);
+        assert_eq!(reader.info().width, (max_x - min_x) as u32);
+        assert_eq!(reader.info().height, (max_y - min_y) as u32);
+        assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+    }
+
+    #[test]
+    fn to_html_embeds_svg() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-80, -160)), (3, (80, -160))]);
+        let edges = vec![(1, 2), (1, 3)];
+
+        let html = to_html(layout.clone(), edges.clone(), 40);
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(&to_svg(layout, edges, 40)));
+        assert_eq!(html.matches("<svg").count(), 1);
+        assert_eq!(html.matches("<circle").count(), 3);
+        assert_eq!(svg_lines(&html).len(), 2);
+        assert!(html.contains("<script>"));
+    }
+
+    #[test]
+    fn to_tikz_one_command_per_node_and_edge() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-80, -160)), (3, (80, -160))]);
+        // 4 is not part of the layout
+        let edges = vec![(1, 2), (1, 3), (3, 4)];
+
+        let tikz = to_tikz(layout, edges, 40);
+
+        assert!(tikz.starts_with("\\begin{tikzpicture}"));
+        assert!(tikz.ends_with("\\end{tikzpicture}\n"));
+        assert_eq!(tikz.matches("\\node").count(), 3);
+        assert_eq!(tikz.matches("\\draw").count(), 2);
+        assert!(tikz.contains("\\node (n2) at (-1.00, -2.00) {2};"));
+        assert!(tikz.contains("\\draw[->] (n1) -- (n3);"));
+        assert_eq!(tikz.matches('{').count(), tikz.matches('}').count());
+        assert_eq!(tikz.matches('(').count(), tikz.matches(')').count());
+    }
+
+    #[test]
+    fn to_dot_keeps_labels_of_parallel_edges() {
+        let nodes = vec![1, 2, 3];
+        let edges = vec![(1, 2), (1, 2), (2, 3)];
+        let labels = vec![
+            "send".to_string(),
+            "ack".to_string(),
+            "\"done\"".to_string(),
+        ];
+        let (layouts, _, _) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        // the layout treats the parallel edges as one
+        assert_eq!(
+            layouts,
+            GraphLayout::create_layers(&nodes, &[(1, 2), (2, 3)], 40, false)
+                .unwrap()
+                .0
+        );
+
+        let dot = to_dot(layouts[0].clone(), edges, Some(labels)).unwrap();
+
+        assert!(dot.starts_with("digraph {"));
+        assert_eq!(dot.matches("pos=").count(), 3);
+        assert!(dot.contains("1 -> 2 [label=\"send\"];"));
+        assert!(dot.contains("1 -> 2 [label=\"ack\"];"));
+        assert!(dot.contains("2 -> 3 [label=\"\\\"done\\\"\"];"));
+        assert!(to_dot(layouts[0].clone(), vec![(1, 2)], Some(vec![])).is_err());
+    }
+
+    #[test]
+    fn to_elk_json_nodes_have_size_and_position() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-80, -160)), (3, (80, -160))]);
+        let edges = vec![(1, 2), (1, 3)];
+
+        let json = to_elk_json(layout, edges, 40);
+
+        let elk = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(elk["id"], "root");
+        let children = elk["children"].as_array().unwrap();
+        assert_eq!(children.len(), 3);
+        for child in children {
+            assert!(child["id"].is_string());
+            assert!(child["x"].as_i64().unwrap() >= 0);
+            assert!(child["y"].as_i64().unwrap() >= 0);
+            assert_eq!(child["width"], 40);
+            assert_eq!(child["height"], 40);
+        }
+        // node 1 is at the top center
+        assert_eq!(
+            (&children[0]["x"], &children[0]["y"]),
+            (&80.into(), &0.into())
+        );
+        assert_eq!((&elk["width"], &elk["height"]), (&200.into(), &200.into()));
+        let edges = elk["edges"].as_array().unwrap();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[1]["sources"], serde_json::json!(["1"]));
+        assert_eq!(edges[1]["targets"], serde_json::json!(["3"]));
+    }
+
+    #[test]
+    fn to_svg_labeled_moves_labels_off_nodes() {
+        // the middle of the edge from 1 to 3 lies on node 2
+        let layout = NodePositions::from([(1, (0, 0)), (2, (0, -160)), (3, (0, -320))]);
+        let edges = vec![(1, 2), (2, 3), (1, 3)];
+        let labels = vec!["a".to_string(), "b".to_string(), "long".to_string()];
+
+        let svg = to_svg_labeled(layout, edges, 40, labels).unwrap();
+
+        let texts = svg
+            .lines()
+            .filter(|line| line.starts_with("<text"))
+            .map(|line| {
+                let parts = line.split('"').collect::<Vec<_>>();
+                let x = parts[1].parse::<isize>().unwrap();
+                let y = parts[3].parse::<isize>().unwrap();
+                let label = parts[4].trim_start_matches('>').trim_end_matches("</text>");
+                (label.to_string(), x, y)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(texts.len(), 3);
+        let (_, x, y) = texts.iter().find(|(label, ..)| label == "long").unwrap();
+        // placing the label at the middle of its edge would overlap node 2
+        assert_eq!(y, &160);
+        let half_width = 4 * LABEL_CHAR_WIDTH / 2;
+        for (label, x, y) in &texts {
+            let half_width = label.len() as isize * LABEL_CHAR_WIDTH / 2;
+            for node_y in [0, 160, 320] {
+                let overlaps = (x - half_width < 20 && x + half_width > -20)
+                    && (y - LABEL_FONT_SIZE / 2 < node_y + 20
+                        && y + LABEL_FONT_SIZE / 2 > node_y - 20);
+                assert!(!overlaps, "label {label} at ({x}, {y}) overlaps a node");
+            }
+        }
+        assert!(x.abs() - half_width >= 20);
+        assert!(to_svg_labeled(NodePositions::new(), vec![(1, 2)], 40, vec![]).is_err());
+    }
+
+    #[test]
+    fn to_svg_bundled_edges_share_control_points() {
+        // a complete bipartite graph between two rows of 4 nodes
+        let layout = (0..4)
+            .flat_map(|i| {
+                [
+                    (i + 1, (i as isize * 160, 0)),
+                    (i + 5, (i as isize * 160, -640)),
+                ]
+            })
+            .collect::<NodePositions>();
+        let edges = (1..=4)
+            .flat_map(|tail| (5..=8).map(move |head| (tail, head)))
+            .collect::<Vec<_>>();
+
+        let lines = svg_lines(&to_svg(layout.clone(), edges.clone(), 40));
+        let mut middles = lines
+            .iter()
+            .map(|(x1, y1, x2, y2)| ((x1 + x2) / 2, (y1 + y2) / 2))
+            .collect::<Vec<_>>();
+        middles.sort();
+        middles.dedup();
+        // only symmetric lines share their middle
+        assert_eq!(middles.len(), 7);
+
+        let svg = to_svg_bundled(layout, edges.clone(), 40, 30);
+        let paths = svg
+            .lines()
+            .filter(|line| line.starts_with("<polyline"))
+            .map(|line| {
+                line.split('"')
+                    .nth(1)
+                    .unwrap()
+                    .split(' ')
+                    .map(|point| {
+                        let (x, y) = point.split_once(',').unwrap();
+                        (x.parse::<isize>().unwrap(), y.parse::<isize>().unwrap())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), edges.len());
+        // the paths keep their endpoints
+        for (path, line) in paths.iter().zip(&lines) {
+            assert_eq!(path.first(), Some(&(line.0, line.1)));
+            assert_eq!(path.last(), Some(&(line.2, line.3)));
+        }
+        // but they all run through the center of the layout
+        let mut middles = paths
+            .iter()
+            .map(|path| path[path.len() / 2])
+            .collect::<Vec<_>>();
+        middles.sort();
+        middles.dedup();
+        assert_eq!(middles, vec![(240, 320)]);
+    }
+
+    #[test]
+    fn component_colors_shared_within_distinct_between_components() {
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = vec![(1, 2), (2, 3), (4, 5), (6, 7), (7, 8), (6, 8), (10, 11)];
+        let components = [
+            vec![1, 2, 3],
+            vec![4, 5],
+            vec![6, 7, 8],
+            vec![9],
+            vec![10, 11],
+            vec![12],
+        ];
+
+        let colors = component_colors(nodes.clone(), edges.clone());
+
+        assert_eq!(colors.len(), nodes.len());
+        let mut component_colors_seen = Vec::new();
+        for component in &components {
+            let color = colors[&component[0]];
+            assert!(component.iter().all(|id| colors[id] == color));
+            assert!(!component_colors_seen.contains(&color));
+            component_colors_seen.push(color);
+        }
+        assert_eq!(component_colors(nodes, edges), colors);
+        assert_eq!(hsv_to_rgb(0., 1., 1.), (255, 0, 0));
+        assert_eq!(hsv_to_rgb(2. / 3., 1., 1.), (0, 0, 255));
+    }
+
+    #[test]
+    fn to_graphml_declares_keys_and_positions() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-80, -160)), (3, (80, -160))]);
+        let edges = vec![(1, 2), (1, 3), (1, 3), (3, 4)];
+
+        let graphml = to_graphml(layout, edges);
+
+        // every element is closed in the order it was opened
+        let mut open = Vec::new();
+        for tag in graphml
+            .split('<')
+            .skip(1)
+            .map(|tag| tag.split('>').next().unwrap())
+        {
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name));
+            } else if !tag.starts_with('?') && !tag.ends_with('/') {
+                open.push(tag.split(' ').next().unwrap());
+            }
+        }
+        assert!(open.is_empty());
+        assert!(graphml.contains(r#"<key id="x" for="node" attr.name="x" attr.type="int"/>"#));
+        assert!(graphml.contains(r#"<key id="y" for="node" attr.name="y" attr.type="int"/>"#));
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert_eq!(graphml.matches("<edge ").count(), 3);
+        assert!(graphml
+            .contains(r#"<node id="n2"><data key="x">-80</data><data key="y">-160</data></node>"#));
+        assert!(graphml.contains(r#"<edge source="n1" target="n3"/>"#));
+    }
+}
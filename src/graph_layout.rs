@@ -29,39 +29,141 @@ ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
 SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 */
 use std::{
+    borrow::Cow,
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    rc::Rc,
 };
 
 use petgraph::{
-    algo::toposort,
+    algo::{
+        condensation, tarjan_scc, toposort,
+        tred::{dag_to_toposorted_adjacency_list, dag_transitive_reduction_closure},
+    },
+    graph::DiGraph,
     stable_graph::{NodeIndex, StableDiGraph},
-    visit::IntoNodeIdentifiers,
+    visit::{EdgeRef, IntoEdgeReferences, IntoNodeIdentifiers, NodeIndexable},
     Direction,
 };
+use pyo3::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use super::NodePositions;
+use super::{
+    analysis::layout_score, create_layouts_sugiyama, error::LayoutError, storage::NodeMap,
+    transform::snap_layout, EdgeListGraph, LayoutOptions, Layouts, NodePositions, SugiyamaConfig,
+};
+
+/// The order in which nodes are moved between levels, after they have been assigned an initial
+/// level.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RankPassOrder {
+    /// Move the nodes as far up as possible, then as far down as possible.
+    #[default]
+    UpThenDown,
+    /// Move the nodes as far down as possible, then as far up as possible.
+    DownThenUp,
+    /// Place each node in the middle of the levels it can be placed in. This reduces the total
+    /// length of the edges, if nodes have neighbors on both sides.
+    Balanced,
+}
+
+/// Where to place nodes, which don't have any neighbors in the levels directly above or below
+/// them. Crossing reduction doesn't move these nodes, since they are not connected to the
+/// adjacent levels.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatingPlacement {
+    /// Leave the nodes where they are.
+    #[default]
+    Keep,
+    /// Move the nodes to the end of the level with the fewest nodes, among the levels they can be
+    /// placed in without reversing an edge.
+    EndOfShortestLevel,
+    /// Keep the nodes in their level, but move them to the free slot closest to the center of all
+    /// other nodes.
+    Centroid,
+}
+
+/// The method used to lay out a component, see [GraphLayout::create_layers_mixed].
+#[pyclass]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMethod {
+    /// The original method of Temanejo.
+    Original,
+    /// Sugiyama's method, see [crate::create_layouts_sugiyama].
+    Sugiyama,
+}
+
+/// The heuristic used to order the nodes of a level by their neighbors in the level before it, see
+/// [sweep_levels].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelOrdering {
+    /// Order the nodes by the mean index of their neighbors.
+    Barycenter,
+    /// Order the nodes by the median index of their neighbors.
+    Median,
+    /// Compute both orderings and keep the one with fewer crossings with the level before.
+    Adaptive,
+}
+
+/// Positions of the nodes of a StableDiGraph, together with their payload.
+pub type PayloadPositions<'a, N> = HashMap<NodeIndex, ((isize, isize), &'a N)>;
+
+/// The number of nodes and the edges of a component, see [GraphLayout::component_shape].
+type ComponentShape = (usize, Vec<(usize, usize)>);
+
+/// Locked levels and order constraints parsed from external hints, see
+/// [GraphLayout::parse_external_hints].
+pub type ExternalHints = (HashMap<u32, usize>, Vec<(u32, u32)>);
+
+/// The bound of the node indices a [LayoutState] may use, so a malformed state can't allocate an
+/// arbitrarily large graph. Components keep the node indices of the whole graph, so the bound
+/// can't be derived from the nodes of the state alone.
+#[cfg(feature = "serde")]
+const MAX_STATE_NODE_BOUND: usize = 1 << 20;
+
+/// A snapshot of an aligned [GraphLayout], see [GraphLayout::save_state].
+///
+/// Nodes are stored by their index, the levels and indices of the nodes are stored as pairs of
+/// node and value.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct LayoutState {
+    nodes: Vec<usize>,
+    edges: Vec<(usize, usize)>,
+    layers: Vec<Vec<Option<usize>>>,
+    level_of_node: Vec<(usize, usize)>,
+    index_of_node: Vec<(usize, usize)>,
+    node_size: isize,
+    global_tasks_in_first_row: bool,
+}
 
 /// Represents a layout of a graph.
 /// The nodes of the graph are arranged in layers.
 ///
 /// The fields are:
-///     - graph: the actual graph
+///     - graph: the actual graph, which never contains a cycle
 ///     - layers: the layers containing the nodes of the graph
 ///     - level_of_node: the current level of a node
 ///     - ndex_of_node: the index of a node in its level
 ///     - node_size: the size of a node when drawn in pixel
-///     - node_separation: the minimum separation of two nodes
+///     - node_separation: the minimum separation of two nodes, `node_size` times
+///       [LayoutOptions::separation_factor]
 ///     - global_tasks_in_first_row: boolean, indicating if global tasks need to be put in the first row  
+///     - options: additional options, see [LayoutOptions]
 #[derive(Debug)]
 pub struct GraphLayout {
     graph: StableDiGraph<(), ()>,
     layers: RefCell<Vec<Vec<Option<NodeIndex>>>>,
-    level_of_node: RefCell<HashMap<NodeIndex, usize>>,
-    index_of_node: RefCell<HashMap<NodeIndex, usize>>,
-    _node_size: isize,
+    level_of_node: RefCell<NodeMap>,
+    index_of_node: RefCell<NodeMap>,
+    node_size: isize,
     node_separation: isize,
     global_tasks_in_first_row: bool,
+    options: Rc<LayoutOptions>,
 }
 
 impl GraphLayout {
@@ -70,525 +172,4586 @@ impl GraphLayout {
     /// A layout contains the position of each node (HashMap of NodeIndex and (x, y)) the height of the layout and the maximum width of the layers.
     /// The layout is created by arranging the nodes of the graph in level and performing some operations them in order to produce a visualization
     /// of the graph.
+    /// If `nodes` is empty, the nodes are inferred from the edges, i.e. all ids up to the largest
+    /// one contained in an edge are used.
+    /// Returns an error naming one of the cycles, if the graph contains any, see
+    /// [GraphLayout::check_acyclic].
     pub fn create_layers(
         nodes: &[u32],
         edges: &[(u32, u32)],
         node_size: isize,
         global_tasks_in_first_row: bool,
-    ) -> (Vec<NodePositions>, Vec<usize>, Vec<usize>) {
-        let mut layout_list = Vec::new();
-        let mut width_list = Vec::new();
-        let mut height_list = Vec::new();
-        let mut graph = StableDiGraph::<(), ()>::new();
+    ) -> Result<Layouts, LayoutError> {
+        Self::create_layers_with_options(
+            nodes,
+            edges,
+            node_size,
+            global_tasks_in_first_row,
+            LayoutOptions::default(),
+        )
+    }
 
-        for _ in nodes {
-            graph.add_node(());
+    /// Create the layouts for each weakly connected component contained in edges.
+    ///
+    /// Works like [GraphLayout::create_layers], with additional options to control the layout.
+    /// See [LayoutOptions] for details.
+    pub fn create_layers_with_options(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        options: LayoutOptions,
+    ) -> Result<Layouts, LayoutError> {
+        if !options.invisible_edges.is_empty() {
+            let edges = [edges, &options.invisible_edges].concat();
+            let options = LayoutOptions {
+                invisible_edges: Vec::new(),
+                ..options
+            };
+            return Self::create_layers_with_options(
+                nodes,
+                &edges,
+                node_size,
+                global_tasks_in_first_row,
+                options,
+            );
         }
-
-        for (predecessor, successor) in edges {
-            // networkx graph is 1 indexed
-            graph.add_edge(
-                NodeIndex::from(*predecessor - 1),
-                NodeIndex::from(*successor - 1),
-                (),
+        if options.dedup_components {
+            return Self::create_layers_deduplicated(
+                nodes,
+                edges,
+                node_size,
+                global_tasks_in_first_row,
+                options,
             );
         }
+        Ok(Self::build_layouts(Self::align_components(
+            nodes,
+            edges,
+            node_size,
+            global_tasks_in_first_row,
+            options,
+        )?))
+    }
 
-        let mut graphs = Self::into_weakly_connected_components(graph)
-            .into_iter()
-            .map(|subgraph| Self::new(subgraph, node_size, global_tasks_in_first_row))
-            .collect::<Vec<_>>();
+    /// Create the layouts like [GraphLayout::create_layers_with_options], but only lay out one
+    /// component of each shape, see [LayoutOptions::dedup_components].
+    ///
+    /// Components which contain nodes referred to by other options, like locked or seeded nodes,
+    /// are always laid out on their own.
+    fn create_layers_deduplicated(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        options: LayoutOptions,
+    ) -> Result<Layouts, LayoutError> {
+        let graphs =
+            Self::split_components(nodes, edges, node_size, global_tasks_in_first_row, options)?;
+        // the index of the layout of each shape, together with the nodes it was created for
+        let mut representatives = HashMap::<ComponentShape, (usize, Vec<NodeIndex>)>::new();
+        let (mut layouts, mut widths, mut heights): Layouts = (Vec::new(), Vec::new(), Vec::new());
+
+        for graph in graphs {
+            let (component_nodes, shape) = graph.component_shape();
+            if let Some((representative, representative_nodes)) = representatives.get(&shape) {
+                let layout = component_nodes
+                    .iter()
+                    .zip(representative_nodes)
+                    .map(|(node, representative_node)| {
+                        // increment index by one for networkx
+                        let position = layouts[*representative][&(representative_node.index() + 1)];
+                        (node.index() + 1, position)
+                    })
+                    .collect::<NodePositions>();
+                widths.push(widths[*representative]);
+                heights.push(heights[*representative]);
+                layouts.push(layout);
+                continue;
+            }
 
-        for graph in graphs.iter_mut() {
             if graph.graph.edge_count() != 0 {
                 graph.align_nodes();
             }
+            let (layout, width, height) = graph.build_layout();
+            if !graph.is_referenced_by_options() {
+                representatives.insert(shape, (layouts.len(), component_nodes));
+            }
+            layouts.push(layout);
+            widths.push(width);
+            heights.push(height);
         }
+        Ok((layouts, widths, heights))
+    }
 
-        for (node_positions, width, height) in graphs.into_iter().map(|graph| graph.build_layout())
-        {
-            layout_list.push(node_positions);
-            width_list.push(width);
-            height_list.push(height);
+    /// Create the layouts for the weakly connected components with at least `min_component_size`
+    /// nodes, collapsing the smaller ones.
+    ///
+    /// Works like [GraphLayout::create_layers_with_options], but instead of laying out the small
+    /// components, returns their ids, so they can be represented by a single entry, e.g. in a
+    /// legend. Each collapsed component is sorted, the components are ordered by their smallest
+    /// id. Components are never deduplicated, see [LayoutOptions::dedup_components].
+    pub fn create_layers_collapsed(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        min_component_size: usize,
+        options: LayoutOptions,
+    ) -> Result<(Layouts, Vec<Vec<u32>>), LayoutError> {
+        let edges = [edges, &options.invisible_edges].concat();
+        let options = LayoutOptions {
+            invisible_edges: Vec::new(),
+            ..options
+        };
+        let (mut large, small): (Vec<_>, Vec<_>) =
+            Self::split_components(nodes, &edges, node_size, global_tasks_in_first_row, options)?
+                .into_iter()
+                .partition(|graph| graph.graph.node_count() >= min_component_size);
+
+        for graph in large.iter_mut() {
+            if graph.graph.edge_count() != 0 {
+                graph.align_nodes();
+            }
         }
+        let mut collapsed = small
+            .iter()
+            .map(|graph| {
+                let mut ids = graph
+                    .graph
+                    .node_indices()
+                    // increment index by one for networkx
+                    .map(|node| node.index() as u32 + 1)
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        collapsed.sort_unstable();
 
-        (layout_list, width_list, height_list)
+        Ok((Self::build_layouts(large), collapsed))
     }
 
-    fn build_layout_no_edges(&self) -> (NodePositions, usize, usize) {
-        let node = self.graph.node_indices().next().unwrap();
-        // increment node index by one for networkx
-        (
-            HashMap::from([(node.index() + 1, (self.node_separation, 0))]),
-            1,
-            1,
-        )
-    }
+    /// Create the layouts for the `n` largest weakly connected components, dropping the others.
+    ///
+    /// Components are compared by their number of nodes, ties are broken by the smallest id of a
+    /// component, so the result is deterministic. The kept components are laid out like
+    /// [GraphLayout::create_layers] and keep their order. Returns the layouts, together with the
+    /// ids of the dropped components, which are sorted and ordered by their smallest id.
+    pub fn create_layers_top_n(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        n: usize,
+        node_size: isize,
+    ) -> Result<(Layouts, Vec<Vec<u32>>), LayoutError> {
+        let graphs = Self::split_components(nodes, edges, node_size, false, Default::default())?;
+        let ids = graphs
+            .iter()
+            .map(|graph| {
+                let mut ids = graph
+                    .graph
+                    .node_indices()
+                    // increment index by one for networkx
+                    .map(|node| node.index() as u32 + 1)
+                    .collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        let mut ranking = (0..graphs.len()).collect::<Vec<_>>();
+        ranking.sort_by_key(|&i| (Reverse(ids[i].len()), ids[i].first().copied()));
+        let kept = ranking.into_iter().take(n).collect::<HashSet<_>>();
 
-    fn build_layout(&self) -> (NodePositions, usize, usize) {
-        if self.graph.edge_count() == 0 {
-            return self.build_layout_no_edges();
+        let mut top = Vec::new();
+        let mut dropped = Vec::new();
+        for (i, (graph, ids)) in graphs.into_iter().zip(ids).enumerate() {
+            if kept.contains(&i) {
+                if graph.graph.edge_count() != 0 {
+                    graph.align_nodes();
+                }
+                top.push(graph);
+            } else {
+                dropped.push(ids);
+            }
         }
-        let mut node_positions = HashMap::new();
-        let offset = if self.layers.borrow()[0].iter().all(|n| n.is_none()) {
-            1
-        } else {
-            0
+        dropped.sort_unstable();
+
+        Ok((Self::build_layouts(top), dropped))
+    }
+
+    /// Create the layouts for each weakly connected component, choosing the method by the size of
+    /// the component.
+    ///
+    /// Components with at least `sugiyama_threshold` nodes are laid out with Sugiyama's method
+    /// and `config`, e.g. because it produces fewer crossings for large components. The others are
+    /// laid out like [GraphLayout::create_layers_with_options]. Returns the layouts, together with
+    /// the method used for each of them. Components are never deduplicated, see
+    /// [LayoutOptions::dedup_components].
+    pub fn create_layers_mixed(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        sugiyama_threshold: usize,
+        config: SugiyamaConfig,
+        options: LayoutOptions,
+    ) -> Result<(Layouts, Vec<LayoutMethod>), LayoutError> {
+        let edges = [edges, &options.invisible_edges].concat();
+        let options = LayoutOptions {
+            invisible_edges: Vec::new(),
+            ..options
         };
+        let (mut layouts, mut widths, mut heights): Layouts = (Vec::new(), Vec::new(), Vec::new());
+        let mut methods = Vec::new();
 
-        for (level_index, level) in self.layers.borrow().iter().enumerate() {
-            for (node_index, node_opt) in level.iter().enumerate() {
-                let node = if let Some(node) = node_opt {
-                    *node
-                } else {
-                    continue;
-                };
-                let x = node_index as isize * self.node_separation;
-                let y = (-(level_index as isize) + offset) * self.node_separation;
-                node_positions.insert(node.index() + 1, (x, y)); // increment index by one for networkx
-            }
+        for graph in
+            Self::split_components(nodes, &edges, node_size, global_tasks_in_first_row, options)?
+        {
+            let (layout, width, height) = if graph.graph.node_count() >= sugiyama_threshold {
+                methods.push(LayoutMethod::Sugiyama);
+                // increment index by one for networkx
+                let component_nodes = graph
+                    .graph
+                    .node_indices()
+                    .map(|node| node.index() as u32 + 1)
+                    .collect();
+                let component_edges = graph
+                    .graph
+                    .edge_references()
+                    .map(|edge| {
+                        (
+                            edge.source().index() as u32 + 1,
+                            edge.target().index() as u32 + 1,
+                        )
+                    })
+                    .collect();
+                let (mut layouts, widths, heights) =
+                    create_layouts_sugiyama(component_nodes, component_edges, config.clone());
+                (layouts.remove(0), widths[0], heights[0])
+            } else {
+                methods.push(LayoutMethod::Original);
+                if graph.graph.edge_count() != 0 {
+                    graph.align_nodes();
+                }
+                graph.build_layout()
+            };
+            layouts.push(layout);
+            widths.push(width);
+            heights.push(height);
         }
-        (node_positions, self.get_width(), self.get_nums_of_level())
+        Ok(((layouts, widths, heights), methods))
     }
 
-    /// Takes a graph and breaks it down into its weakly connected components.
-    /// A weakly connected component is a list of edges which are connected with each other.
-    fn into_weakly_connected_components(
-        graph: StableDiGraph<(), ()>,
-    ) -> Vec<StableDiGraph<(), ()>> {
-        let mut components = Vec::new();
-        let mut visited = HashSet::new();
+    /// Create the layouts for each weakly connected component, given which nodes can be reached
+    /// from each other instead of the edges.
+    ///
+    /// `reachable` contains a pair `(a, b)` if `b` can be reached from `a`. The edges are
+    /// recovered with [GraphLayout::transitive_reduction] and laid out like
+    /// [GraphLayout::create_layers]. Returns an error if the reachability contains a cycle.
+    pub fn create_layers_from_reachability(
+        nodes: &[u32],
+        reachable: &[(u32, u32)],
+        node_size: isize,
+    ) -> Result<Layouts, LayoutError> {
+        let edges = Self::transitive_reduction(nodes, reachable)?;
+        Self::create_layers(nodes, &edges, node_size, false)
+    }
 
-        for node in graph.node_indices() {
-            if visited.contains(&node) {
-                continue;
-            }
+    /// Returns the sorted edges of the transitive reduction, i.e. the pairs of `reachable` which
+    /// are not implied by other pairs.
+    ///
+    /// For the full reachability of a graph without cycles, these are the direct edges of the
+    /// graph, as long as it has no shortcuts, i.e. edges parallel to a longer path. Pairs of a node
+    /// with itself are ignored. Returns an error if the pairs contain a cycle.
+    pub fn transitive_reduction(
+        nodes: &[u32],
+        reachable: &[(u32, u32)],
+    ) -> Result<Vec<(u32, u32)>, LayoutError> {
+        let mut graph = DiGraph::<(), ()>::new();
+        for _ in Self::nodes_or_inferred(nodes, reachable).iter() {
+            graph.add_node(());
+        }
+        let mut seen = HashSet::new();
+        for (tail, head) in reachable
+            .iter()
+            .filter(|(tail, head)| tail != head)
+            .filter(|pair| seen.insert(**pair))
+        {
+            // networkx graph is 1 indexed
+            graph.add_edge(NodeIndex::from(*tail - 1), NodeIndex::from(*head - 1), ());
+        }
 
-            let component_nodes = Self::component_dfs(node, &graph);
-            let component = graph.filter_map(
-                |n, _| {
-                    if component_nodes.contains(&n) {
-                        Some(())
-                    } else {
-                        None
-                    }
-                },
-                |_, _| Some(()),
-            );
+        let order = toposort(&graph, None).map_err(|cycle| {
+            LayoutError::InvalidGraph(format!(
+                "Reachability contains a cycle at node {}",
+                cycle.node_id().index() + 1
+            ))
+        })?;
+        let (sorted, _) = dag_to_toposorted_adjacency_list::<_, u32>(&graph, &order);
+        let (reduction, _) = dag_transitive_reduction_closure(&sorted);
 
-            component_nodes.into_iter().for_each(|n| {
-                visited.insert(n);
-            });
-            components.push(component);
+        // increment index by one for networkx
+        let id = |position: u32| order[position as usize].index() as u32 + 1;
+        let mut edges = reduction
+            .edge_references()
+            .map(|edge| (id(edge.source()), id(edge.target())))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        Ok(edges)
+    }
+
+    /// Create the layouts of a graph, which may contain cycles, by condensing each strongly
+    /// connected component into a single super node.
+    ///
+    /// The super nodes are numbered starting at `1` and the layouts are keyed by these ids.
+    /// Returns the layouts of the condensed graph, together with the sorted members of each super
+    /// node, so they can be expanded again. Nodes which are not part of a cycle are the only
+    /// member of their super node. Returns an error if an edge refers to an unknown node or if the
+    /// node size isn't positive.
+    pub fn create_layers_condensed(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+    ) -> Result<(Layouts, HashMap<u32, Vec<u32>>), LayoutError> {
+        Self::check_node_size(node_size)?;
+        let mut graph = DiGraph::<u32, ()>::new();
+        let indices = Self::nodes_or_inferred(nodes, edges)
+            .iter()
+            .map(|id| (*id, graph.add_node(*id)))
+            .collect::<HashMap<_, _>>();
+        let index = |id: &u32| {
+            indices.get(id).copied().ok_or_else(|| {
+                LayoutError::InvalidGraph(format!("Edge refers to unknown node {id}"))
+            })
+        };
+        for (tail, head) in edges {
+            graph.add_edge(index(tail)?, index(head)?, ());
         }
+        let condensed = condensation(graph, true);
 
-        components
+        let mut members = HashMap::new();
+        for super_node in condensed.node_indices() {
+            let mut ids = condensed[super_node].clone();
+            ids.sort();
+            members.insert(super_node.index() as u32 + 1, ids);
+        }
+        let super_nodes = (1..=condensed.node_count() as u32).collect::<Vec<_>>();
+        let super_edges = condensed
+            .edge_references()
+            .map(|edge| {
+                (
+                    edge.source().index() as u32 + 1,
+                    edge.target().index() as u32 + 1,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Ok((
+            Self::create_layers(&super_nodes, &super_edges, node_size, false)?,
+            members,
+        ))
     }
 
-    fn component_dfs(start: NodeIndex, graph: &StableDiGraph<(), ()>) -> HashSet<NodeIndex> {
-        let mut queue = vec![start];
-        let mut visited = HashSet::new();
+    /// Create the layouts of a preview of a graph, which contains at most `max_nodes` nodes.
+    ///
+    /// If the graph has more nodes, a connected sample of it is laid out instead. The sample is
+    /// grown by a breadth first search, ignoring the direction of the edges, starting at the node
+    /// with the highest degree. Returns the layouts together with the sorted ids of the nodes
+    /// they contain, or an error if the sample contains a cycle.
+    pub fn create_layers_sampled(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        max_nodes: usize,
+        node_size: isize,
+    ) -> Result<(Layouts, Vec<u32>), LayoutError> {
+        let nodes = Self::nodes_or_inferred(nodes, edges);
+        if nodes.len() <= max_nodes {
+            let mut included = nodes.to_vec();
+            included.sort();
+            return Ok((
+                Self::create_layers(&nodes, edges, node_size, false)?,
+                included,
+            ));
+        }
 
-        visited.insert(start);
+        let mut neighbors = HashMap::<u32, Vec<u32>>::new();
+        for (tail, head) in edges {
+            neighbors.entry(*tail).or_default().push(*head);
+            neighbors.entry(*head).or_default().push(*tail);
+        }
+        // visit the neighbors in a fixed order, so the sample is deterministic
+        neighbors.values_mut().for_each(|ids| ids.sort());
+        let degree = |id: &u32| neighbors.get(id).map_or(0, Vec::len);
 
-        while let Some(cur) = queue.pop() {
-            for neighbor in graph.neighbors_undirected(cur) {
-                if visited.contains(&neighbor) {
-                    continue;
+        let mut included = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = nodes
+            .iter()
+            .max_by_key(|id| (degree(id), Reverse(**id)))
+            .filter(|_| max_nodes > 0)
+            .copied()
+            .into_iter()
+            .collect::<VecDeque<_>>();
+        visited.extend(queue.iter().copied());
+        while let Some(id) = queue.pop_front() {
+            included.push(id);
+            if included.len() == max_nodes {
+                break;
+            }
+            for neighbor in neighbors.get(&id).into_iter().flatten() {
+                if visited.insert(*neighbor) {
+                    queue.push_back(*neighbor);
                 }
-                visited.insert(neighbor);
-                queue.push(neighbor);
             }
         }
+        included.sort();
 
-        visited
+        // the sample is laid out with the ids 1..=len, which are mapped back afterwards
+        let sample_id = included
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index as u32 + 1))
+            .collect::<HashMap<_, _>>();
+        let sample_nodes = (1..=included.len() as u32).collect::<Vec<_>>();
+        let sample_edges = edges
+            .iter()
+            .filter_map(|(tail, head)| Some((*sample_id.get(tail)?, *sample_id.get(head)?)))
+            .collect::<Vec<_>>();
+        let (layouts, widths, heights) =
+            Self::create_layers(&sample_nodes, &sample_edges, node_size, false)?;
+        let layouts = layouts
+            .into_iter()
+            .map(|layout| {
+                layout
+                    .into_iter()
+                    .map(|(id, position)| (included[id - 1] as usize, position))
+                    .collect()
+            })
+            .collect();
+
+        Ok(((layouts, widths, heights), included))
     }
 
-    fn new(
-        graph: StableDiGraph<(), ()>,
+    /// Create the layout of a graph, which is a single weakly connected component.
+    ///
+    /// This skips splitting the graph into its components, e.g. if they are already known.
+    /// Returns the same layout, width and height as [GraphLayout::create_layers] would for the
+    /// component, or an error if the graph is not connected or contains a cycle.
+    pub fn create_layers_for_component(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
         node_size: isize,
         global_tasks_in_first_row: bool,
-    ) -> Self {
-        Self {
+    ) -> Result<(NodePositions, usize, usize), LayoutError> {
+        let graph = Self::build_acyclic_graph(nodes, edges)?;
+        if let Some(start) = graph.node_indices().next() {
+            let component = Self::component_dfs(start, &graph);
+            if let Some(node) = graph.node_indices().find(|node| !component.contains(node)) {
+                return Err(LayoutError::InvalidGraph(format!(
+                    "Graph is not connected: node {} is not connected to node {}",
+                    node.index() + 1,
+                    start.index() + 1
+                )));
+            }
+        }
+
+        let graph = Self::new(
             graph,
-            level_of_node: RefCell::new(HashMap::new()),
-            index_of_node: RefCell::new(HashMap::new()),
-            layers: RefCell::new(Vec::new()),
-            _node_size: node_size,
-            node_separation: node_size * 4,
+            node_size,
             global_tasks_in_first_row,
+            Default::default(),
+        );
+        if graph.graph.edge_count() != 0 {
+            graph.align_nodes();
         }
+        Ok(graph.build_layout())
     }
 
-    fn get_level_of_node(&self, node: &NodeIndex) -> Option<usize> {
-        self.level_of_node.borrow().get(node).cloned()
-    }
+    /// Create the layouts with and without `global_tasks_in_first_row` and keep the better ones.
+    ///
+    /// The layouts are compared by the sum of the [layout_score](crate::analysis::layout_score)
+    /// of their components. Returns the layouts together with the value of
+    /// `global_tasks_in_first_row`, that was chosen. If both are equally good, `false` is chosen.
+    pub fn create_layers_auto_first_row(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        options: LayoutOptions,
+    ) -> Result<(Layouts, bool), LayoutError> {
+        let score = |layouts: &Layouts| {
+            layouts
+                .0
+                .iter()
+                .map(|layout| layout_score(layout, edges, node_size))
+                .sum::<f64>()
+        };
+        let layouts =
+            Self::create_layers_with_options(nodes, edges, node_size, false, options.clone())?;
+        let layouts_first_row =
+            Self::create_layers_with_options(nodes, edges, node_size, true, options)?;
 
-    fn insert_level_of_node(&self, node: NodeIndex, level: usize) -> Option<usize> {
-        self.level_of_node.borrow_mut().insert(node, level)
+        Ok(if score(&layouts_first_row) < score(&layouts) {
+            (layouts_first_row, true)
+        } else {
+            (layouts, false)
+        })
     }
 
-    fn get_index_of_node(&self, node: &NodeIndex) -> Option<usize> {
-        self.index_of_node.borrow().get(node).cloned()
+    /// Calculate the level of each node, for each weakly connected component contained in edges.
+    ///
+    /// The levels are the same as the ones used by [GraphLayout::create_layers], with level `0`
+    /// being the topmost level.
+    pub fn create_levels(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        global_tasks_in_first_row: bool,
+    ) -> Result<Vec<HashMap<u32, usize>>, LayoutError> {
+        Ok(Self::align_components(
+            nodes,
+            edges,
+            1,
+            global_tasks_in_first_row,
+            LayoutOptions::default(),
+        )?
+        .into_iter()
+        .map(|graph| {
+            graph
+                .graph
+                .node_indices()
+                // increment index by one for networkx, single nodes are never aligned
+                .map(|node| {
+                    (
+                        node.index() as u32 + 1,
+                        graph.get_level_of_node(&node).unwrap_or(0),
+                    )
+                })
+                .collect()
+        })
+        .collect())
     }
 
-    fn insert_index_of_node(&self, node: NodeIndex, index: usize) -> Option<usize> {
-        self.index_of_node.borrow_mut().insert(node, index)
+    /// Returns the ids of all nodes in reading order, i.e. level by level from top to bottom and
+    /// from left to right within each level.
+    ///
+    /// The weakly connected components are read one after another, in the order in which
+    /// [GraphLayout::create_layers_with_options] returns their layouts.
+    pub fn reading_order(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        options: LayoutOptions,
+    ) -> Result<Vec<u32>, LayoutError> {
+        let mut order = Vec::new();
+        for graph in Self::align_components(nodes, edges, 1, false, options)? {
+            if graph.graph.edge_count() == 0 {
+                // single nodes are never aligned
+                order.extend(
+                    graph
+                        .graph
+                        .node_indices()
+                        .map(|node| node.index() as u32 + 1),
+                );
+                continue;
+            }
+            order.extend(
+                graph
+                    .layers
+                    .borrow()
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    // increment index by one for networkx
+                    .map(|node| node.index() as u32 + 1),
+            );
+        }
+        Ok(order)
     }
 
-    fn get_nums_of_level(&self) -> usize {
-        let mut num_levels = 0;
-        for layer in self.layers.borrow().iter() {
-            if layer.iter().any(|n| n.is_some()) {
-                num_levels += 1;
+    /// Returns the neighbors of each node, which were taken into account when reducing crossings.
+    ///
+    /// These are all predecessors and successors, which end up in the same or an adjacent level
+    /// as the node. Neighbors further away don't influence the position of the node within its
+    /// level. The neighbors are sorted and every node is contained, even if it has none.
+    pub fn placement_influences(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+    ) -> Result<HashMap<u32, Vec<u32>>, LayoutError> {
+        let mut influences = HashMap::new();
+        for graph in Self::align_components(nodes, edges, node_size, false, Default::default())? {
+            for node in graph.graph.node_indices() {
+                let level = graph.get_level_of_node(&node).unwrap_or(0);
+                let mut neighbors = graph
+                    .graph
+                    .neighbors_undirected(node)
+                    .filter(|neighbor| {
+                        level.abs_diff(graph.get_level_of_node(neighbor).unwrap()) < 2
+                    })
+                    // increment index by one for networkx
+                    .map(|neighbor| neighbor.index() as u32 + 1)
+                    .collect::<Vec<_>>();
+                neighbors.sort();
+                neighbors.dedup();
+                influences.insert(node.index() as u32 + 1, neighbors);
             }
         }
-        num_levels
+        Ok(influences)
     }
 
-    fn get_width(&self) -> usize {
-        self.layers
-            .borrow()
-            .iter()
-            .map(|level| {
-                level
-                    .iter()
-                    .map(|n| if n.is_some() { 1 } else { 0 })
-                    .sum::<usize>()
-            })
-            .max()
-            .unwrap_or(0)
+    /// Returns the smallest and largest level among the nodes reachable from each node, including
+    /// the node itself.
+    ///
+    /// The levels are the ones of [GraphLayout::create_levels], so a UI can e.g. draw a bracket
+    /// around the levels of a collapsed subtree.
+    pub fn subtree_level_span(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+    ) -> Result<HashMap<u32, (usize, usize)>, LayoutError> {
+        let mut spans = HashMap::new();
+        for graph in Self::align_components(nodes, edges, 1, false, Default::default())? {
+            // visit the successors first, so their spans are known
+            let order = toposort(&graph.graph, None).unwrap();
+            for node in order.into_iter().rev() {
+                let level = graph.get_level_of_node(&node).unwrap_or(0);
+                let span = graph
+                    .graph
+                    .neighbors_directed(node, Direction::Outgoing)
+                    .map(|successor| spans[&(successor.index() as u32 + 1)])
+                    .fold(
+                        (level, level),
+                        |(min, max), (successor_min, successor_max)| {
+                            (min.min(successor_min), max.max(successor_max))
+                        },
+                    );
+                // increment index by one for networkx
+                spans.insert(node.index() as u32 + 1, span);
+            }
+        }
+        Ok(spans)
     }
 
-    /// Align the nodes contained in the graph in layers.
+    /// Check that the levels in `locked_levels` are consistent with the edges of the graph.
     ///
-    /// This performs the following steps:
-    /// 1. Put each node in a layer, minimizing the height
-    /// 2. Add padding to each level, so that each level has the same length
-    /// 3. Reduce the number of crossings between to consecutive layers
-    /// 4. Add spacing between the nodes
-    fn align_nodes(&self) {
-        if self.graph.node_count() == 0 {
-            return;
+    /// Each node has to be locked to a level below the levels of all of its (transitive)
+    /// predecessors, leaving room for the nodes in between. Returns an error for the first node
+    /// violating this, or if a locked node is not part of the graph. Inconsistent locks are
+    /// ignored by [GraphLayout::create_layers_with_options], which places such nodes as high as
+    /// possible instead.
+    pub fn check_locked_levels(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        locked_levels: &HashMap<u32, usize>,
+    ) -> Result<(), LayoutError> {
+        let nodes = Self::nodes_or_inferred(nodes, edges);
+        if let Some(id) = locked_levels.keys().find(|id| !nodes.contains(id)) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Locked node {id} is not part of the graph"
+            )));
         }
 
-        // arrange nodes in levels,
-        self.arrange_nodes_in_levels();
+        let graph = Self::build_graph(&nodes, edges);
+        let order = toposort(&graph, None).map_err(|cycle| {
+            LayoutError::InvalidGraph(format!(
+                "Graph contains a cycle at node {}",
+                cycle.node_id().index() + 1
+            ))
+        })?;
 
-        // arrange vertically: moves nodes up as far as possible, by looking at successors
-        for node in self.graph.node_identifiers().rev() {
-            self.move_node_in_level(node, Direction::Outgoing)
+        let mut highest_level = HashMap::new();
+        for node in order {
+            let level = graph
+                .neighbors_directed(node, Direction::Incoming)
+                .map(|predecessor| highest_level[&predecessor] + 1)
+                .max()
+                .unwrap_or(0);
+            let level = match locked_levels.get(&(node.index() as u32 + 1)) {
+                Some(locked) if *locked < level => {
+                    return Err(LayoutError::InvalidGraph(format!(
+                        "Node {} can't be locked to level {locked}, its predecessors need at least level {level}",
+                        node.index() + 1
+                    )))
+                }
+                Some(locked) => *locked,
+                None => level,
+            };
+            highest_level.insert(node, level);
         }
-        //  arrange vertically: move nodes down as far as possible, by looking at predecessors
-        for node in self.graph.node_identifiers() {
-            self.move_node_in_level(node, Direction::Incoming)
+        Ok(())
+    }
+
+    /// Check that the graph doesn't contain a cycle, which can't be arranged in levels.
+    ///
+    /// The error names the nodes of one of the cycles, in the order of its edges. Self loops are
+    /// ignored, since they are dropped from the layout.
+    pub fn check_acyclic(nodes: &[u32], edges: &[(u32, u32)]) -> Result<(), LayoutError> {
+        Self::check_graph_acyclic(&Self::build_graph(nodes, edges))
+    }
+
+    /// Check that a graph built by [GraphLayout::build_graph] doesn't contain a cycle, see
+    /// [GraphLayout::check_acyclic].
+    fn check_graph_acyclic(graph: &StableDiGraph<(), ()>) -> Result<(), LayoutError> {
+        let cyclic = match toposort(graph, None) {
+            Ok(_) => return Ok(()),
+            Err(cycle) => cycle.node_id(),
+        };
+        let component = tarjan_scc(graph)
+            .into_iter()
+            .find(|component| component.contains(&cyclic))
+            .unwrap();
+
+        // every node of the strongly connected component has a successor in it, so following
+        // them from its smallest node leads back to a node visited before
+        let mut path = vec![*component.iter().min().unwrap()];
+        let cycle_start = loop {
+            let current = *path.last().unwrap();
+            let next = graph
+                .neighbors(current)
+                .filter(|successor| component.contains(successor))
+                .min()
+                .unwrap();
+            if let Some(position) = path.iter().position(|node| *node == next) {
+                break position;
+            }
+            path.push(next);
+        };
+        let cycle = path[cycle_start..]
+            .iter()
+            .chain([&path[cycle_start]])
+            // increment index by one for networkx
+            .map(|node| (node.index() + 1).to_string())
+            .collect::<Vec<_>>();
+        Err(LayoutError::InvalidGraph(format!(
+            "Graph contains the cycle {}",
+            cycle.join(" -> ")
+        )))
+    }
+
+    /// Check that the node size is positive, a size of `0` would put all nodes on top of each
+    /// other.
+    pub fn check_node_size(node_size: isize) -> Result<(), LayoutError> {
+        if node_size <= 0 {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Node size must be positive, got {node_size}"
+            )));
         }
+        Ok(())
+    }
 
-        // center levels
-        let max_level_length = self
-            .layers
-            .borrow()
+    /// Check that the scale applied to the coordinates is positive and finite.
+    pub fn check_scale(scale: f64) -> Result<(), LayoutError> {
+        if !(scale.is_finite() && scale > 0.) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Scale must be positive and finite, got {scale}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that the target aspect ratio (width / height) is positive and finite.
+    pub fn check_target_ratio(target_ratio: f64) -> Result<(), LayoutError> {
+        if !(target_ratio.is_finite() && target_ratio > 0.) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Target ratio must be positive and finite, got {target_ratio}"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that all sizes, spacings and ratios in `options` are positive and finite.
+    pub fn check_options(options: &LayoutOptions) -> Result<(), LayoutError> {
+        if let Some((level, spacing)) = options
+            .level_spacing
             .iter()
-            .map(|level| level.len())
-            .max()
-            .unwrap();
-        for level in self.layers.borrow_mut().iter_mut() {
-            let mut padding = vec![None; (max_level_length - level.len()) / 2 + 1];
-            padding.append(level);
-            padding.append(&mut vec![None; (max_level_length - level.len()) / 2]);
-            *level = padding;
+            .find(|(_, spacing)| **spacing <= 0)
+        {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Spacing of level {level} must be positive, got {spacing}"
+            )));
+        }
+        if let Some(height) = options.target_height.filter(|height| *height <= 0) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Target height must be positive, got {height}"
+            )));
+        }
+        if options.separation_factor <= 0 {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Separation factor must be positive, got {}",
+                options.separation_factor
+            )));
+        }
+        if let Some(grid) = options.snap_to_grid.filter(|grid| *grid <= 0) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Grid size must be positive, got {grid}"
+            )));
+        }
+        if let Some((min_ratio, max_ratio)) = options.aspect_ratio_bounds {
+            if !(min_ratio.is_finite() && max_ratio.is_finite() && 0. < min_ratio)
+                || min_ratio > max_ratio
+            {
+                return Err(LayoutError::InvalidGraph(format!(
+                    "Aspect ratio bounds must be finite, positive and ordered, got ({min_ratio}, {max_ratio})"
+                )));
+            }
         }
+        Ok(())
+    }
 
-        // fill index_of_node
-        for level in self.layers.borrow().iter() {
-            for (index, node_opt) in level.iter().enumerate() {
-                if let Some(node) = node_opt {
-                    self.insert_index_of_node(*node, index);
+    /// Check that the nodes in `inputs` can be pinned to the first level.
+    ///
+    /// Returns an error if an input is not part of the graph, or if it has an incoming edge from a
+    /// node which is not an input.
+    pub fn check_inputs(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        inputs: &[u32],
+    ) -> Result<(), LayoutError> {
+        let nodes = Self::nodes_or_inferred(nodes, edges);
+        if let Some(id) = inputs.iter().find(|id| !nodes.contains(id)) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Input {id} is not part of the graph"
+            )));
+        }
+        match edges
+            .iter()
+            .find(|(tail, head)| inputs.contains(head) && !inputs.contains(tail))
+        {
+            Some((tail, head)) => Err(LayoutError::InvalidGraph(format!(
+                "Input {head} has an incoming edge from {tail}, which is not an input"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that the nodes in `order_constraints` can be ordered as requested.
+    ///
+    /// Returns an error if a node is not part of the graph, if the constraints contain a cycle, or
+    /// if two ordered nodes are locked to different levels, see [LayoutOptions::order_constraints].
+    pub fn check_order_constraints(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        order_constraints: &[(u32, u32)],
+        locked_levels: &HashMap<u32, usize>,
+    ) -> Result<(), LayoutError> {
+        let nodes = Self::nodes_or_inferred(nodes, edges);
+        if let Some(id) = order_constraints
+            .iter()
+            .flat_map(|(left, right)| [left, right])
+            .find(|id| !nodes.contains(id))
+        {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Ordered node {id} is not part of the graph"
+            )));
+        }
+        for (left, right) in order_constraints {
+            if let (Some(left_level), Some(right_level)) =
+                (locked_levels.get(left), locked_levels.get(right))
+            {
+                if left_level != right_level {
+                    return Err(LayoutError::InvalidGraph(format!(
+                        "Node {left} can't be placed before node {right}, they are locked to levels {left_level} and {right_level}"
+                    )));
                 }
             }
         }
 
-        for _ in 0..10 {
-            for _ in 0..2 {
-                let levels = self.layers.borrow().clone();
-                for (level_index, level) in levels.into_iter().enumerate() {
-                    for node in level.iter().skip(1).flatten() {
-                        if let Some(left) = level[self.get_index_of_node(node).unwrap() - 1] {
-                            self.reduce_crossings(*node, left, level_index)
-                        }
+        let mut constraints = DiGraph::<u32, ()>::new();
+        let mut indices = HashMap::new();
+        for (left, right) in order_constraints {
+            let [left, right] = [left, right].map(|id| {
+                *indices
+                    .entry(*id)
+                    .or_insert_with(|| constraints.add_node(*id))
+            });
+            constraints.add_edge(left, right, ());
+        }
+        toposort(&constraints, None).map_err(|cycle| {
+            LayoutError::InvalidGraph(format!(
+                "Order constraints contain a cycle at node {}",
+                constraints[cycle.node_id()]
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Parse the level and order hints of an external layout tool.
+    ///
+    /// The hints are separated by newlines or `;`, everything after a `#` is ignored. A hint is
+    /// either `<id> @ <level>`, locking a node to a level, or `<id> < <id>`, placing the first node
+    /// before the second one within their level. Returns the locked levels and order constraints
+    /// (see [LayoutOptions::locked_levels] and [LayoutOptions::order_constraints]), or an error for
+    /// malformed hints and nodes locked to different levels.
+    pub fn parse_external_hints(hints: &str) -> Result<ExternalHints, LayoutError> {
+        let mut locked_levels = HashMap::new();
+        let mut order_constraints = Vec::new();
+        for hint in hints
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default())
+            .flat_map(|line| line.split(';'))
+            .map(str::trim)
+            .filter(|hint| !hint.is_empty())
+        {
+            let invalid = || LayoutError::InvalidGraph(format!("Invalid hint \"{hint}\""));
+            if let Some((id, level)) = hint.split_once('@') {
+                let id = id.trim().parse::<u32>().map_err(|_| invalid())?;
+                let level = level.trim().parse::<usize>().map_err(|_| invalid())?;
+                match locked_levels.insert(id, level) {
+                    Some(previous) if previous != level => {
+                        return Err(LayoutError::InvalidGraph(format!(
+                            "Node {id} is hinted to be in level {previous} and {level}"
+                        )))
                     }
+                    _ => (),
                 }
+            } else if let Some((left, right)) = hint.split_once('<') {
+                let left = left.trim().parse::<u32>().map_err(|_| invalid())?;
+                let right = right.trim().parse::<u32>().map_err(|_| invalid())?;
+                order_constraints.push((left, right));
+            } else {
+                return Err(invalid());
             }
+        }
+        Ok((locked_levels, order_constraints))
+    }
 
-            // swap with none neighbors
-            for _ in 0..2 {
-                let mut did_not_swap = true;
-                let levels = self.layers.borrow().clone();
-                for (level_index, level) in levels.iter().enumerate() {
-                    for _ in 0..level.len() {
-                        did_not_swap = true;
-                        for node_opt in level.iter() {
-                            let node = if let Some(node) = node_opt {
-                                node
-                            } else {
-                                continue;
-                            };
-                            if !self.swap_with_none_neighbors(*node, level_index) {
-                                did_not_swap = false;
-                            }
-                        }
-                        if did_not_swap {
-                            break;
-                        }
-                    }
-                }
-                if did_not_swap {
-                    break;
-                }
+    /// Split the graph into its weakly connected components and align the nodes of each of them.
+    ///
+    /// The coordinates of each component can be computed with [GraphLayout::build_layout]. In
+    /// contrast to [GraphLayout::create_layers_with_options], invisible edges are not handled.
+    pub fn align_components(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        options: LayoutOptions,
+    ) -> Result<Vec<Self>, LayoutError> {
+        let mut graphs =
+            Self::split_components(nodes, edges, node_size, global_tasks_in_first_row, options)?;
+
+        for graph in graphs.iter_mut() {
+            if graph.graph.edge_count() != 0 {
+                graph.align_nodes();
             }
         }
 
-        #[cfg(feature = "debug")]
-        self.print_layout(GraphPrintStyle::Char('#'));
+        Ok(graphs)
+    }
 
-        if self.global_tasks_in_first_row {
-            for node in self.graph.node_identifiers() {
-                let node_level = self.get_level_of_node(&node).unwrap();
-                if node_level != 0
-                    && self
-                        .graph
-                        .neighbors_directed(node, Direction::Incoming)
-                        .count()
-                        == 0
-                {
-                    self.layers.borrow_mut()[node_level]
-                        .remove(self.get_index_of_node(&node).unwrap());
-                    self.layers.borrow_mut()[0].push(Some(node));
-                    self.insert_level_of_node(node, 0);
-                }
+    /// Split the graph into its weakly connected components, without aligning them.
+    ///
+    /// Returns an error if the graph contains a cycle, see [GraphLayout::check_acyclic].
+    fn split_components(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        options: LayoutOptions,
+    ) -> Result<Vec<Self>, LayoutError> {
+        let graph = if options.super_endpoints {
+            let (nodes, edges) = Self::add_super_endpoints(nodes, edges);
+            Self::build_acyclic_graph(&nodes, &edges)?
+        } else {
+            Self::build_acyclic_graph(nodes, edges)?
+        };
+        let options = Rc::new(options);
+
+        Ok(Self::into_weakly_connected_components(graph)
+            .into_iter()
+            .map(|subgraph| {
+                Self::new(
+                    subgraph,
+                    node_size,
+                    global_tasks_in_first_row,
+                    options.clone(),
+                )
+            })
+            .collect())
+    }
+
+    /// Create approximate layouts for each weakly connected component contained in edges.
+    ///
+    /// This is meant for previews, where speed matters more than quality. The nodes are ranked
+    /// like in [GraphLayout::create_layers], but the levels are only ordered by a single barycenter
+    /// sweep from top to bottom. Nodes are neither moved between levels, nor centered, nor is the
+    /// number of crossings reduced any further, so the layout is usually worse than the full one.
+    pub fn create_preview_layers(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+    ) -> Result<Layouts, LayoutError> {
+        let graph = Self::build_acyclic_graph(nodes, edges)?;
+
+        let graphs = Self::into_weakly_connected_components(graph)
+            .into_iter()
+            .map(|subgraph| Self::new(subgraph, node_size, false, Default::default()))
+            .collect::<Vec<_>>();
+
+        for graph in graphs.iter() {
+            if graph.graph.edge_count() != 0 {
+                graph.align_nodes_preview();
             }
-            for (node_index, node) in self.layers.borrow()[0].iter().enumerate() {
-                if node.is_some() {
-                    self.insert_index_of_node(node.unwrap(), node_index);
-                }
+        }
+
+        Ok(Self::build_layouts(graphs))
+    }
+
+    /// Create the layouts for each weakly connected component of a bipartite graph.
+    ///
+    /// Each node of the graph needs to be either a source or a sink. The sources are put in the
+    /// first level and the sinks in the second level, only the crossings between the two levels
+    /// are reduced.
+    /// Returns an error if a node has both incoming and outgoing edges.
+    pub fn create_layers_bipartite(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+        node_size: isize,
+    ) -> Result<Layouts, LayoutError> {
+        let graph = Self::build_graph(nodes, edges);
+
+        if let Some(node) = graph.node_indices().find(|node| {
+            graph
+                .neighbors_directed(*node, Direction::Incoming)
+                .next()
+                .is_some()
+                && graph
+                    .neighbors_directed(*node, Direction::Outgoing)
+                    .next()
+                    .is_some()
+        }) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Graph is not bipartite: node {} has incoming and outgoing edges",
+                node.index() + 1
+            )));
+        }
+
+        let graphs = Self::into_weakly_connected_components(graph)
+            .into_iter()
+            .map(|subgraph| Self::new(subgraph, node_size, false, Default::default()))
+            .collect::<Vec<_>>();
+
+        for graph in graphs.iter() {
+            if graph.graph.edge_count() != 0 {
+                graph.align_nodes_bipartite();
+            }
+        }
+
+        Ok(Self::build_layouts(graphs))
+    }
+
+    /// Returns the ids of the (super source, super sink) which are inserted into a graph with the
+    /// given nodes, if [LayoutOptions::super_endpoints] is set.
+    ///
+    /// The ids directly follow the ids of the real nodes, so they never collide with them.
+    pub fn super_endpoint_ids(nodes: &[u32]) -> (u32, u32) {
+        let node_count = nodes.len() as u32;
+        (node_count + 1, node_count + 2)
+    }
+
+    /// Insert a super source, which is connected to every source of the graph and a super sink,
+    /// to which every sink of the graph is connected.
+    fn add_super_endpoints(nodes: &[u32], edges: &[(u32, u32)]) -> EdgeListGraph {
+        let nodes = Self::nodes_or_inferred(nodes, edges);
+        if nodes.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let (super_source, super_sink) = Self::super_endpoint_ids(&nodes);
+        let tails = edges.iter().map(|(tail, _)| *tail).collect::<HashSet<_>>();
+        let heads = edges.iter().map(|(_, head)| *head).collect::<HashSet<_>>();
+
+        let mut edges = edges.to_vec();
+        for node in nodes.iter() {
+            if !heads.contains(node) {
+                edges.push((super_source, *node));
+            }
+            if !tails.contains(node) {
+                edges.push((*node, super_sink));
             }
         }
+        let mut nodes = nodes.to_vec();
+        nodes.extend([super_source, super_sink]);
+
+        (nodes, edges)
+    }
+
+    /// Returns the (in-degree, out-degree) of every node, taken from the graph which is laid out.
+    ///
+    /// The graph is built like for [GraphLayout::create_layers], so parallel edges are counted
+    /// once and self loops are not counted. Nodes without any edges report `(0, 0)`.
+    pub fn node_degrees(nodes: &[u32], edges: &[(u32, u32)]) -> HashMap<u32, (usize, usize)> {
+        let graph = Self::build_graph(nodes, edges);
+        graph
+            .node_indices()
+            .map(|node| {
+                let degree = |direction| graph.neighbors_directed(node, direction).count();
+                // increment index by one for networkx
+                (
+                    node.index() as u32 + 1,
+                    (degree(Direction::Incoming), degree(Direction::Outgoing)),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the sorted ids of the nodes which only have self loops.
+    ///
+    /// Self loops are dropped before creating the layout, so these nodes end up in a component of
+    /// their own, even though they appear in `edges`. They are still positioned like any other
+    /// isolated node.
+    pub fn dangling_nodes(edges: &[(u32, u32)]) -> Vec<u32> {
+        let connected = edges
+            .iter()
+            .filter(|(tail, head)| tail != head)
+            .flat_map(|(tail, head)| [*tail, *head])
+            .collect::<HashSet<_>>();
+        let mut dangling = edges
+            .iter()
+            .filter(|(tail, head)| tail == head && !connected.contains(tail))
+            .map(|(tail, _)| *tail)
+            .collect::<Vec<_>>();
+        dangling.sort_unstable();
+        dangling.dedup();
+        dangling
+    }
+
+    /// Returns the nodes, or if there are none, all ids up to the largest one contained in edges.
+    fn nodes_or_inferred<'a>(nodes: &'a [u32], edges: &[(u32, u32)]) -> Cow<'a, [u32]> {
+        if !nodes.is_empty() {
+            return Cow::Borrowed(nodes);
+        }
+        let max_id = edges
+            .iter()
+            .map(|(tail, head)| *tail.max(head))
+            .max()
+            .unwrap_or(0);
+        Cow::Owned((1..=max_id).collect())
+    }
+
+    /// Build the graph like [GraphLayout::build_graph], but return an error naming one of its
+    /// cycles, if it contains any.
+    ///
+    /// Every [GraphLayout] is created from an acyclic graph, so its levels can be derived from a
+    /// topological order.
+    fn build_acyclic_graph(
+        nodes: &[u32],
+        edges: &[(u32, u32)],
+    ) -> Result<StableDiGraph<(), ()>, LayoutError> {
+        let graph = Self::build_graph(nodes, edges);
+        Self::check_graph_acyclic(&graph)?;
+        Ok(graph)
+    }
+
+    fn build_graph(nodes: &[u32], edges: &[(u32, u32)]) -> StableDiGraph<(), ()> {
+        let mut graph = StableDiGraph::<(), ()>::new();
+
+        for _ in Self::nodes_or_inferred(nodes, edges).iter() {
+            graph.add_node(());
+        }
+
+        // parallel edges are laid out as one, exporters still get all of them, self loops are
+        // dropped, see GraphLayout::dangling_nodes
+        let mut seen = HashSet::new();
+        for (predecessor, successor) in edges
+            .iter()
+            .filter(|(tail, head)| tail != head)
+            .filter(|edge| seen.insert(**edge))
+        {
+            // networkx graph is 1 indexed
+            graph.add_edge(
+                NodeIndex::from(*predecessor - 1),
+                NodeIndex::from(*successor - 1),
+                (),
+            );
+        }
+
+        graph
+    }
+
+    /// Returns the nodes of the component sorted by index, together with its shape.
+    ///
+    /// The shape consists of the number of nodes and the sorted edges, with each node replaced by
+    /// its position among the sorted nodes. Components with the same shape are laid out the same
+    /// way, so the nodes at the same position end up at the same coordinates.
+    fn component_shape(&self) -> (Vec<NodeIndex>, ComponentShape) {
+        let mut nodes = self.graph.node_indices().collect::<Vec<_>>();
+        nodes.sort();
+        let position = nodes
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (*node, position))
+            .collect::<HashMap<_, _>>();
+        let mut edges = self
+            .graph
+            .edge_references()
+            .map(|edge| (position[&edge.source()], position[&edge.target()]))
+            .collect::<Vec<_>>();
+        edges.sort();
+        let shape = (nodes.len(), edges);
+        (nodes, shape)
+    }
+
+    /// Returns true, if an option refers to a node of the component by its id.
+    fn is_referenced_by_options(&self) -> bool {
+        self.graph.node_indices().any(|node| {
+            self.get_locked_level(&node).is_some()
+                || self
+                    .options
+                    .seed_order_from
+                    .as_ref()
+                    .is_some_and(|previous| previous.contains_key(&(node.index() + 1)))
+                || self
+                    .options
+                    .visit_priority
+                    .contains_key(&(node.index() as u32 + 1))
+                || self
+                    .options
+                    .order_constraints
+                    .iter()
+                    .any(|(left, right)| [left, right].contains(&&(node.index() as u32 + 1)))
+        })
+    }
+
+    fn build_layouts(graphs: Vec<Self>) -> Layouts {
+        let mut layout_list = Vec::new();
+        let mut width_list = Vec::new();
+        let mut height_list = Vec::new();
+
+        for (node_positions, width, height) in graphs.into_iter().map(|graph| graph.build_layout())
+        {
+            layout_list.push(node_positions);
+            width_list.push(width);
+            height_list.push(height);
+        }
+
+        (layout_list, width_list, height_list)
+    }
+
+    /// Create the layouts for each weakly connected component of a StableDiGraph.
+    ///
+    /// In contrast to [GraphLayout::create_layers], the positions are keyed by the [NodeIndex] of
+    /// the graph and are returned together with a reference to the payload of the node. This
+    /// makes it possible to correlate positions and node data, even if the graph had nodes
+    /// removed and its indices are no longer contiguous. Returns an error if the graph contains a
+    /// cycle.
+    pub fn create_layers_from_stable_digraph<N, E>(
+        graph: &StableDiGraph<N, E>,
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+    ) -> Result<Vec<PayloadPositions<'_, N>>, LayoutError> {
+        // map the (possibly non-contiguous) indices to 1 based ids
+        let indices = graph.node_indices().collect::<Vec<_>>();
+        let ids = indices
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (*node, id as u32 + 1))
+            .collect::<HashMap<_, _>>();
+        let nodes = (1..=indices.len() as u32).collect::<Vec<_>>();
+        let edges = graph
+            .edge_references()
+            .map(|edge| (ids[&edge.source()], ids[&edge.target()]))
+            .collect::<Vec<_>>();
+
+        let (layouts, _, _) =
+            Self::create_layers(&nodes, &edges, node_size, global_tasks_in_first_row)?;
+
+        Ok(layouts
+            .into_iter()
+            .map(|layout| {
+                layout
+                    .into_iter()
+                    .map(|(id, coords)| {
+                        let node = indices[id - 1];
+                        (node, (coords, &graph[node]))
+                    })
+                    .collect()
+            })
+            .collect())
+    }
+
+    fn build_layout_no_edges(&self) -> (NodePositions, usize, usize) {
+        let node = self.graph.node_indices().next().unwrap();
+        // increment node index by one for networkx
+        (
+            HashMap::from([(node.index() + 1, (self.node_separation, 0))]),
+            1,
+            1,
+        )
+    }
+
+    pub fn build_layout(&self) -> (NodePositions, usize, usize) {
+        if self.graph.edge_count() == 0 {
+            return self.build_layout_no_edges();
+        }
+        let mut node_positions = HashMap::new();
+        let offset = if self.layers.borrow()[0].iter().all(|n| n.is_none()) {
+            1
+        } else {
+            0
+        };
+
+        let ys = self.get_level_ys(offset);
+        for (level_index, level) in self.layers.borrow().iter().enumerate() {
+            let y = ys[level_index];
+            for (node_index, node_opt) in level.iter().enumerate() {
+                let node = if let Some(node) = node_opt {
+                    *node
+                } else {
+                    continue;
+                };
+                let x = node_index as isize * self.node_separation;
+                let y = if self.options.screen_coords { -y } else { y };
+                node_positions.insert(node.index() + 1, (x, y)); // increment index by one for networkx
+            }
+        }
+        if self.options.symmetric_subtrees {
+            self.mirror_symmetric_subtrees(&mut node_positions);
+        }
+        if let Some(grid) = self.options.snap_to_grid {
+            node_positions = snap_layout(node_positions, grid);
+        }
+        (node_positions, self.get_width(), self.get_nums_of_level())
+    }
+
+    /// Save the graph, levels and indices of the nodes as JSON, so the layout can be restored
+    /// with [GraphLayout::load_state] without aligning the nodes again.
+    ///
+    /// The options are not part of the state, so the coordinates can be assigned with different
+    /// options, e.g. [LayoutOptions::screen_coords].
+    #[cfg(feature = "serde")]
+    pub fn save_state(&self) -> String {
+        let node_values = |map: &RefCell<NodeMap>| {
+            self.graph
+                .node_indices()
+                .filter_map(|node| Some((node.index(), map.borrow().get(&node)?)))
+                .collect()
+        };
+        let state = LayoutState {
+            nodes: self.graph.node_indices().map(|node| node.index()).collect(),
+            edges: self
+                .graph
+                .edge_references()
+                .map(|edge| (edge.source().index(), edge.target().index()))
+                .collect(),
+            layers: self
+                .layers
+                .borrow()
+                .iter()
+                .map(|level| {
+                    level
+                        .iter()
+                        .map(|node| node.map(|node| node.index()))
+                        .collect()
+                })
+                .collect(),
+            level_of_node: node_values(&self.level_of_node),
+            index_of_node: node_values(&self.index_of_node),
+            node_size: self.node_size,
+            global_tasks_in_first_row: self.global_tasks_in_first_row,
+        };
+        // the state only contains sequences and numbers, so serializing it can't fail
+        serde_json::to_string(&state).unwrap()
+    }
+
+    /// Restore a layout saved with [GraphLayout::save_state], using `options` for the remaining
+    /// steps, e.g. [GraphLayout::build_layout].
+    ///
+    /// Returns an error, if the state is malformed, refers to nodes which are not part of it,
+    /// contains a cycle or doesn't place each of its nodes in its levels.
+    #[cfg(feature = "serde")]
+    pub fn load_state(state: &str, options: LayoutOptions) -> Result<Self, LayoutError> {
+        let state = serde_json::from_str::<LayoutState>(state)
+            .map_err(|err| LayoutError::InvalidGraph(format!("Invalid layout state: {err}")))?;
+        Self::check_node_size(state.node_size)?;
+        let nodes = state.nodes.iter().copied().collect::<HashSet<_>>();
+        let unknown = |node: usize| {
+            LayoutError::InvalidGraph(format!("Layout state refers to unknown node {node}"))
+        };
+        if nodes.is_empty() {
+            return Err(LayoutError::InvalidGraph(
+                "Layout state contains no nodes".to_string(),
+            ));
+        }
+        // single nodes are never aligned, so their layout only has the one node
+        if state.edges.is_empty() && nodes.len() > 1 {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Layout state contains {} nodes, but no edges",
+                nodes.len()
+            )));
+        }
+        if !state.edges.is_empty() && state.layers.is_empty() {
+            return Err(LayoutError::InvalidGraph(
+                "Layout state contains edges, but no levels".to_string(),
+            ));
+        }
+
+        // add the nodes up to the largest one and remove the missing ones again, so the indices
+        // stay the same
+        let mut graph = StableDiGraph::new();
+        if let Some(&node) = nodes.iter().find(|&&node| node >= MAX_STATE_NODE_BOUND) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Layout state uses node {node}, but at most {MAX_STATE_NODE_BOUND} nodes are supported"
+            )));
+        }
+        let node_bound = nodes.iter().max().map_or(0, |node| node + 1);
+        for _ in 0..node_bound {
+            graph.add_node(());
+        }
+        for node in 0..node_bound {
+            if !nodes.contains(&node) {
+                graph.remove_node(NodeIndex::new(node));
+            }
+        }
+        for (tail, head) in state.edges {
+            for node in [tail, head] {
+                if !nodes.contains(&node) {
+                    return Err(unknown(node));
+                }
+            }
+            graph.add_edge(NodeIndex::new(tail), NodeIndex::new(head), ());
+        }
+        Self::check_graph_acyclic(&graph)?;
+
+        let layout = Self::new(
+            graph,
+            state.node_size,
+            state.global_tasks_in_first_row,
+            Rc::new(options),
+        );
+        let mut layers = Vec::new();
+        for level in state.layers {
+            let mut nodes_of_level = Vec::new();
+            for node in level {
+                match node {
+                    Some(node) if !nodes.contains(&node) => return Err(unknown(node)),
+                    node => nodes_of_level.push(node.map(NodeIndex::new)),
+                }
+            }
+            layers.push(nodes_of_level);
+        }
+
+        // every node of an aligned layout has to have a level and an index, which point to its
+        // position in the layers
+        let level_of_node = state.level_of_node.into_iter().collect::<HashMap<_, _>>();
+        let index_of_node = state.index_of_node.into_iter().collect::<HashMap<_, _>>();
+        for &node in level_of_node.keys().chain(index_of_node.keys()) {
+            if !nodes.contains(&node) {
+                return Err(unknown(node));
+            }
+        }
+        if layout.graph.edge_count() != 0 {
+            let mut sorted_nodes = state.nodes.clone();
+            sorted_nodes.sort_unstable();
+            for node in sorted_nodes {
+                let (Some(&level), Some(&index)) =
+                    (level_of_node.get(&node), index_of_node.get(&node))
+                else {
+                    return Err(LayoutError::InvalidGraph(format!(
+                        "Layout state doesn't contain the level and index of node {node}"
+                    )));
+                };
+                let Some(level_nodes) = layers.get(level) else {
+                    return Err(LayoutError::InvalidGraph(format!(
+                        "Layout state places node {node} on level {level}, but it only has {} levels",
+                        layers.len()
+                    )));
+                };
+                if level_nodes.get(index) != Some(&Some(NodeIndex::new(node))) {
+                    return Err(LayoutError::InvalidGraph(format!(
+                        "Layout state places node {node} at index {index} of level {level}, but it isn't there"
+                    )));
+                }
+            }
+        }
+
+        *layout.layers.borrow_mut() = layers;
+        for (node, level) in level_of_node {
+            layout.insert_level_of_node(NodeIndex::new(node), level);
+        }
+        for (node, index) in index_of_node {
+            layout.insert_index_of_node(NodeIndex::new(node), index);
+        }
+        Ok(layout)
+    }
+
+    /// Place the two subtrees below a node symmetrically around the node, if they have the same
+    /// shape, see [LayoutOptions::symmetric_subtrees].
+    ///
+    /// This only applies to nodes with exactly two children, whose subtrees are trees which aren't
+    /// reachable from any other node. The nodes of the subtrees are paired up like in a mirror
+    /// image and each pair is centered below the node, keeping its distance if possible. Pairs
+    /// which would end up closer than the node separation to another node of their level are
+    /// spread further apart. Nodes are visited from top to bottom, so nested symmetric subtrees
+    /// are mirrored as well.
+    fn mirror_symmetric_subtrees(&self, positions: &mut NodePositions) {
+        // increment index by one for networkx
+        let id = |node: NodeIndex| node.index() + 1;
+        let is_private = |node: NodeIndex| {
+            self.graph
+                .neighbors_directed(node, Direction::Incoming)
+                .count()
+                == 1
+        };
+        let mut shapes = HashMap::new();
+        for node in toposort(&self.graph, None).unwrap().into_iter().rev() {
+            let children = self.graph.neighbors(node).collect::<Vec<_>>();
+            let shape = match children.iter().all(|child| is_private(*child)) {
+                true => children
+                    .iter()
+                    .map(|child| shapes.get(child).cloned())
+                    .collect::<Option<Vec<String>>>()
+                    .map(|mut child_shapes| {
+                        child_shapes.sort();
+                        format!("({})", child_shapes.concat())
+                    }),
+                false => None,
+            };
+            if let Some(shape) = shape {
+                shapes.insert(node, shape);
+            }
+        }
+
+        let mut parents = self.graph.node_indices().collect::<Vec<_>>();
+        parents.sort_by_key(|node| (-positions[&id(*node)].1, id(*node)));
+        for parent in parents {
+            let mut children = self.graph.neighbors(parent).collect::<Vec<_>>();
+            if children.len() != 2
+                || !children.iter().all(|child| is_private(*child))
+                || !shapes.contains_key(&children[0])
+                || shapes.get(&children[0]) != shapes.get(&children[1])
+            {
+                continue;
+            }
+            children.sort_by_key(|child| positions[&id(*child)].0);
+
+            // pair up the nodes of the left subtree with their mirror image in the right one
+            let mut pairs = Vec::new();
+            let mut queue = VecDeque::from([(children[0], children[1])]);
+            while let Some((left, right)) = queue.pop_front() {
+                pairs.push((id(left), id(right)));
+                let by_shape_and_x = |node: NodeIndex, mirrored: bool| {
+                    let mut children = self.graph.neighbors(node).collect::<Vec<_>>();
+                    children.sort_by_key(|child| {
+                        let x = positions[&id(*child)].0;
+                        (shapes[child].clone(), if mirrored { -x } else { x })
+                    });
+                    children
+                };
+                queue.extend(
+                    by_shape_and_x(left, false)
+                        .into_iter()
+                        .zip(by_shape_and_x(right, true)),
+                );
+            }
+
+            let center = positions[&id(parent)].0;
+            for (left, right) in pairs {
+                let y = positions[&left].1;
+                let is_free = |x: isize| {
+                    positions.iter().all(|(other, (other_x, other_y))| {
+                        [left, right].contains(other)
+                            || *other_y != y
+                            || (x - other_x).abs() >= self.node_separation
+                    })
+                };
+                // spread the pair until both positions are free, which is the case at the latest
+                // once they are further out than all other nodes
+                let mut half_distance = (positions[&right].0 - positions[&left].0).abs() / 2;
+                while !(is_free(center - half_distance) && is_free(center + half_distance)) {
+                    half_distance += (self.node_separation / 2).max(1);
+                }
+                positions.insert(left, (center - half_distance, y));
+                positions.insert(right, (center + half_distance, y));
+            }
+        }
+    }
+
+    /// Returns the y-coordinate of each level.
+    ///
+    /// Each level is placed its gap below the previous one, unless its y-coordinate is given by
+    /// [LayoutOptions::level_y].
+    fn get_level_ys(&self, offset: usize) -> Vec<isize> {
+        let mut y = 0;
+        self.get_level_gaps(offset)
+            .into_iter()
+            .enumerate()
+            .map(|(level_index, gap)| {
+                y = match self.options.level_y.get(&level_index) {
+                    // the coordinates are flipped later on
+                    Some(level_y) if self.options.screen_coords => -level_y,
+                    Some(level_y) => *level_y,
+                    None => y - gap,
+                };
+                y
+            })
+            .collect()
+    }
+
+    /// Returns the vertical gap above each level, the levels up to and including `offset` have
+    /// none.
+    ///
+    /// The gaps are reduced proportionally, if they exceed [LayoutOptions::target_height], but
+    /// never below the node size.
+    fn get_level_gaps(&self, offset: usize) -> Vec<isize> {
+        let gaps = (0..self.layers.borrow().len())
+            .map(|level_index| {
+                if level_index <= offset {
+                    return 0;
+                }
+                // the gap above each level can be overridden
+                self.options
+                    .level_spacing
+                    .get(&level_index)
+                    .copied()
+                    .unwrap_or(self.node_separation)
+            })
+            .collect::<Vec<_>>();
+
+        let height = gaps.iter().sum::<isize>();
+        match self.options.target_height {
+            Some(target_height) if height > target_height => gaps
+                .into_iter()
+                .map(|gap| (gap * target_height / height).max(gap.min(self.node_size)))
+                .collect(),
+            _ => gaps,
+        }
+    }
+
+    /// Takes a graph and breaks it down into its weakly connected components.
+    /// A weakly connected component is a list of edges which are connected with each other.
+    fn into_weakly_connected_components(
+        graph: StableDiGraph<(), ()>,
+    ) -> Vec<StableDiGraph<(), ()>> {
+        let mut components = Vec::new();
+        let mut visited = HashSet::new();
+
+        for node in graph.node_indices() {
+            if visited.contains(&node) {
+                continue;
+            }
+
+            let component_nodes = Self::component_dfs(node, &graph);
+            let component = graph.filter_map(
+                |n, _| {
+                    if component_nodes.contains(&n) {
+                        Some(())
+                    } else {
+                        None
+                    }
+                },
+                |_, _| Some(()),
+            );
+
+            component_nodes.into_iter().for_each(|n| {
+                visited.insert(n);
+            });
+            components.push(component);
+        }
+
+        components
+    }
+
+    fn component_dfs(start: NodeIndex, graph: &StableDiGraph<(), ()>) -> HashSet<NodeIndex> {
+        let mut queue = vec![start];
+        let mut visited = HashSet::new();
+
+        visited.insert(start);
+
+        while let Some(cur) = queue.pop() {
+            for neighbor in graph.neighbors_undirected(cur) {
+                if visited.contains(&neighbor) {
+                    continue;
+                }
+                visited.insert(neighbor);
+                queue.push(neighbor);
+            }
+        }
+
+        visited
+    }
+
+    fn new(
+        graph: StableDiGraph<(), ()>,
+        node_size: isize,
+        global_tasks_in_first_row: bool,
+        options: Rc<LayoutOptions>,
+    ) -> Self {
+        let node_bound = graph.node_bound();
+        Self {
+            graph,
+            level_of_node: RefCell::new(NodeMap::new(options.storage, node_bound)),
+            index_of_node: RefCell::new(NodeMap::new(options.storage, node_bound)),
+            layers: RefCell::new(Vec::new()),
+            node_size,
+            node_separation: node_size * options.separation_factor,
+            global_tasks_in_first_row,
+            options,
+        }
+    }
+
+    fn get_level_of_node(&self, node: &NodeIndex) -> Option<usize> {
+        self.level_of_node.borrow().get(node)
+    }
+
+    fn insert_level_of_node(&self, node: NodeIndex, level: usize) -> Option<usize> {
+        self.level_of_node.borrow_mut().insert(node, level)
+    }
+
+    fn get_index_of_node(&self, node: &NodeIndex) -> Option<usize> {
+        self.index_of_node.borrow().get(node)
+    }
+
+    fn insert_index_of_node(&self, node: NodeIndex, index: usize) -> Option<usize> {
+        self.index_of_node.borrow_mut().insert(node, index)
+    }
+
+    fn get_nums_of_level(&self) -> usize {
+        let mut num_levels = 0;
+        for layer in self.layers.borrow().iter() {
+            if layer.iter().any(|n| n.is_some()) {
+                num_levels += 1;
+            }
+        }
+        num_levels
+    }
+
+    /// Returns the number of levels up to and including the last one, which contains any nodes.
+    ///
+    /// In contrast to [GraphLayout::get_nums_of_level], empty levels in between are counted, which
+    /// occur if nodes are locked to a level.
+    fn get_levels_spanned(&self) -> usize {
+        self.layers
+            .borrow()
+            .iter()
+            .rposition(|level| level.iter().any(Option::is_some))
+            .map_or(0, |level| level + 1)
+    }
+
+    /// Returns the level the node is locked to, see [LayoutOptions::locked_levels].
+    ///
+    /// Inputs are always locked to the first level, see [LayoutOptions::inputs].
+    fn get_locked_level(&self, node: &NodeIndex) -> Option<usize> {
+        if self.is_input(node) {
+            return Some(0);
+        }
+        // increment index by one for networkx
+        self.options
+            .locked_levels
+            .get(&(node.index() as u32 + 1))
+            .copied()
+    }
+
+    fn is_input(&self, node: &NodeIndex) -> bool {
+        // increment index by one for networkx
+        self.options.inputs.contains(&(node.index() as u32 + 1))
+    }
+
+    /// Returns the first level the node may be placed in.
+    ///
+    /// If the component contains inputs, the first level is reserved for them.
+    fn get_first_allowed_level(&self, node: &NodeIndex) -> usize {
+        let has_inputs = self
+            .options
+            .inputs
+            .iter()
+            .any(|id| self.graph.contains_node(NodeIndex::new(*id as usize - 1)));
+        if has_inputs && !self.is_input(node) {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn get_width(&self) -> usize {
+        self.layers
+            .borrow()
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|n| if n.is_some() { 1 } else { 0 })
+                    .sum::<usize>()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Align the nodes contained in the graph in layers.
+    ///
+    /// This performs the following steps:
+    /// 1. Put each node in a layer, minimizing the height
+    /// 2. Add padding to each level, so that each level has the same length
+    /// 3. Reduce the number of crossings between to consecutive layers
+    /// 4. Add spacing between the nodes
+    fn align_nodes(&self) {
+        if self.graph.node_count() == 0 {
+            return;
+        }
+        if self.is_trivial() {
+            self.align_nodes_trivial();
+            return;
+        }
+
+        // arrange nodes in levels,
+        self.arrange_nodes_in_levels();
+
+        match self.options.rank_pass_order {
+            RankPassOrder::UpThenDown => {
+                self.move_nodes_up();
+                self.move_nodes_down();
+            }
+            RankPassOrder::DownThenUp => {
+                self.move_nodes_down();
+                self.move_nodes_up();
+            }
+            RankPassOrder::Balanced => self.move_nodes_to_midpoint(),
+        }
+
+        if let Some(previous) = &self.options.seed_order_from {
+            self.seed_level_order(previous);
+        }
+        if let Some((min_ratio, max_ratio)) = self.options.aspect_ratio_bounds {
+            self.fit_aspect_ratio_bounds(min_ratio, max_ratio);
+        }
+        if let Some(seed) = self.options.seed {
+            self.shuffle_levels(seed);
+        }
+        self.center_levels();
+        self.fill_index_of_node();
+        if !self.options.order_constraints.is_empty() {
+            self.apply_order_constraints();
+        }
+        self.minimize_crossings();
+        if self.options.floating_placement != FloatingPlacement::Keep {
+            self.place_floating_nodes();
+        }
+        if self.options.center_hubs {
+            self.center_hubs();
+        }
+        if self.options.straighten_critical_path {
+            self.straighten_critical_path();
+        }
+        if !self.options.inputs.is_empty() {
+            self.order_inputs();
+        }
+        // crossing reduction and the passes after it may have swapped ordered nodes
+        if !self.options.order_constraints.is_empty() {
+            self.apply_order_constraints();
+        }
+
+        #[cfg(feature = "debug")]
+        self.print_layout(GraphPrintStyle::Char('#'));
+
+        if self.global_tasks_in_first_row {
+            for node in self.graph.node_identifiers() {
+                let node_level = self.get_level_of_node(&node).unwrap();
+                if node_level != 0
+                    && self.get_locked_level(&node).is_none()
+                    && self.get_first_allowed_level(&node) == 0
+                    && self
+                        .graph
+                        .neighbors_directed(node, Direction::Incoming)
+                        .count()
+                        == 0
+                {
+                    self.layers.borrow_mut()[node_level]
+                        .remove(self.get_index_of_node(&node).unwrap());
+                    self.layers.borrow_mut()[0].push(Some(node));
+                    self.insert_level_of_node(node, 0);
+                }
+            }
+            for (node_index, node) in self.layers.borrow()[0].iter().enumerate() {
+                if node.is_some() {
+                    self.insert_index_of_node(node.unwrap(), node_index);
+                }
+            }
+        }
+
+        if self.options.sinks_in_last_row {
+            self.move_sinks_to_last_level();
+        }
+    }
+
+    /// Order the inputs in the first level like in [LayoutOptions::inputs], keeping the slots they
+    /// occupy.
+    fn order_inputs(&self) {
+        let mut layers = self.layers.borrow_mut();
+        let Some(first_level) = layers.first_mut() else {
+            return;
+        };
+        let mut inputs = self
+            .options
+            .inputs
+            .iter()
+            .map(|id| NodeIndex::new(*id as usize - 1))
+            .filter(|node| first_level.contains(&Some(*node)))
+            .collect::<Vec<_>>()
+            .into_iter();
+        for slot in first_level.iter_mut() {
+            if slot.is_some_and(|node| self.is_input(&node)) {
+                *slot = inputs.next();
+            }
+        }
+        for (index, node) in first_level.iter().enumerate() {
+            if let Some(node) = node {
+                self.insert_index_of_node(*node, index);
+            }
+        }
+    }
+
+    /// Reorder the nodes of each level, so that they respect [LayoutOptions::order_constraints],
+    /// keeping the slots they occupy.
+    ///
+    /// Of the nodes which may be placed next, the leftmost one is taken, so nodes which already
+    /// respect the constraints keep their order.
+    fn apply_order_constraints(&self) {
+        let constraints = self
+            .options
+            .order_constraints
+            .iter()
+            .copied()
+            .collect::<HashSet<_>>();
+        // increment index by one for networkx
+        let id = |node: NodeIndex| node.index() as u32 + 1;
+        let is_constrained = |node: NodeIndex| {
+            constraints
+                .iter()
+                .any(|(left, right)| *left == id(node) || *right == id(node))
+        };
+
+        for level in self.layers.borrow_mut().iter_mut() {
+            let slots = (0..level.len())
+                .filter(|index| level[*index].is_some_and(is_constrained))
+                .collect::<Vec<_>>();
+            let mut remaining = slots
+                .iter()
+                .map(|index| level[*index].unwrap())
+                .collect::<Vec<_>>();
+            for slot in slots {
+                // the constraints are acyclic, see GraphLayout::check_order_constraints
+                let next = remaining
+                    .iter()
+                    .position(|node| {
+                        !remaining
+                            .iter()
+                            .any(|other| constraints.contains(&(id(*other), id(*node))))
+                    })
+                    .unwrap_or(0);
+                level[slot] = Some(remaining.remove(next));
+            }
+        }
+        self.fill_index_of_node();
+    }
+
+    /// Returns true, if the component is small enough to be aligned by
+    /// [GraphLayout::align_nodes_trivial], see [LayoutOptions::trivial_threshold].
+    fn is_trivial(&self) -> bool {
+        self.graph.node_count() <= self.options.trivial_threshold
+    }
+
+    /// Align the nodes of a tiny component, by ranking them and placing the nodes of each level in
+    /// a centered row ordered by their id.
+    fn align_nodes_trivial(&self) {
+        self.arrange_nodes_in_levels();
+        for level in self.layers.borrow_mut().iter_mut() {
+            level.sort();
+        }
+        self.center_levels();
+        self.fill_index_of_node();
+    }
+
+    /// Move all nodes without successors to the last level, which contains any nodes.
+    fn move_sinks_to_last_level(&self) {
+        let Some(last_level) = self
+            .layers
+            .borrow()
+            .iter()
+            .rposition(|level| level.iter().any(Option::is_some))
+        else {
+            return;
+        };
+
+        for node in self.graph.node_identifiers() {
+            let node_level = self.get_level_of_node(&node).unwrap();
+            if node_level != last_level
+                && self.get_locked_level(&node).is_none()
+                && self
+                    .graph
+                    .neighbors_directed(node, Direction::Outgoing)
+                    .next()
+                    .is_none()
+            {
+                self.layers.borrow_mut()[node_level].retain(|other| other != &Some(node));
+                self.layers.borrow_mut()[last_level].push(Some(node));
+                self.insert_level_of_node(node, last_level);
+            }
+        }
+        self.fill_index_of_node();
+    }
+
+    /// Align the nodes in levels for a preview.
+    ///
+    /// The nodes are ranked, then each level is ordered by a single barycenter sweep with respect
+    /// to the level above it.
+    fn align_nodes_preview(&self) {
+        self.arrange_nodes_in_levels();
+
+        let level_ids = |level: &Vec<Option<NodeIndex>>| {
+            level
+                .iter()
+                .flatten()
+                .map(|node| node.index() as u32)
+                .collect::<HashSet<_>>()
+        };
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| (edge.source().index() as u32, edge.target().index() as u32))
+            .collect::<Vec<_>>();
+
+        let num_levels = self.layers.borrow().len();
+        for level in 1..num_levels {
+            let upper = self.layers.borrow()[level - 1]
+                .iter()
+                .map(|node| node.map(|node| node.index() as u32))
+                .collect::<Vec<_>>();
+            let lower = level_ids(&self.layers.borrow()[level]);
+            // only look at the edges between the two levels
+            let level_edges = edges
+                .iter()
+                .filter(|(tail, head)| lower.contains(head) && upper.contains(&Some(*tail)))
+                .copied()
+                .collect::<Vec<_>>();
+            self.layers.borrow_mut()[level] = barycenter_order(&upper, &level_edges)
+                .into_iter()
+                .map(|node| Some(NodeIndex::from(node)))
+                .collect();
+        }
+    }
+
+    /// Align the nodes of a bipartite graph in exactly two levels.
+    ///
+    /// All sources are put in the first level, all sinks in the second one. Only the crossings
+    /// between the two levels are reduced, there is no need to arrange the levels vertically.
+    /// The levels are first ordered with alternating barycenter sweeps, keeping the ordering with
+    /// the fewest crossings, before running the usual crossing reduction.
+    fn align_nodes_bipartite(&self) {
+        for node in self.graph.node_identifiers() {
+            if self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .next()
+                .is_none()
+            {
+                self.insert_level_of_node(node, 0);
+                self.add_node_to_level(node, 0);
+            }
+        }
+        for node in self.graph.node_identifiers() {
+            if self.get_level_of_node(&node).is_none() {
+                self.insert_level_of_node(node, 1);
+                self.add_node_to_level(node, 1);
+            }
+        }
+
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| (edge.source().index() as u32, edge.target().index() as u32))
+            .collect::<Vec<_>>();
+        let level_ids = |level: &Vec<Option<NodeIndex>>| {
+            level
+                .iter()
+                .flatten()
+                .map(|node| Some(node.index() as u32))
+                .collect::<Vec<_>>()
+        };
+        let mut upper = level_ids(&self.layers.borrow()[0]);
+        let mut lower = level_ids(&self.layers.borrow()[1]);
+        let mut best = (
+            count_crossings(&upper, &lower, &edges),
+            upper.clone(),
+            lower.clone(),
+        );
+        for _ in 0..4 {
+            lower = barycenter_order(&upper, &edges)
+                .into_iter()
+                .map(Some)
+                .collect();
+            upper = barycenter_order(&lower, &edges)
+                .into_iter()
+                .map(Some)
+                .collect();
+            let crossings = count_crossings(&upper, &lower, &edges);
+            if crossings < best.0 {
+                best = (crossings, upper.clone(), lower.clone());
+            }
+        }
+        let (_, upper, lower) = best;
+        *self.layers.borrow_mut() = [upper, lower]
+            .into_iter()
+            .map(|level| {
+                level
+                    .into_iter()
+                    .map(|node| node.map(NodeIndex::from))
+                    .collect()
+            })
+            .collect();
+
+        self.center_levels();
+        self.fill_index_of_node();
+        self.minimize_crossings();
+    }
+
+    fn fill_index_of_node(&self) {
+        for level in self.layers.borrow().iter() {
+            for (index, node_opt) in level.iter().enumerate() {
+                if let Some(node) = node_opt {
+                    self.insert_index_of_node(*node, index);
+                }
+            }
+        }
+    }
+
+    /// Reduce the number of crossings between consecutive levels, by swapping neighboring nodes
+    /// and moving nodes into empty slots closer to their neighbors.
+    fn minimize_crossings(&self) {
+        // the levels don't change, so the successors taken into account stay the same
+        let successors = self.get_direct_successors();
+        for _ in 0..10 {
+            for _ in 0..2 {
+                let mut index_of_node = self.get_index_array();
+                let levels = self.layers.borrow().clone();
+                for (level_index, level) in levels.into_iter().enumerate() {
+                    for node in level.iter().skip(1).flatten() {
+                        if let Some(left) = level[index_of_node[node.index()].unwrap() - 1] {
+                            self.reduce_crossings(
+                                *node,
+                                left,
+                                level_index,
+                                &successors,
+                                &mut index_of_node,
+                            )
+                        }
+                    }
+                }
+            }
+
+            // swap with none neighbors
+            for _ in 0..2 {
+                let mut did_not_swap = true;
+                let levels = self.layers.borrow().clone();
+                for (level_index, level) in levels.iter().enumerate() {
+                    for _ in 0..level.len() {
+                        did_not_swap = true;
+                        for node_opt in level.iter() {
+                            let node = if let Some(node) = node_opt {
+                                node
+                            } else {
+                                continue;
+                            };
+                            if !self.swap_with_none_neighbors(*node, level_index) {
+                                did_not_swap = false;
+                            }
+                        }
+                        if did_not_swap {
+                            break;
+                        }
+                    }
+                }
+                if did_not_swap {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Order the nodes of each level by their x-coordinate in a previous layout.
+    ///
+    /// Nodes which are not part of the previous layout keep their relative order and are put after
+    /// the nodes which are.
+    fn seed_level_order(&self, previous: &NodePositions) {
+        for level in self.layers.borrow_mut().iter_mut() {
+            // increment index by one for networkx
+            level.sort_by_key(|node| {
+                let x = node.and_then(|node| previous.get(&(node.index() + 1)));
+                (x.is_none(), x.map(|(x, _)| *x))
+            });
+        }
+    }
+
+    /// Place the nodes without neighbors in the adjacent levels according to
+    /// [LayoutOptions::floating_placement].
+    fn place_floating_nodes(&self) {
+        let floating = self
+            .graph
+            .node_indices()
+            .filter(|node| {
+                let level = self.get_level_of_node(node).unwrap();
+                self.graph
+                    .neighbors_undirected(*node)
+                    .all(|neighbor| level.abs_diff(self.get_level_of_node(&neighbor).unwrap()) > 1)
+            })
+            .collect::<Vec<_>>();
+
+        for node in floating {
+            let level_index = self.get_level_of_node(&node).unwrap();
+            let slot = self.layers.borrow()[level_index]
+                .iter()
+                .position(|other| other == &Some(node))
+                .unwrap();
+            // the levels the node can be placed in, without reversing an edge
+            let highest_level = self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .map(|predecessor| self.get_level_of_node(&predecessor).unwrap() + 1)
+                .max()
+                .unwrap_or(0);
+            let deepest_level = self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .map(|successor| self.get_level_of_node(&successor).unwrap() - 1)
+                .min()
+                .unwrap_or(self.layers.borrow().len() - 1);
+
+            let (target_level, target_slot) = match self.options.floating_placement {
+                FloatingPlacement::Keep => continue,
+                FloatingPlacement::EndOfShortestLevel => {
+                    let other_nodes = |level: usize| {
+                        self.layers.borrow()[level]
+                            .iter()
+                            .flatten()
+                            .filter(|other| **other != node)
+                            .count()
+                    };
+                    let target_level = (highest_level..=deepest_level)
+                        .min_by_key(|level| other_nodes(*level))
+                        .unwrap();
+                    if other_nodes(target_level) >= other_nodes(level_index) {
+                        continue;
+                    }
+                    let end = self.layers.borrow()[target_level]
+                        .iter()
+                        .rposition(Option::is_some)
+                        .map_or(0, |last| last + 1);
+                    (target_level, end)
+                }
+                FloatingPlacement::Centroid => {
+                    let others = self
+                        .layers
+                        .borrow()
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(level, nodes)| {
+                            nodes
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, other)| other.is_some() && **other != Some(node))
+                                .map(move |(slot, _)| (level as f64, slot as f64))
+                        })
+                        .collect::<Vec<_>>();
+                    if others.is_empty() {
+                        continue;
+                    }
+                    let count = others.len() as f64;
+                    let centroid = others.into_iter().fold(
+                        (0., 0.),
+                        |(level, slot), (other_level, other_slot)| {
+                            (level + other_level / count, slot + other_slot / count)
+                        },
+                    );
+                    let distance = |(level, slot): (usize, usize)| {
+                        (level as f64 - centroid.0).powi(2) + (slot as f64 - centroid.1).powi(2)
+                    };
+
+                    let layers = self.layers.borrow();
+                    let target = (highest_level..=deepest_level)
+                        .flat_map(|level| {
+                            let nodes = &layers[level];
+                            (0..nodes.len())
+                                .filter(move |slot| nodes[*slot].is_none())
+                                .map(move |slot| (level, slot))
+                        })
+                        .min_by(|a, b| distance(*a).total_cmp(&distance(*b)));
+                    match target {
+                        Some(target) if distance(target) < distance((level_index, slot)) => target,
+                        _ => continue,
+                    }
+                }
+            };
+
+            self.layers.borrow_mut()[level_index][slot] = None;
+            let mut layers = self.layers.borrow_mut();
+            let level = &mut layers[target_level];
+            if target_slot < level.len() {
+                level[target_slot] = Some(node);
+            } else {
+                level.push(Some(node));
+            }
+            drop(layers);
+            self.insert_level_of_node(node, target_level);
+        }
+
+        self.fill_index_of_node();
+    }
+
+    /// Move the nodes with the highest degree of each level towards its center.
+    ///
+    /// A node is swapped with its neighbor towards the center, as long as the neighbor has a lower
+    /// degree and the swap doesn't increase the number of crossings.
+    fn center_hubs(&self) {
+        let edges = self
+            .graph
+            .edge_references()
+            .map(|edge| (edge.source().index() as u32, edge.target().index() as u32))
+            .collect::<Vec<_>>();
+        let level_ids = |level: &Vec<Option<NodeIndex>>| {
+            level
+                .iter()
+                .map(|node| node.map(|node| node.index() as u32))
+                .collect::<Vec<_>>()
+        };
+        let crossings_of_level = |level_index: usize| {
+            let layers = self.layers.borrow();
+            let level = level_ids(&layers[level_index]);
+            let upper = level_index.checked_sub(1).map_or(0, |upper| {
+                count_crossings(&level_ids(&layers[upper]), &level, &edges)
+            });
+            let lower = layers.get(level_index + 1).map_or(0, |lower| {
+                count_crossings(&level, &level_ids(lower), &edges)
+            });
+            upper + lower
+        };
+        let degree = |node: NodeIndex| self.graph.neighbors_undirected(node).count();
+
+        let num_levels = self.layers.borrow().len();
+        for level_index in 0..num_levels {
+            // slots of the level which contain a node
+            let slots = self.layers.borrow()[level_index]
+                .iter()
+                .enumerate()
+                .filter_map(|(slot, node)| node.map(|_| slot))
+                .collect::<Vec<_>>();
+            let center = (slots.len() as f64 - 1.) / 2.;
+            let mut hubs = self.layers.borrow()[level_index]
+                .iter()
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            hubs.sort_by_key(|node| std::cmp::Reverse(degree(*node)));
+
+            for hub in hubs {
+                loop {
+                    let position = slots
+                        .iter()
+                        .position(|slot| self.layers.borrow()[level_index][*slot] == Some(hub))
+                        .unwrap();
+                    if (position as f64 - center).abs() <= 0.5 {
+                        break;
+                    }
+                    let other_position = if (position as f64) < center {
+                        position + 1
+                    } else {
+                        position - 1
+                    };
+                    let (slot, other_slot) = (slots[position], slots[other_position]);
+                    let other = self.layers.borrow()[level_index][other_slot].unwrap();
+                    if degree(other) >= degree(hub) {
+                        break;
+                    }
+
+                    let crossings = crossings_of_level(level_index);
+                    self.layers.borrow_mut()[level_index].swap(slot, other_slot);
+                    if crossings_of_level(level_index) > crossings {
+                        self.layers.borrow_mut()[level_index].swap(slot, other_slot);
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.fill_index_of_node();
+    }
+
+    /// Shuffle the order of the nodes within each level, so crossing reduction starts from a
+    /// different ordering.
+    fn shuffle_levels(&self, seed: u64) {
+        let mut rng = Lcg(seed);
+        for level in self.layers.borrow_mut().iter_mut() {
+            rng.shuffle(level);
+        }
+    }
+
+    /// Reshape the levels, so that the ratio of width to height of the layout lies within
+    /// `[min_ratio, max_ratio]`.
+    ///
+    /// As long as the layout is too wide, the widest level is split into two consecutive levels.
+    /// As long as it is too tall, two adjacent levels without edges between them are merged.
+    /// Neither of these operations can reverse the direction of an edge. Since levels can't always
+    /// be merged, a layout may remain too tall.
+    fn fit_aspect_ratio_bounds(&self, min_ratio: f64, max_ratio: f64) {
+        self.layers.borrow_mut().retain(|level| !level.is_empty());
+        let ratio = |layers: &Vec<Vec<Option<NodeIndex>>>| {
+            let width = layers.iter().map(|level| level.len()).max().unwrap_or(0);
+            width as f64 / layers.len() as f64
+        };
+
+        while ratio(&self.layers.borrow()) > max_ratio {
+            let mut layers = self.layers.borrow_mut();
+            let (widest, _) = layers
+                .iter()
+                .enumerate()
+                .max_by_key(|(index, level)| (level.len(), std::cmp::Reverse(*index)))
+                .unwrap();
+            if layers[widest].len() < 2 {
+                break;
+            }
+            let half = layers[widest].len() / 2;
+            let lower = layers[widest].split_off(half);
+            layers.insert(widest + 1, lower);
+        }
+
+        while ratio(&self.layers.borrow()) < min_ratio {
+            let layers = self.layers.borrow().clone();
+            let connected = |upper: &Vec<Option<NodeIndex>>, lower: &Vec<Option<NodeIndex>>| {
+                upper.iter().flatten().any(|node| {
+                    lower
+                        .iter()
+                        .flatten()
+                        .any(|other| self.graph.contains_edge(*node, *other))
+                })
+            };
+            // merge the pair, which results in the narrowest level
+            let Some(upper) = (0..layers.len().saturating_sub(1))
+                .filter(|index| !connected(&layers[*index], &layers[index + 1]))
+                .min_by_key(|index| layers[*index].len() + layers[index + 1].len())
+            else {
+                break;
+            };
+            let mut layers = self.layers.borrow_mut();
+            let mut lower = layers.remove(upper + 1);
+            layers[upper].append(&mut lower);
+        }
+
+        for (level_index, level) in self.layers.borrow().iter().enumerate() {
+            for node in level.iter().flatten() {
+                self.insert_level_of_node(*node, level_index);
+            }
+        }
+    }
+
+    /// Returns one of the longest paths of the graph, starting at a source.
+    fn critical_path(&self) -> Vec<NodeIndex> {
+        let mut longest = HashMap::<NodeIndex, (usize, Option<NodeIndex>)>::new();
+        for node in toposort(&self.graph, None).unwrap() {
+            let entry = self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .map(|predecessor| (longest[&predecessor].0 + 1, Some(predecessor)))
+                .max_by_key(|(length, predecessor)| (*length, std::cmp::Reverse(*predecessor)))
+                .unwrap_or((0, None));
+            longest.insert(node, entry);
+        }
+
+        let mut end = longest
+            .iter()
+            .max_by_key(|(node, (length, _))| (*length, std::cmp::Reverse(**node)))
+            .map(|(node, _)| *node);
+        let mut path = Vec::new();
+        while let Some(node) = end {
+            path.push(node);
+            end = longest[&node].1;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Move the nodes of the critical path into the same slot of their levels, so that they form
+    /// a straight vertical line.
+    ///
+    /// The slot is the median of the slots the nodes had before. The other nodes of each level
+    /// keep their order.
+    fn straighten_critical_path(&self) {
+        let path = self.critical_path();
+        let mut slots = path
+            .iter()
+            .map(|node| self.get_index_of_node(node).unwrap())
+            .collect::<Vec<_>>();
+        slots.sort_unstable();
+        let Some(&target) = slots.get(slots.len() / 2) else {
+            return;
+        };
+
+        for node in path {
+            let level_index = self.get_level_of_node(&node).unwrap();
+            let level = &mut self.layers.borrow_mut()[level_index];
+            let slot = level.iter().position(|other| other == &Some(node)).unwrap();
+            level.remove(slot);
+            if level.len() < target {
+                level.resize(target, None);
+            }
+            level.insert(target, Some(node));
+        }
+        self.fill_index_of_node();
+    }
+
+    /// Add padding to each level, so that the levels are centered.
+    ///
+    /// The maximum length is computed from the current levels, after all nodes have been moved.
+    /// The padding is clamped at zero, so a level which is longer than the others doesn't cause an
+    /// underflow.
+    fn center_levels(&self) {
+        let max_level_length = self
+            .layers
+            .borrow()
+            .iter()
+            .map(|level| level.len())
+            .max()
+            .unwrap_or(0);
+        for level in self.layers.borrow_mut().iter_mut() {
+            let mut padding = vec![None; max_level_length.saturating_sub(level.len()) / 2 + 1];
+            padding.append(level);
+            padding.append(&mut vec![
+                None;
+                max_level_length.saturating_sub(level.len()) / 2
+            ]);
+            *level = padding;
+        }
+    }
+
+    #[inline(always)]
+    fn arrange_nodes_in_levels(&self) {
+        for node in self.get_visit_order() {
+            let node_level = self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .filter_map(|predecessor| {
+                    self.get_level_of_node(&predecessor).map(|level| level + 1)
+                })
+                .max()
+                .unwrap_or(0);
+            // ignore locks, which would put the node above one of its predecessors, except for
+            // inputs, which may have edges between each other
+            let node_level = match self.get_locked_level(&node) {
+                _ if self.is_input(&node) => 0,
+                Some(locked) => locked.max(node_level),
+                None => node_level.max(self.get_first_allowed_level(&node)),
+            };
+            self.insert_level_of_node(node, node_level);
+            self.add_node_to_level(node, node_level);
+        }
+    }
+
+    /// Returns the nodes in topological order, see [LayoutOptions::visit_priority].
+    ///
+    /// Without priorities, this is the order of [toposort].
+    fn get_visit_order(&self) -> Vec<NodeIndex> {
+        if self.options.visit_priority.is_empty() {
+            return toposort(&self.graph, None).unwrap();
+        }
+        let priority = |node: NodeIndex| {
+            // increment index by one for networkx
+            let id = node.index() as u32 + 1;
+            Reverse((
+                self.options.visit_priority.get(&id).copied().unwrap_or(0),
+                id,
+            ))
+        };
+
+        // Kahn's algorithm, always visiting the available node with the smallest key
+        let mut in_degrees = self
+            .graph
+            .node_indices()
+            .map(|node| {
+                let in_degree = self
+                    .graph
+                    .neighbors_directed(node, Direction::Incoming)
+                    .count();
+                (node, in_degree)
+            })
+            .collect::<HashMap<_, _>>();
+        let mut available = in_degrees
+            .iter()
+            .filter(|(_, in_degree)| **in_degree == 0)
+            .map(|(node, _)| (priority(*node), *node))
+            .collect::<BinaryHeap<_>>();
+        let mut order = Vec::new();
+        while let Some((_, node)) = available.pop() {
+            order.push(node);
+            for successor in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                let in_degree = in_degrees.get_mut(&successor).unwrap();
+                *in_degree -= 1;
+                if *in_degree == 0 {
+                    available.push((priority(successor), successor));
+                }
+            }
+        }
+        order
+    }
+
+    /// arrange vertically: moves nodes up as far as possible, by looking at successors
+    fn move_nodes_up(&self) {
+        for node in self.graph.node_identifiers().rev() {
+            self.move_node_in_level(node, Direction::Outgoing)
+        }
+    }
+
+    /// arrange vertically: move nodes down as far as possible, by looking at predecessors
+    fn move_nodes_down(&self) {
+        for node in self.graph.node_identifiers() {
+            self.move_node_in_level(node, Direction::Incoming)
+        }
+    }
+
+    /// Place each node at the midpoint of the range of levels it can be placed in.
+    ///
+    /// The range of a node starts directly below its lowest predecessor and ends at the deepest
+    /// level, that still leaves room for all of its successors.
+    fn move_nodes_to_midpoint(&self) {
+        let order = toposort(&self.graph, None).unwrap();
+        let num_levels = self.get_levels_spanned();
+
+        let mut deepest_level = HashMap::new();
+        for node in order.iter().rev() {
+            let level = self
+                .graph
+                .neighbors_directed(*node, Direction::Outgoing)
+                .map(|successor| deepest_level[&successor])
+                .min()
+                .unwrap_or(num_levels)
+                .saturating_sub(1);
+            let level = match self.get_locked_level(node) {
+                Some(_) => self.get_level_of_node(node).unwrap(),
+                None => level,
+            };
+            deepest_level.insert(*node, level);
+        }
+
+        for node in order {
+            if self.get_locked_level(&node).is_some() {
+                continue;
+            }
+            let highest_level = self
+                .graph
+                .neighbors_directed(node, Direction::Incoming)
+                .filter_map(|predecessor| self.get_level_of_node(&predecessor))
+                .max()
+                .map(|level| level + 1)
+                .unwrap_or(0);
+            self.move_node_to_level(node, (highest_level + deepest_level[&node]) / 2);
+        }
+    }
+
+    /// Arrange Nodes in level depending on the direction.
+    /// If the direction is Direction::Outgoing, it will try to move the nodes up as far as possible
+    /// otherwise it will try to move the nodes as far down as possible
+    #[inline(always)]
+    fn move_node_in_level(&self, node: NodeIndex, direction: Direction) {
+        if self.get_locked_level(&node).is_some() {
+            return;
+        }
+        let neighbor_levels = self
+            .graph
+            .neighbors_directed(node, direction)
+            .filter_map(|neighbor| self.get_level_of_node(&neighbor));
+        let new_node_level = match direction {
+            Direction::Outgoing => neighbor_levels
+                .min()
+                .unwrap_or(self.get_levels_spanned())
+                .saturating_sub(1), // move up
+            Direction::Incoming => neighbor_levels.max().map(|level| level + 1).unwrap_or(0), // move down
+        };
+
+        self.move_node_to_level(node, new_node_level);
+    }
+
+    fn move_node_to_level(&self, node: NodeIndex, new_node_level: usize) {
+        let new_node_level = new_node_level.max(self.get_first_allowed_level(&node));
+        let current_node_level = self.get_level_of_node(&node).unwrap();
+        if current_node_level == new_node_level {
+            return;
+        }
+
+        // remove the node from the old level, if it was already inserted before
+        self.layers.borrow_mut()[current_node_level].retain(|other_node| &Some(node) != other_node);
+        self.add_node_to_level(node, new_node_level);
+        self.insert_level_of_node(node, new_node_level);
+    }
+
+    fn add_node_to_level(&self, node: NodeIndex, node_level: usize) {
+        let mut layers = self.layers.borrow_mut();
+        // locked nodes might skip levels
+        if layers.len() <= node_level {
+            layers.resize(node_level + 1, Vec::new());
+        }
+        layers[node_level].push(Some(node));
+    }
+
+    /// Returns the successors of each node, which are in the same or the next level, indexed by
+    /// [NodeIndex::index].
+    fn get_direct_successors(&self) -> Vec<Vec<usize>> {
+        let mut successors = vec![Vec::new(); self.graph.node_bound()];
+        for node in self.graph.node_indices() {
+            let level = self.get_level_of_node(&node).unwrap();
+            successors[node.index()] = self
+                .graph
+                .neighbors_directed(node, Direction::Outgoing)
+                .filter(|n| self.get_level_of_node(n).unwrap().abs_diff(level) < 2)
+                .map(|n| n.index())
+                .collect();
+        }
+        successors
+    }
+
+    /// Returns the index of each node within its level, indexed by [NodeIndex::index].
+    fn get_index_array(&self) -> Vec<Option<usize>> {
+        (0..self.graph.node_bound())
+            .map(|index| self.get_index_of_node(&NodeIndex::new(index)))
+            .collect()
+    }
+
+    /// Swap the node with its left neighbor, if this reduces the crossings with their successors.
+    ///
+    /// `successors` and `index_of_node` are the arrays returned by
+    /// [GraphLayout::get_direct_successors] and [GraphLayout::get_index_array], the latter is kept
+    /// up to date, as well as the index stored in the graph.
+    fn reduce_crossings(
+        &self,
+        node: NodeIndex,
+        left: NodeIndex,
+        level_index: usize,
+        successors: &[Vec<usize>],
+        index_of_node: &mut [Option<usize>],
+    ) {
+        let left_successors = &successors[left.index()];
+        let mut cross_count = 0;
+        let mut cross_count_swap = 0;
+        for successor in &successors[node.index()] {
+            let successor_index = index_of_node[*successor];
+            cross_count += left_successors
+                .iter()
+                .filter(|l_s| index_of_node[**l_s] > successor_index)
+                .count();
+            cross_count_swap += left_successors
+                .iter()
+                .filter(|l_s| index_of_node[**l_s] < successor_index)
+                .count();
+        }
+        if cross_count_swap < cross_count {
+            let level = &mut self.layers.borrow_mut()[level_index];
+            let node_index = index_of_node[node.index()].unwrap();
+            let left_index = index_of_node[left.index()].unwrap();
+            level[node_index] = Some(left);
+            level[left_index] = Some(node);
+
+            index_of_node[left.index()] = Some(node_index);
+            index_of_node[node.index()] = Some(left_index);
+            self.insert_index_of_node(left, node_index);
+            self.insert_index_of_node(node, left_index);
+        }
+    }
+
+    fn swap_with_none_neighbors(&self, node: NodeIndex, level_index: usize) -> bool {
+        let node_index = self.layers.borrow()[level_index]
+            .iter()
+            .position(|n| n == &Some(node))
+            .unwrap();
+        assert_ne!(node_index, 0);
+        let left = if node_index == 0 {
+            None
+        } else {
+            self.layers.borrow()[level_index][node_index - 1]
+        };
+        let right = if node_index >= self.layers.borrow()[level_index].len() - 1 {
+            None
+        } else {
+            self.layers.borrow()[level_index][node_index + 1]
+        };
+
+        if left.is_some() && right.is_some() {
+            return true;
+        }
+
+        let neighbor_indices = self
+            .graph
+            .neighbors_undirected(node)
+            .filter(|neighbor| level_index.abs_diff(self.get_level_of_node(neighbor).unwrap()) < 2)
+            .map(|neighbor| self.get_index_of_node(&neighbor).unwrap());
+
+        let mean_neighbor_index = if let Some(mean) = barycenter(neighbor_indices) {
+            mean
+        } else {
+            return true;
+        };
+
+        // swap nodes for being closer to mean_neighbor_index
+        let swap_index = if mean_neighbor_index < node_index as f64 - 0.5 && left.is_none() {
+            node_index - 1
+        } else if mean_neighbor_index > node_index as f64 + 0.5 && right.is_none() {
+            node_index + 1
+        } else {
+            return true;
+        };
+
+        let level = &mut self.layers.borrow_mut()[level_index];
+        level[node_index] = None;
+
+        if swap_index > level.len() {
+            level.push(Some(node));
+        } else {
+            level[swap_index] = Some(node);
+        }
+
+        self.insert_index_of_node(node, swap_index);
+
+        false
+    }
+
+    /// Prints the graph to the console.
+    ///
+    /// Parameters:
+    /// - style: GraphPrintStyle, the style in which the nodes of the graph are displayed.
+    /// Can be either a specific char or the id of a node.
+    #[cfg(feature = "debug")]
+    fn print_layout(&self, style: GraphPrintStyle) {
+        for l in self.layers.borrow().iter() {
+            for n in l {
+                if let Some(n) = n {
+                    match &style {
+                        GraphPrintStyle::Node => print!("{:>2?}, ", n.index()),
+                        GraphPrintStyle::Char(c) => print!("{:}", c),
+                    }
+                } else {
+                    print!(" ");
+                }
+            }
+            println!("");
+        }
+    }
+}
+
+/// A linear congruential generator, used for shuffling reproducibly.
+pub(crate) struct Lcg(pub(crate) u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    /// Shuffle the items with the Fisher-Yates algorithm.
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, (self.next() % (i as u64 + 1)) as usize);
+        }
+    }
+}
+
+/// Calculate the barycenter (mean) of the given indices.
+///
+/// Returns `None` if there are no indices.
+fn barycenter(indices: impl Iterator<Item = usize>) -> Option<f64> {
+    let (sum, count) = indices.fold((0, 0), |(sum, count), index| (sum + index, count + 1));
+    if count == 0 {
+        None
+    } else {
+        Some(sum as f64 / count as f64)
+    }
+}
+
+/// Count the crossings of the edges between two consecutive levels.
+fn count_crossings(upper: &[Option<u32>], lower: &[Option<u32>], edges: &[(u32, u32)]) -> usize {
+    let index = |level: &[Option<u32>]| {
+        level
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| node.map(|node| (node, index as isize)))
+            .collect::<HashMap<_, _>>()
+    };
+    let (upper, lower) = (index(upper), index(lower));
+    let edges = edges
+        .iter()
+        .filter_map(|(tail, head)| {
+            match (
+                upper.get(tail),
+                lower.get(head),
+                upper.get(head),
+                lower.get(tail),
+            ) {
+                (Some(u), Some(l), _, _) | (_, _, Some(u), Some(l)) => Some((*u, *l)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut crossings = 0;
+    for (i, (u1, l1)) in edges.iter().enumerate() {
+        for (u2, l2) in &edges[i + 1..] {
+            if (u1 - u2).signum() * (l1 - l2).signum() < 0 {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+/// Compute the order of a level, which a single barycenter sweep would produce.
+///
+/// `upper` is the neighboring layer, where `None` denotes an empty slot. The nodes of the level
+/// are all nodes which are connected to a node of `upper` by one of the `edges`, regardless of the
+/// direction of the edge. Each of them is placed according to the mean index of its neighbors in
+/// `upper`. Nodes with the same barycenter keep the order in which they first appear in `edges`.
+pub fn barycenter_order(upper: &[Option<u32>], edges: &[(u32, u32)]) -> Vec<u32> {
+    let upper_index = upper
+        .iter()
+        .enumerate()
+        .filter_map(|(index, node)| node.map(|node| (node, index)))
+        .collect::<HashMap<_, _>>();
+    let mut level = Vec::new();
+    let mut neighbor_indices = HashMap::<u32, Vec<usize>>::new();
+
+    for (tail, head) in edges {
+        let (node, index) = match (upper_index.get(tail), upper_index.get(head)) {
+            (Some(index), None) => (*head, *index),
+            (None, Some(index)) => (*tail, *index),
+            _ => continue,
+        };
+        if !neighbor_indices.contains_key(&node) {
+            level.push(node);
+        }
+        neighbor_indices.entry(node).or_default().push(index);
+    }
+
+    let mut level = level
+        .into_iter()
+        .map(|node| {
+            let center = barycenter(neighbor_indices[&node].iter().copied()).unwrap();
+            (node, center)
+        })
+        .collect::<Vec<_>>();
+    level.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    level.into_iter().map(|(node, _)| node).collect()
+}
+
+/// Order the levels by a single sweep from the first to the last level.
+///
+/// Each level is ordered by the indices of the neighbors of its nodes in the level before it,
+/// using `ordering`. Nodes without neighbors in the level before keep their index, nodes with the
+/// same position keep their order. With [LevelOrdering::Adaptive], the heuristic is chosen for
+/// each level: both orderings are computed and the one with fewer crossings with the level before
+/// is kept, ties are won by the barycenter ordering.
+pub fn sweep_levels(levels: &mut [Vec<u32>], edges: &[(u32, u32)], ordering: LevelOrdering) {
+    for level in 1..levels.len() {
+        let (before, after) = levels.split_at_mut(level);
+        let (upper, lower) = (&before[level - 1], &mut after[0]);
+        *lower = match ordering {
+            LevelOrdering::Adaptive => {
+                let barycenter = order_level(upper, lower, edges, LevelOrdering::Barycenter);
+                let median = order_level(upper, lower, edges, LevelOrdering::Median);
+                let crossings = |lower: &[u32]| {
+                    let slots = |level: &[u32]| level.iter().copied().map(Some).collect::<Vec<_>>();
+                    count_crossings(&slots(upper), &slots(lower), edges)
+                };
+                if crossings(&median) < crossings(&barycenter) {
+                    median
+                } else {
+                    barycenter
+                }
+            }
+            ordering => order_level(upper, lower, edges, ordering),
+        };
+    }
+}
+
+/// Order the nodes of `lower` by the indices of their neighbors in `upper`, see [sweep_levels].
+fn order_level(
+    upper: &[u32],
+    lower: &[u32],
+    edges: &[(u32, u32)],
+    ordering: LevelOrdering,
+) -> Vec<u32> {
+    let upper_index = upper
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (*node, index))
+        .collect::<HashMap<_, _>>();
+    let mut neighbor_indices = HashMap::<u32, Vec<usize>>::new();
+    for (tail, head) in edges {
+        for (node, neighbor) in [(tail, head), (head, tail)] {
+            if let Some(index) = upper_index.get(neighbor) {
+                neighbor_indices.entry(*node).or_default().push(*index);
+            }
+        }
+    }
+
+    let mut level = Vec::new();
+    for (index, node) in lower.iter().enumerate() {
+        let position = match neighbor_indices.get_mut(node) {
+            None => index as f64,
+            Some(indices) if ordering == LevelOrdering::Median => median(indices),
+            Some(indices) => barycenter(indices.iter().copied()).unwrap(),
+        };
+        level.push((*node, position));
+    }
+    level.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    level.into_iter().map(|(node, _)| node).collect()
+}
+
+/// Calculate the median of the given indices, which must not be empty. For an even number of
+/// indices, it is the mean of the two middle ones.
+fn median(indices: &mut [usize]) -> f64 {
+    indices.sort_unstable();
+    let middle = indices.len() / 2;
+    if indices.len() % 2 == 1 {
+        indices[middle] as f64
+    } else {
+        (indices[middle - 1] + indices[middle]) as f64 / 2.
+    }
+}
+
+/// Specifies in which style a graph can be printed.
+/// Variants are a user specified char or the id of a node.
+#[cfg(feature = "debug")]
+enum GraphPrintStyle {
+    Node,
+    Char(char),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use super::{
+        barycenter_order, sweep_levels, FloatingPlacement, GraphLayout, LayoutMethod,
+        LevelOrdering, RankPassOrder,
+    };
+    use crate::{error::LayoutError, storage::NodeStorage};
+    use petgraph::stable_graph::NodeIndex;
+
+    #[test]
+    fn test_into_weakly_connected_components_two_single_nodes() {
+        let mut g = petgraph::stable_graph::StableDiGraph::<(), ()>::new();
+        g.add_node(());
+        g.add_node(());
+        assert_eq!(GraphLayout::into_weakly_connected_components(g).len(), 2);
+    }
+
+    #[test]
+    fn test_into_weakly_connected_compontents_two_components_correct_number_of_components() {
+        let mut g = petgraph::stable_graph::StableDiGraph::<(), ()>::new();
+        for _ in 0..4 {
+            g.add_node(());
+        }
+        g.add_edge(NodeIndex::from(1), NodeIndex::from(0), ());
+        g.add_edge(NodeIndex::from(2), NodeIndex::from(0), ());
+        assert_eq!(GraphLayout::into_weakly_connected_components(g).len(), 2);
+    }
+
+    #[test]
+    fn into_weakly_connected_components_two_components_correct_nodes_and_edges() {
+        let g = petgraph::stable_graph::StableDiGraph::<(), ()>::from_edges([
+            (0, 1),
+            (1, 2),
+            (3, 2),
+            (4, 5),
+            (4, 6),
+        ]);
+        let sgs = GraphLayout::into_weakly_connected_components(g);
+        assert_eq!(sgs.len(), 2);
+        assert!(sgs[0].contains_edge(0.into(), 1.into()));
+        assert!(sgs[0].contains_edge(1.into(), 2.into()));
+        assert!(sgs[0].contains_edge(3.into(), 2.into()));
+        assert!(sgs[1].contains_edge(4.into(), 5.into()));
+        assert!(sgs[1].contains_edge(4.into(), 6.into()));
+    }
+
+    /// Count the crossings between the edges of two consecutive levels in a layout.
+    fn count_layout_crossings(layout: &crate::NodePositions, edges: &[(u32, u32)]) -> usize {
+        let x = |id: &u32| layout[&(*id as usize)].0;
+        let mut crossings = 0;
+        for (i, (t1, h1)) in edges.iter().enumerate() {
+            for (t2, h2) in &edges[i + 1..] {
+                if (x(t1) - x(t2)).signum() * (x(h1) - x(h2)).signum() < 0 {
+                    crossings += 1;
+                }
+            }
+        }
+        crossings
+    }
+
+    #[test]
+    fn create_layers_bipartite_two_levels_no_crossings() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 6), (1, 5), (2, 5), (2, 4), (3, 4)];
+
+        let (layouts, _, heights) =
+            GraphLayout::create_layers_bipartite(&nodes, &edges, 40).unwrap();
+
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(heights, vec![2]);
+        let layout = &layouts[0];
+        assert!((1..=3).all(|n| layout[&n].1 == 0));
+        assert!((4..=6).all(|n| layout[&n].1 == -160));
+        assert_eq!(count_layout_crossings(layout, &edges), 0);
+    }
+
+    #[test]
+    fn create_layers_bipartite_rejects_non_bipartite_graph() {
+        let result = GraphLayout::create_layers_bipartite(&[1, 2, 3], &[(1, 2), (2, 3)], 40);
+
+        assert_eq!(
+            result.unwrap_err(),
+            LayoutError::InvalidGraph(
+                "Graph is not bipartite: node 2 has incoming and outgoing edges".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn sweep_levels_adaptive_beats_both_heuristics() {
+        let levels = vec![vec![1, 2, 3, 4], vec![5, 6, 7, 8, 9], vec![10, 11]];
+        let edges = [
+            (1, 6),
+            (1, 7),
+            (1, 8),
+            (2, 6),
+            (2, 7),
+            (3, 8),
+            (4, 7),
+            (5, 11),
+            (6, 10),
+            (6, 11),
+            (8, 10),
+            (9, 11),
+        ];
+        let sweep = |ordering| {
+            let mut levels = levels.clone();
+            sweep_levels(&mut levels, &edges, ordering);
+            let crossings = levels
+                .windows(2)
+                .map(|pair| {
+                    let slots = |level: &[u32]| level.iter().copied().map(Some).collect::<Vec<_>>();
+                    super::count_crossings(&slots(&pair[0]), &slots(&pair[1]), &edges)
+                })
+                .sum::<usize>();
+            (levels, crossings)
+        };
+
+        // the barycenter ordering is better for the second level, the median for the third one
+        let barycenter = sweep(LevelOrdering::Barycenter);
+        assert_eq!(barycenter.0[1..], [vec![5, 6, 8, 7, 9], vec![10, 11]]);
+        assert_eq!(barycenter.1, 7);
+        let median = sweep(LevelOrdering::Median);
+        assert_eq!(median.0[1..], [vec![5, 6, 7, 8, 9], vec![11, 10]]);
+        assert_eq!(median.1, 7);
+        let adaptive = sweep(LevelOrdering::Adaptive);
+        assert_eq!(adaptive.0[1..], [vec![5, 6, 8, 7, 9], vec![11, 10]]);
+        assert_eq!(adaptive.1, 6);
+    }
+
+    #[test]
+    fn barycenter_order_hand_computed_example() {
+        let upper = [Some(1), None, Some(2), Some(3)];
+        // barycenters: 10 -> (0 + 3) / 2, 11 -> 2, 12 -> 0, 13 -> 3, 14 -> (2 + 3) / 2
+        let edges = [
+            (1, 10),
+            (3, 10),
+            (2, 11),
+            (1, 12),
+            (14, 3),
+            (3, 13),
+            (2, 14),
+        ];
+        assert_eq!(barycenter_order(&upper, &edges), vec![12, 10, 11, 14, 13]);
+    }
+
+    #[test]
+    fn center_levels_level_grown_past_initial_max() {
+        let g =
+            petgraph::stable_graph::StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (3, 4)]);
+        let layout = GraphLayout::new(g, 40, false, Default::default());
+        layout.arrange_nodes_in_levels();
+        let initial_max = layout
+            .layers
+            .borrow()
+            .iter()
+            .map(|l| l.len())
+            .max()
+            .unwrap();
+        // simulate a level growing after the maximum was computed
+        layout.layers.borrow_mut()[1].push(Some(NodeIndex::from(5)));
+        assert!(layout.layers.borrow()[1].len() > initial_max);
+
+        layout.center_levels();
+
+        let layers = layout.layers.borrow();
+        let center = |level: &Vec<Option<NodeIndex>>| {
+            let occupied = level
+                .iter()
+                .enumerate()
+                .filter(|(_, n)| n.is_some())
+                .map(|(i, _)| i as f64)
+                .collect::<Vec<_>>();
+            occupied.iter().sum::<f64>() / occupied.len() as f64
+        };
+        assert_eq!(layers[0].iter().flatten().count(), 2);
+        assert_eq!(layers[1].iter().flatten().count(), 4);
+        assert_eq!(center(&layers[0]), center(&layers[1]));
+    }
+
+    #[test]
+    fn create_layers_from_stable_digraph_keeps_payloads_after_removal() {
+        let mut g = petgraph::stable_graph::StableDiGraph::<&str, ()>::new();
+        let a = g.add_node("a");
+        let b = g.add_node("b");
+        let c = g.add_node("c");
+        let d = g.add_node("d");
+        g.add_edge(a, b, ());
+        g.add_edge(c, d, ());
+        g.add_edge(a, d, ());
+        g.remove_node(b);
+
+        let layouts = GraphLayout::create_layers_from_stable_digraph(&g, 40, false).unwrap();
+
+        assert_eq!(layouts.len(), 1);
+        let layout = &layouts[0];
+        assert_eq!(layout.len(), 3);
+        assert!(!layout.contains_key(&b));
+        assert_eq!(*layout[&a].1, "a");
+        assert_eq!(*layout[&c].1, "c");
+        assert_eq!(*layout[&d].1, "d");
+        // d is a successor of a and c, so it needs to be placed below them
+        assert!(layout[&d].0 .1 < layout[&a].0 .1);
+        assert!(layout[&d].0 .1 < layout[&c].0 .1);
+    }
+
+    /// Node ids of each level, ordered from left to right.
+    fn level_orders(layout: &crate::NodePositions) -> Vec<Vec<usize>> {
+        let mut nodes = layout.iter().collect::<Vec<_>>();
+        nodes.sort_by_key(|(_, (x, y))| (-y, *x));
+        let mut levels: Vec<(isize, Vec<usize>)> = Vec::new();
+        for (id, (_, y)) in nodes {
+            match levels.last_mut() {
+                Some((level_y, level)) if level_y == y => level.push(*id),
+                _ => levels.push((*y, vec![*id])),
+            }
+        }
+        levels.into_iter().map(|(_, level)| level).collect()
+    }
+
+    #[test]
+    fn create_layers_with_options_seed_order_keeps_previous_order() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 4), (1, 5), (2, 5), (2, 6), (3, 6)];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let previous = layouts[0].clone();
+
+        // add a new node, which needs to be appended to its level
+        let nodes = [1, 2, 3, 4, 5, 6, 7];
+        let edges = [(1, 4), (1, 5), (2, 5), (2, 6), (3, 6), (1, 7)];
+        let options = crate::LayoutOptions {
+            seed_order_from: Some(previous.clone()),
+            ..Default::default()
+        };
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        let orders = level_orders(&layouts[0]);
+        assert_eq!(orders[1].last(), Some(&7));
+        let without_new_node = orders
+            .into_iter()
+            .map(|level| level.into_iter().filter(|id| *id != 7).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(without_new_node, level_orders(&previous));
+    }
+
+    #[test]
+    fn create_layers_with_options_super_endpoints_above_and_below() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 3), (2, 3), (3, 4), (5, 6)];
+        let options = crate::LayoutOptions {
+            super_endpoints: true,
+            ..Default::default()
+        };
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        // the super endpoints connect all components
+        assert_eq!(layouts.len(), 1);
+        let layout = &layouts[0];
+        let (super_source, super_sink) = GraphLayout::super_endpoint_ids(&nodes);
+        let levels = level_orders(layout);
+        assert_eq!(levels[0], vec![super_source as usize]);
+        assert_eq!(levels.last().unwrap(), &vec![super_sink as usize]);
+        // every real source is connected to the super source, so they are on the second level
+        let mut sources = levels[1].clone();
+        sources.sort();
+        assert_eq!(sources, vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn create_layers_with_options_storages_produce_same_layout() {
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = [
+            (1, 4),
+            (1, 5),
+            (2, 5),
+            (2, 7),
+            (3, 6),
+            (4, 8),
+            (5, 9),
+            (6, 9),
+            (7, 10),
+            (8, 11),
+            (9, 11),
+            (10, 12),
+            (3, 12),
+        ];
+        let with_storage = |storage| {
+            let options = crate::LayoutOptions {
+                storage,
+                ..Default::default()
+            };
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap()
+        };
+
+        assert_eq!(
+            with_storage(NodeStorage::HashMap),
+            with_storage(NodeStorage::BTreeMap)
+        );
+        assert_eq!(
+            with_storage(NodeStorage::HashMap),
+            with_storage(NodeStorage::Vec)
+        );
+    }
+
+    #[test]
+    fn create_preview_layers_positions_all_nodes() {
+        let nodes = (1..=10).collect::<Vec<_>>();
+        let edges = [
+            (1, 3),
+            (2, 3),
+            (2, 4),
+            (3, 5),
+            (4, 5),
+            (1, 6),
+            (5, 7),
+            (6, 7),
+            (9, 10),
+        ];
+
+        let (layouts, widths, heights) =
+            GraphLayout::create_preview_layers(&nodes, &edges, 40).unwrap();
+
+        // 8 is a single node and 9, 10 are a separate component
+        assert_eq!(layouts.len(), 3);
+        assert_eq!(widths.len(), 3);
+        assert_eq!(heights.len(), 3);
+        let mut positioned = layouts
+            .iter()
+            .flat_map(|layout| layout.keys().copied())
+            .collect::<Vec<_>>();
+        positioned.sort();
+        assert_eq!(positioned, (1..=10).collect::<Vec<_>>());
+        for layout in &layouts {
+            for (tail, head) in edges {
+                if let (Some(tail), Some(head)) =
+                    (layout.get(&(tail as usize)), layout.get(&(head as usize)))
+                {
+                    assert!(head.1 < tail.1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn create_layers_with_options_balanced_pass_order_shortest_edges() {
+        // 1 -> 2 -> 3 -> 4 -> 5 is the longest path, 6 pulls towards its successors and 8 towards
+        // its predecessors
+        let nodes = (1..=10).collect::<Vec<_>>();
+        let edges = [
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (4, 7),
+            (1, 6),
+            (6, 5),
+            (6, 7),
+            (1, 8),
+            (9, 8),
+            (10, 8),
+            (8, 4),
+        ];
+        let total_edge_span = |rank_pass_order| {
+            let options = crate::LayoutOptions {
+                rank_pass_order,
+                ..Default::default()
+            };
+            let (layouts, ..) =
+                GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options)
+                    .unwrap();
+            edges
+                .iter()
+                .map(|(tail, head)| {
+                    layouts[0][&(*tail as usize)].1 - layouts[0][&(*head as usize)].1
+                })
+                .sum::<isize>()
+                / 160
+        };
+
+        let up_then_down = total_edge_span(RankPassOrder::UpThenDown);
+        let down_then_up = total_edge_span(RankPassOrder::DownThenUp);
+        let balanced = total_edge_span(RankPassOrder::Balanced);
+        assert!(balanced < up_then_down);
+        assert!(balanced < down_then_up);
+    }
+
+    #[test]
+    fn create_layers_with_options_center_hubs_moves_hub_to_center() {
+        let nodes = (1..=10).collect::<Vec<_>>();
+        let edges = [
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (1, 5),
+            (1, 6),
+            (6, 7),
+            (6, 8),
+            (6, 9),
+            (6, 10),
+        ];
+        let options = crate::LayoutOptions {
+            center_hubs: true,
+            ..Default::default()
+        };
+
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        let levels = level_orders(&layouts[0]);
+        assert_eq!(levels[1].len(), 5);
+        assert_eq!(levels[1][2], 6);
+    }
+
+    #[test]
+    fn create_layers_with_options_floating_node_relocated() {
+        // 5 is a source, but its only neighbor is three levels below it
+        let nodes = (1..=7).collect::<Vec<_>>();
+        let edges = [(1, 2), (2, 3), (3, 4), (5, 4), (6, 2), (7, 2)];
+        let layout_with = |floating_placement| {
+            let options = crate::LayoutOptions {
+                floating_placement,
+                ..Default::default()
+            };
+            let (mut layouts, ..) =
+                GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options)
+                    .unwrap();
+            layouts.remove(0)
+        };
+
+        let layout = layout_with(FloatingPlacement::EndOfShortestLevel);
+        // the first level contains 1, 6 and 7, the second only 2
+        assert_eq!(layout[&5].1, layout[&2].1);
+        assert!(layout[&5].0 > layout[&2].0);
+
+        let layout = layout_with(FloatingPlacement::Centroid);
+        // the center of the other nodes lies at 2, which is taken, so it is placed next to it
+        assert_eq!(layout[&5].1, layout[&2].1);
+        assert_eq!(layout[&5].0.abs_diff(layout[&2].0), 160);
+    }
+
+    #[test]
+    fn create_layers_with_options_wide_graph_within_aspect_ratio() {
+        // a star with 20 leaves is 20 nodes wide, but only 2 levels tall
+        let nodes = (1..=21).collect::<Vec<_>>();
+        let edges = (2..=21).map(|leaf| (1, leaf)).collect::<Vec<_>>();
+        let options = crate::LayoutOptions {
+            aspect_ratio_bounds: Some((0.5, 2.)),
+            ..Default::default()
+        };
+
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        let levels = level_orders(&layouts[0]);
+        let width = levels.iter().map(|level| level.len()).max().unwrap();
+        let ratio = width as f64 / levels.len() as f64;
+        assert!((0.5..=2.).contains(&ratio), "ratio {ratio} out of bounds");
+        for (tail, head) in edges {
+            assert!(layouts[0][&(head as usize)].1 < layouts[0][&(tail as usize)].1);
+        }
+    }
+
+    #[test]
+    fn placement_influences_only_adjacent_levels() {
+        // 1 -> 2 -> 3 -> 4 and 1 -> 4, which spans three levels. The source 5 is placed in the
+        // first level, so it is too far away from 3 as well
+        let nodes = [1, 2, 3, 4, 5];
+        let edges = [(1, 2), (2, 3), (3, 4), (1, 4), (5, 3)];
+
+        let influences = GraphLayout::placement_influences(&nodes, &edges, 40).unwrap();
+
+        assert_eq!(
+            influences,
+            HashMap::from([
+                (1, vec![2]),
+                (2, vec![1, 3]),
+                (3, vec![2, 4]),
+                (4, vec![3]),
+                (5, vec![]),
+            ])
+        );
+    }
+
+    #[test]
+    fn create_layers_with_options_sinks_in_last_row() {
+        // 5 is a sink, which would be placed in the second level
+        let nodes = [1, 2, 3, 4, 5];
+        let edges = [(1, 2), (2, 3), (3, 4), (1, 5)];
+        let layout_with = |sinks_in_last_row| {
+            let options = crate::LayoutOptions {
+                sinks_in_last_row,
+                ..Default::default()
+            };
+            let (mut layouts, ..) =
+                GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options)
+                    .unwrap();
+            layouts.remove(0)
+        };
+
+        let layout = layout_with(false);
+        assert_eq!(layout[&5].1, layout[&2].1);
+
+        let layout = layout_with(true);
+        assert_eq!(layout[&5].1, layout[&4].1);
+        assert!(layout[&5].1 < layout[&1].1);
+    }
+
+    #[test]
+    fn create_layers_with_options_locked_level_kept() {
+        // 8 is a source, which would be placed in the first level
+        let nodes = [1, 2, 3, 4, 5, 6, 7, 8];
+        let edges = [(1, 2), (2, 3), (3, 4), (1, 5), (5, 6), (6, 7), (8, 7)];
+        let options = crate::LayoutOptions {
+            locked_levels: HashMap::from([(8, 2)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            GraphLayout::check_locked_levels(&nodes, &edges, &options.locked_levels),
+            Ok(())
+        );
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+        let layout = &layouts[0];
+
+        assert_eq!(layout[&8].1, layout[&3].1);
+        // 8 is still placed next to 6, so its edge doesn't cross the one of 3
+        assert_eq!(crate::analysis::count_layout_crossings(layout, &edges), 0);
+    }
+
+    #[test]
+    fn check_locked_levels_above_predecessor() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (2, 3), (3, 4)];
+
+        assert_eq!(
+            GraphLayout::check_locked_levels(&nodes, &edges, &HashMap::from([(4, 1)])),
+            Err(LayoutError::InvalidGraph(
+                "Node 4 can't be locked to level 1, its predecessors need at least level 3"
+                    .to_string()
+            ))
+        );
+        assert!(GraphLayout::check_locked_levels(&nodes, &edges, &HashMap::from([(2, 1)])).is_ok());
+    }
+
+    #[test]
+    fn create_layers_with_options_inputs_in_first_level() {
+        // 2 is a source, which would be placed in the first level
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 4), (2, 4), (3, 5), (1, 5), (4, 6), (5, 6)];
+        let options = crate::LayoutOptions {
+            inputs: vec![3, 1],
+            ..Default::default()
+        };
+        assert_eq!(
+            GraphLayout::check_inputs(&nodes, &edges, &options.inputs),
+            Ok(())
+        );
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        assert_eq!(level_orders(&layouts[0])[0], vec![3, 1]);
+    }
+
+    #[test]
+    fn check_inputs_incoming_edge_from_non_input() {
+        let nodes = [1, 2, 3];
+        let edges = [(1, 2), (2, 3)];
+
+        assert_eq!(
+            GraphLayout::check_inputs(&nodes, &edges, &[1, 3]),
+            Err(LayoutError::InvalidGraph(
+                "Input 3 has an incoming edge from 2, which is not an input".to_string()
+            ))
+        );
+        assert!(GraphLayout::check_inputs(&nodes, &edges, &[1, 2]).is_ok());
+    }
+
+    #[test]
+    fn create_layers_with_options_dedup_components_reuses_layout() {
+        // two components with the same shape, offset by 5
+        let nodes = (1..=10).collect::<Vec<_>>();
+        let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (4, 5)];
+        let edges = edges
+            .iter()
+            .chain(&edges.map(|(tail, head)| (tail + 5, head + 5)))
+            .copied()
+            .collect::<Vec<_>>();
+        let components = GraphLayout::split_components(
+            &nodes,
+            &edges,
+            40,
+            false,
+            crate::LayoutOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            components[0].component_shape().1,
+            components[1].component_shape().1
+        );
+
+        let options = crate::LayoutOptions {
+            dedup_components: true,
+            ..Default::default()
+        };
+        let (layouts, widths, heights) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        assert_eq!(
+            (layouts.clone(), widths.clone(), heights.clone()),
+            GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap()
+        );
+        assert!((1..=5).all(|id| layouts[0][&id] == layouts[1][&(id + 5)]));
+        assert_eq!((widths[0], heights[0]), (widths[1], heights[1]));
+    }
+
+    #[test]
+    fn create_layers_trivial_threshold_selects_path() {
+        let nodes = (1..=5).collect::<Vec<_>>();
+        let edges = [(1, 2), (3, 4), (3, 5)];
+        let components =
+            GraphLayout::split_components(&nodes, &edges, 40, false, Default::default()).unwrap();
+        assert!(components[0].is_trivial());
+        assert!(!components[1].is_trivial());
+
+        let (layouts, widths, heights) =
+            GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        assert_eq!(level_orders(&layouts[0]), vec![vec![1], vec![2]]);
+        assert_eq!((widths[0], heights[0]), (1, 2));
+        let levels = level_orders(&layouts[1]);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0], vec![3]);
+        assert!(edges.iter().all(|(tail, head)| {
+            let layout = &layouts[(*tail > 2) as usize];
+            layout[&(*tail as usize)].1 > layout[&(*head as usize)].1
+        }));
+        // the full alignment gives the same layout for the tiny component
+        let options = crate::LayoutOptions {
+            trivial_threshold: 0,
+            ..Default::default()
+        };
+        let (full_layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+        assert_eq!(full_layouts, layouts);
+    }
+
+    #[test]
+    fn check_node_size_negative() {
+        assert_eq!(
+            GraphLayout::check_node_size(-40),
+            Err(LayoutError::InvalidGraph(
+                "Node size must be positive, got -40".to_string()
+            ))
+        );
+        assert!(GraphLayout::check_node_size(40).is_ok());
+    }
+
+    #[test]
+    fn check_options_zero_spacing() {
+        let options = crate::LayoutOptions {
+            level_spacing: HashMap::from([(2, 0)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            GraphLayout::check_options(&options),
+            Err(LayoutError::InvalidGraph(
+                "Spacing of level 2 must be positive, got 0".to_string()
+            ))
+        );
+        let options = crate::LayoutOptions {
+            snap_to_grid: Some(0),
+            ..Default::default()
+        };
+        assert!(GraphLayout::check_options(&options).is_err());
+        assert!(GraphLayout::check_options(&Default::default()).is_ok());
+    }
+
+    #[test]
+    fn check_options_nan_ratio() {
+        let options = crate::LayoutOptions {
+            aspect_ratio_bounds: Some((f64::NAN, 2.)),
+            ..Default::default()
+        };
+        assert!(GraphLayout::check_options(&options).is_err());
+        let options = crate::LayoutOptions {
+            aspect_ratio_bounds: Some((0.5, f64::INFINITY)),
+            ..Default::default()
+        };
+        assert!(GraphLayout::check_options(&options).is_err());
+    }
+
+    #[test]
+    fn sugiyama_config_nan_dummy_size() {
+        let new = |vertex_size, dummy_size| {
+            crate::SugiyamaConfig::new(
+                vertex_size,
+                true,
+                dummy_size,
+                "barycenter",
+                false,
+                "minimize",
+                None,
+                None,
+            )
+        };
+        assert_eq!(
+            new(40, f64::NAN).err().map(|err| err.to_string()),
+            Some("ValueError: Dummy size must be positive and finite, got NaN".to_string())
+        );
+        assert!(new(0, 1.).is_err());
+        assert!(new(40, 1.).is_ok());
+    }
+
+    #[test]
+    fn sugiyama_config_setters_reject_invalid_values() {
+        let mut config = crate::SugiyamaConfig::default();
+
+        assert_eq!(
+            config.set_vertex_size(-40).err().map(|err| err.to_string()),
+            Some("ValueError: Node size must be positive, got -40".to_string())
+        );
+        assert!(config.set_vertex_spacing(Some(0)).is_err());
+        assert!(config.set_dummy_size(f64::INFINITY).is_err());
+        assert_eq!(config.vertex_size, 40);
+        assert_eq!(config.vertex_spacing, None);
+        assert_eq!(config.dummy_size, 1.);
+
+        config.set_vertex_size(20).unwrap();
+        config.set_vertex_spacing(Some(50)).unwrap();
+        assert_eq!((config.vertex_size, config.vertex_spacing), (20, Some(50)));
+    }
+
+    #[test]
+    fn sugiyama_config_vertex_spacing_overrides_vertex_size() {
+        let config = |vertex_spacing| crate::SugiyamaConfig {
+            vertex_spacing,
+            ..Default::default()
+        };
+        let spacing = |vertex_spacing| {
+            rust_sugiyama::configure::Config::from(config(vertex_spacing)).vertex_spacing
+        };
+
+        assert_eq!(spacing(None), 160);
+        assert_eq!(spacing(Some(50)), 50);
+        assert!(config(Some(0)).check().is_err());
+
+        let sibling_distance = |vertex_spacing| {
+            let (layouts, ..) = crate::create_layouts_sugiyama(
+                vec![1, 2, 3],
+                vec![(1, 2), (1, 3)],
+                config(vertex_spacing),
+            );
+            (layouts[0][&2].0 - layouts[0][&3].0).abs()
+        };
+        assert_eq!(
+            sibling_distance(Some(50)) * 160,
+            sibling_distance(None) * 50
+        );
+    }
+
+    #[test]
+    fn create_layers_sampled_connected_and_capped() {
+        // 1 is the hub, 11 and 12 form a separate component
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let mut edges = (2..=6).map(|id| (1, id)).collect::<Vec<_>>();
+        edges.extend([(6, 7), (7, 8), (8, 9), (9, 10), (11, 12)]);
+
+        let ((layouts, ..), included) =
+            GraphLayout::create_layers_sampled(&nodes, &edges, 4, 40).unwrap();
+
+        assert_eq!(included.len(), 4);
+        assert!(included.contains(&1));
+        assert_eq!(layouts.len(), 1);
+        let mut ids = layouts[0].keys().map(|id| *id as u32).collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, included);
+        // every included node is connected to the hub
+        assert!(included
+            .iter()
+            .all(|id| *id == 1 || edges.contains(&(1, *id))));
+
+        let (_, included) = GraphLayout::create_layers_sampled(&nodes, &edges, 12, 40).unwrap();
+        assert_eq!(included, nodes);
+    }
+
+    #[test]
+    fn create_layers_with_options_target_height_compresses_levels() {
+        let nodes = (1..=8).collect::<Vec<_>>();
+        let edges = (1..8).map(|id| (id, id + 1)).collect::<Vec<_>>();
+        let options = crate::LayoutOptions {
+            target_height: Some(560),
+            ..Default::default()
+        };
+
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        let ys = (1..=8).map(|id| layouts[0][&id].1).collect::<Vec<_>>();
+        assert_eq!(ys[0] - ys[7], 560);
+        // the levels keep their order with a uniform gap of 80 instead of 160
+        assert!(ys.windows(2).all(|ys| ys[0] - ys[1] == 80));
+
+        // the gaps are never smaller than the nodes
+        let options = crate::LayoutOptions {
+            target_height: Some(70),
+            ..Default::default()
+        };
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+        assert_eq!(layouts[0][&1].1 - layouts[0][&8].1, 7 * 40);
+    }
+
+    #[test]
+    fn create_layers_auto_first_row_chooses_first_row() {
+        // the balanced pass order places the sources 3 and 5 next to 2 in the second level, where
+        // their edges cross the ones of 2
+        let nodes = [1, 2, 3, 4, 5, 6, 7, 8];
+        let edges = [(1, 2), (2, 4), (2, 6), (2, 8), (3, 6), (4, 7), (5, 7)];
+        let options = crate::LayoutOptions {
+            rank_pass_order: RankPassOrder::Balanced,
+            ..Default::default()
+        };
+
+        let (layouts, global_tasks_in_first_row) =
+            GraphLayout::create_layers_auto_first_row(&nodes, &edges, 40, options.clone()).unwrap();
+
+        assert!(global_tasks_in_first_row);
+        assert_eq!(
+            layouts,
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, true, options).unwrap()
+        );
+    }
+
+    #[test]
+    fn create_layers_with_options_level_spacing_pushes_levels_down() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (2, 3), (3, 4)];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let options = crate::LayoutOptions {
+            level_spacing: HashMap::from([(2, 260)]),
+            ..Default::default()
+        };
+        let (spaced_layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        // the default gap is 4 * 40
+        for (node, extra) in [(1, 0), (2, 0), (3, 100), (4, 100)] {
+            assert_eq!(spaced_layouts[0][&node].0, layouts[0][&node].0);
+            assert_eq!(spaced_layouts[0][&node].1, layouts[0][&node].1 - extra);
+        }
+    }
+
+    #[test]
+    fn create_layers_nodes_inferred_from_edges() {
+        let edges = [(1, 2), (1, 3), (3, 4)];
+
+        let (layouts, widths, heights) =
+            GraphLayout::create_layers(&[], &edges, 40, false).unwrap();
+
+        assert_eq!(
+            (layouts.clone(), widths, heights),
+            GraphLayout::create_layers(&[1, 2, 3, 4], &edges, 40, false).unwrap()
+        );
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].len(), 4);
+    }
+
+    #[test]
+    fn create_layers_with_options_screen_coords_y_increases_downward() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (1, 3), (3, 4)];
+        let options = crate::LayoutOptions {
+            screen_coords: true,
+            ..Default::default()
+        };
+
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        assert_eq!(layouts[0][&1].1, 0);
+        assert_eq!(layouts[0][&2].1, 160);
+        assert_eq!(layouts[0][&3].1, 160);
+        assert_eq!(layouts[0][&4].1, 320);
+    }
+
+    #[test]
+    fn minimize_crossings_crossing_counts_unchanged() {
+        // the crossings of random graphs, counted before precomputing the successors and indices
+        // in minimize_crossings
+        let crossings = (0..20)
+            .map(|seed| {
+                let mut rng = super::Lcg(seed);
+                let node_count = rng.next() % 40 + 10;
+                let nodes = (1..=node_count as u32).collect::<Vec<_>>();
+                let mut edges = Vec::new();
+                for tail in 1..=node_count as u32 {
+                    for head in tail + 1..=node_count as u32 {
+                        if rng.next() % 100 < 8 {
+                            edges.push((tail, head));
+                        }
+                    }
+                }
+                let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+                layouts
+                    .iter()
+                    .map(|layout| crate::analysis::count_layout_crossings(layout, &edges))
+                    .sum::<usize>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            crossings,
+            [0, 23, 101, 114, 77, 286, 258, 713, 1, 0, 13, 18, 37, 56, 57, 211, 297, 0, 699, 0]
+        );
     }
 
-    #[inline(always)]
-    fn arrange_nodes_in_levels(&self) {
-        for node in toposort(&self.graph, None).unwrap() {
-            let node_level = self
-                .graph
-                .neighbors_directed(node, Direction::Incoming)
-                .filter_map(|predecessor| {
-                    self.get_level_of_node(&predecessor).map(|level| level + 1)
-                })
-                .max()
-                .unwrap_or(0);
-            self.insert_level_of_node(node, node_level);
-            self.add_node_to_level(node, node_level);
-        }
+    #[test]
+    fn reading_order_matches_layout() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 3), (1, 4), (2, 4), (3, 5), (4, 6)];
+
+        let order = GraphLayout::reading_order(&nodes, &edges, Default::default()).unwrap();
+
+        // sweep the layout from the top left to the bottom right
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let mut positions = layouts[0].iter().collect::<Vec<_>>();
+        positions.sort_by_key(|(_, (x, y))| (-y, *x));
+        let expected = positions
+            .into_iter()
+            .map(|(id, _)| *id as u32)
+            .collect::<Vec<_>>();
+        assert_eq!(order, expected);
     }
 
-    /// Arrange Nodes in level depending on the direction.
-    /// If the direction is Direction::Outgoing, it will try to move the nodes up as far as possible
-    /// otherwise it will try to move the nodes as far down as possible
-    #[inline(always)]
-    fn move_node_in_level(&self, node: NodeIndex, direction: Direction) {
-        let neighbor_levels = self
-            .graph
-            .neighbors_directed(node, direction)
-            .filter_map(|neighbor| self.get_level_of_node(&neighbor));
-        let new_node_level = match direction {
-            Direction::Outgoing => neighbor_levels
-                .min()
-                .unwrap_or(self.get_nums_of_level())
-                .saturating_sub(1), // move up
-            Direction::Incoming => neighbor_levels.max().map(|level| level + 1).unwrap_or(0), // move down
+    #[test]
+    fn create_layers_with_options_critical_path_straight() {
+        // 1 -> 2 -> 3 -> 4 is the longest path
+        let nodes = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let edges = [
+            (1, 2),
+            (2, 3),
+            (3, 4),
+            (5, 2),
+            (5, 6),
+            (6, 4),
+            (7, 3),
+            (8, 9),
+            (1, 9),
+            (9, 4),
+        ];
+        let options = crate::LayoutOptions {
+            straighten_critical_path: true,
+            ..Default::default()
         };
 
-        let current_node_level = self.get_level_of_node(&node).unwrap();
-        if current_node_level == new_node_level {
-            return;
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+        let layout = &layouts[0];
+
+        for node in [2, 3, 4] {
+            assert_eq!(layout[&node].0, layout[&1].0);
         }
+        let mut positions = layout.values().collect::<Vec<_>>();
+        positions.sort();
+        positions.dedup();
+        assert_eq!(positions.len(), nodes.len());
+    }
 
-        // remove the node from the old level, if it was already inserted before
-        self.layers.borrow_mut()[current_node_level].retain(|other_node| &Some(node) != other_node);
-        self.add_node_to_level(node, new_node_level);
-        self.insert_level_of_node(node, new_node_level);
+    #[test]
+    fn create_layers_for_component_same_as_general() {
+        let nodes = [1, 2, 3, 4, 5];
+        let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (5, 3)];
+
+        let (layouts, widths, heights) =
+            GraphLayout::create_layers(&nodes, &edges, 40, true).unwrap();
+
+        assert_eq!(
+            GraphLayout::create_layers_for_component(&nodes, &edges, 40, true),
+            Ok((layouts[0].clone(), widths[0], heights[0]))
+        );
     }
 
-    fn add_node_to_level(&self, node: NodeIndex, node_level: usize) {
-        if let Some(level) = self.layers.borrow_mut().get_mut(node_level) {
-            level.push(Some(node));
-            return;
-        }
-        self.layers.borrow_mut().push(vec![Some(node)]);
+    #[test]
+    fn create_layers_for_component_disconnected() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (3, 4)];
+
+        assert_eq!(
+            GraphLayout::create_layers_for_component(&nodes, &edges, 40, false),
+            Err(LayoutError::InvalidGraph(
+                "Graph is not connected: node 3 is not connected to node 1".to_string()
+            ))
+        );
     }
 
-    fn reduce_crossings(&self, node: NodeIndex, left: NodeIndex, level_index: usize) {
-        let get_direct_successors = |node| {
-            self.graph
-                .neighbors_directed(node, Direction::Outgoing)
-                .filter(|n| self.get_level_of_node(n).unwrap().abs_diff(level_index) < 2)
-                .collect::<Vec<_>>()
+    #[test]
+    fn create_layers_condensed_cycle_is_one_super_node() {
+        let nodes = [1, 2, 3, 4, 5];
+        let edges = [(1, 2), (2, 3), (3, 1), (3, 4), (5, 1)];
+
+        let ((layouts, ..), members) =
+            GraphLayout::create_layers_condensed(&nodes, &edges, 40).unwrap();
+
+        assert_eq!(members.len(), 3);
+        let super_node = |ids: Vec<u32>| {
+            let (super_node, _) = members.iter().find(|(_, m)| **m == ids).unwrap();
+            *super_node as usize
         };
+        let (cycle, source, sink) = (
+            super_node(vec![1, 2, 3]),
+            super_node(vec![5]),
+            super_node(vec![4]),
+        );
+        assert_eq!(layouts.len(), 1);
+        let layout = &layouts[0];
+        assert_eq!(layout.len(), 3);
+        assert!(layout[&source].1 > layout[&cycle].1);
+        assert!(layout[&cycle].1 > layout[&sink].1);
+    }
 
-        let successors = get_direct_successors(node);
-        let left_successors = get_direct_successors(left);
-        let mut cross_count = 0;
-        let mut cross_count_swap = 0;
-        for successor in successors {
-            cross_count += left_successors
-                .iter()
-                .filter(|l_s| self.get_index_of_node(l_s) > self.get_index_of_node(&successor))
-                .count();
-            cross_count_swap += left_successors
-                .iter()
-                .filter(|l_s| self.get_index_of_node(l_s) < self.get_index_of_node(&successor))
-                .count();
-        }
-        if cross_count_swap < cross_count {
-            let level = &mut self.layers.borrow_mut()[level_index];
-            let node_index = self.get_index_of_node(&node).unwrap();
-            let left_index = self.get_index_of_node(&left).unwrap();
-            level[node_index] = Some(left);
-            level[left_index] = Some(node);
+    #[test]
+    fn node_degrees_count_the_laid_out_graph() {
+        let nodes = [1, 2, 3, 4, 5];
+        // the parallel edge is counted once, the self loop not at all
+        let edges = [(1, 2), (1, 2), (1, 3), (2, 3), (4, 3), (5, 5)];
 
-            self.insert_index_of_node(left, node_index);
-            self.insert_index_of_node(node, left_index);
-        }
+        assert_eq!(
+            GraphLayout::node_degrees(&nodes, &edges),
+            HashMap::from([
+                (1, (0, 2)),
+                (2, (1, 1)),
+                (3, (3, 0)),
+                (4, (0, 1)),
+                (5, (0, 0)),
+            ])
+        );
     }
 
-    fn swap_with_none_neighbors(&self, node: NodeIndex, level_index: usize) -> bool {
-        let node_index = self.layers.borrow()[level_index]
-            .iter()
-            .position(|n| n == &Some(node))
-            .unwrap();
-        assert_ne!(node_index, 0);
-        let left = if node_index == 0 {
-            None
-        } else {
-            self.layers.borrow()[level_index][node_index - 1]
+    #[test]
+    fn create_layers_condensed_rejects_dangling_edges_and_node_size() {
+        let invalid = |msg: &str| Err(LayoutError::InvalidGraph(msg.to_string()));
+
+        assert_eq!(
+            GraphLayout::create_layers_condensed(&[1, 2], &[(1, 2), (2, 3)], 40),
+            invalid("Edge refers to unknown node 3")
+        );
+        // inferred nodes start at 1
+        assert_eq!(
+            GraphLayout::create_layers_condensed(&[], &[(0, 1)], 40),
+            invalid("Edge refers to unknown node 0")
+        );
+        assert_eq!(
+            GraphLayout::create_layers_condensed(&[1, 2], &[(1, 2)], 0),
+            invalid("Node size must be positive, got 0")
+        );
+    }
+
+    #[test]
+    fn create_layers_with_options_invisible_edges_constrain_levels() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (3, 4)];
+        let options = crate::LayoutOptions {
+            invisible_edges: vec![(2, 3)],
+            ..Default::default()
         };
-        let right = if node_index >= self.layers.borrow()[level_index].len() - 1 {
-            None
-        } else {
-            self.layers.borrow()[level_index][node_index + 1]
+
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
+
+        // without the invisible edge, 1 and 3 would be two components in the first level
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(
+            level_orders(&layouts[0]),
+            vec![vec![1], vec![2], vec![3], vec![4]]
+        );
+        let dot = crate::export::to_dot(layouts[0].clone(), edges.to_vec(), None).unwrap();
+        assert!(dot.contains("1 -> 2;") && dot.contains("3 -> 4;"));
+        assert!(!dot.contains("2 -> 3"));
+        let svg = crate::export::to_svg(layouts[0].clone(), edges.to_vec(), 40);
+        assert_eq!(svg.matches("<line").count(), 2);
+    }
+
+    #[test]
+    fn create_layers_with_options_level_y_overrides_levels() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (2, 3), (3, 4)];
+        let options = crate::LayoutOptions {
+            level_y: HashMap::from([(1, -1000), (2, -1050)]),
+            ..Default::default()
         };
 
-        if left.is_some() && right.is_some() {
-            return true;
-        }
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
 
-        let neighbor_indices: Vec<f64> = self
-            .graph
-            .neighbors_undirected(node)
-            .filter(|neighbor| level_index.abs_diff(self.get_level_of_node(neighbor).unwrap()) < 2)
-            .map(|neighbor| self.get_index_of_node(&neighbor).unwrap() as f64)
-            .collect();
+        let ys = (1..=4).map(|id| layouts[0][&id].1).collect::<Vec<_>>();
+        // the last level keeps the default gap of 4 * 40 to the one above
+        assert_eq!(ys, vec![0, -1000, -1050, -1210]);
+    }
 
-        if neighbor_indices.is_empty() {
-            return true;
-        }
+    #[test]
+    fn subtree_level_span_root_spans_all_levels() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 2), (1, 3), (2, 4), (2, 5), (4, 6)];
 
-        let mean_neighbor_index =
-            neighbor_indices.iter().sum::<f64>() / neighbor_indices.len() as f64;
+        let spans = GraphLayout::subtree_level_span(&nodes, &edges).unwrap();
 
-        // swap nodes for being closer to mean_neighbor_index
-        let swap_index = if mean_neighbor_index < node_index as f64 - 0.5 && left.is_none() {
-            node_index - 1
-        } else if mean_neighbor_index > node_index as f64 + 0.5 && right.is_none() {
-            node_index + 1
-        } else {
-            return true;
+        assert_eq!(spans.len(), nodes.len());
+        assert_eq!(spans[&1], (0, 3));
+        assert_eq!(spans[&2], (1, 3));
+        // leaves only span their own level
+        assert_eq!(spans[&3], (1, 1));
+        assert_eq!(spans[&5], (2, 2));
+        assert_eq!(spans[&6], (3, 3));
+    }
+
+    #[test]
+    fn create_layers_with_options_visit_priority_orders_level() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (1, 3), (1, 4)];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        assert_eq!(level_orders(&layouts[0])[1], vec![4, 3, 2]);
+
+        // 4 has a key of 0
+        let options = crate::LayoutOptions {
+            visit_priority: HashMap::from([(3, -2), (2, -1)]),
+            ..Default::default()
         };
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
 
-        let level = &mut self.layers.borrow_mut()[level_index];
-        level[node_index] = None;
+        assert_eq!(level_orders(&layouts[0])[1], vec![3, 2, 4]);
+    }
 
-        if swap_index > level.len() {
-            level.push(Some(node));
-        } else {
-            level[swap_index] = Some(node);
-        }
+    #[test]
+    fn create_layers_with_options_external_hints_respected() {
+        // without hints, 5 and 6 would be placed in the first level
+        let nodes = [1, 2, 3, 4, 5, 6, 7];
+        let edges = [
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 7),
+            (3, 7),
+            (4, 7),
+            (5, 7),
+            (6, 7),
+        ];
+        let (locked_levels, order_constraints) = GraphLayout::parse_external_hints(
+            "5 @ 1; 6 @ 1 # the sources go next to the children of 1\n4 < 3\n3 < 2; 6 < 5",
+        )
+        .unwrap();
+        assert_eq!(locked_levels, HashMap::from([(5, 1), (6, 1)]));
+        assert!(GraphLayout::check_locked_levels(&nodes, &edges, &locked_levels).is_ok());
+        assert!(GraphLayout::check_order_constraints(
+            &nodes,
+            &edges,
+            &order_constraints,
+            &locked_levels
+        )
+        .is_ok());
+        let options = crate::LayoutOptions {
+            locked_levels,
+            order_constraints: order_constraints.clone(),
+            ..Default::default()
+        };
 
-        self.insert_index_of_node(node, swap_index);
+        let (layouts, ..) =
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options).unwrap();
 
-        false
+        let layout = &layouts[0];
+        assert_eq!(layout[&5].1, layout[&2].1);
+        assert_eq!(layout[&6].1, layout[&2].1);
+        assert_eq!(layout[&3].1, layout[&2].1);
+        for (left, right) in order_constraints {
+            assert!(layout[&(left as usize)].0 < layout[&(right as usize)].0);
+        }
     }
 
-    /// Prints the graph to the console.
-    ///
-    /// Parameters:
-    /// - style: GraphPrintStyle, the style in which the nodes of the graph are displayed.
-    /// Can be either a specific char or the id of a node.
-    #[cfg(feature = "debug")]
-    fn print_layout(&self, style: GraphPrintStyle) {
-        for l in self.layers.borrow().iter() {
-            for n in l {
-                if let Some(n) = n {
-                    match &style {
-                        GraphPrintStyle::Node => print!("{:>2?}, ", n.index()),
-                        GraphPrintStyle::Char(c) => print!("{:}", c),
-                    }
-                } else {
-                    print!(" ");
-                }
-            }
-            println!("");
-        }
+    #[test]
+    fn parse_external_hints_conflicting_hints() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (1, 3), (3, 4)];
+        let check = |hints: &str| {
+            let (locked_levels, order_constraints) = GraphLayout::parse_external_hints(hints)?;
+            GraphLayout::check_locked_levels(&nodes, &edges, &locked_levels)?;
+            GraphLayout::check_order_constraints(&nodes, &edges, &order_constraints, &locked_levels)
+        };
+
+        let invalid = |message: &str| Err(LayoutError::InvalidGraph(message.to_string()));
+        assert_eq!(
+            check("2 @ 1; 2 @ 2"),
+            invalid("Node 2 is hinted to be in level 1 and 2")
+        );
+        assert_eq!(
+            check("2 < 3; 3 < 2"),
+            invalid("Order constraints contain a cycle at node 3")
+        );
+        assert_eq!(
+            check("2 @ 1; 4 @ 2; 2 < 4"),
+            invalid("Node 2 can't be placed before node 4, they are locked to levels 1 and 2")
+        );
+        assert_eq!(check("2 before 3"), invalid("Invalid hint \"2 before 3\""));
+        assert_eq!(check("2 < 3; 4 @ 2"), Ok(()));
     }
-}
 
-/// Specifies in which style a graph can be printed.
-/// Variants are a user specified char or the id of a node.
-#[cfg(feature = "debug")]
-enum GraphPrintStyle {
-    Node,
-    Char(char),
-}
+    #[test]
+    fn dangling_nodes_self_loop_positioned() {
+        let nodes = [1, 2, 3, 4];
+        let edges = [(1, 2), (2, 3), (2, 2), (4, 4)];
 
-#[cfg(test)]
-mod tests {
-    use super::GraphLayout;
-    use petgraph::stable_graph::NodeIndex;
+        assert_eq!(GraphLayout::dangling_nodes(&edges), vec![4]);
+
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        assert_eq!(layouts.len(), 2);
+        assert_eq!(layouts[1].keys().collect::<Vec<_>>(), [&4]);
+        assert_eq!(layouts[0].len(), 3);
+    }
 
     #[test]
-    fn test_into_weakly_connected_components_two_single_nodes() {
-        let mut g = petgraph::stable_graph::StableDiGraph::<(), ()>::new();
-        g.add_node(());
-        g.add_node(());
-        assert_eq!(GraphLayout::into_weakly_connected_components(g).len(), 2);
+    fn create_layers_collapsed_groups_tiny_components() {
+        let nodes = (1..=14).collect::<Vec<_>>();
+        let edges = [
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 5),
+            (6, 7),
+            (9, 10),
+            (10, 11),
+            (12, 13),
+        ];
+
+        let ((layouts, widths, heights), collapsed) = GraphLayout::create_layers_collapsed(
+            &nodes,
+            &edges,
+            40,
+            false,
+            4,
+            crate::LayoutOptions::default(),
+        )
+        .unwrap();
+
+        let (expected, ..) =
+            GraphLayout::create_layers(&nodes[..5], &edges[..5], 40, false).unwrap();
+        assert_eq!(layouts, expected);
+        assert_eq!((widths.len(), heights.len()), (1, 1));
+        assert_eq!(
+            collapsed,
+            vec![vec![6, 7], vec![8], vec![9, 10, 11], vec![12, 13], vec![14]]
+        );
     }
 
     #[test]
-    fn test_into_weakly_connected_compontents_two_components_correct_number_of_components() {
-        let mut g = petgraph::stable_graph::StableDiGraph::<(), ()>::new();
-        for _ in 0..4 {
-            g.add_node(());
-        }
-        g.add_edge(NodeIndex::from(1), NodeIndex::from(0), ());
-        g.add_edge(NodeIndex::from(2), NodeIndex::from(0), ());
-        assert_eq!(GraphLayout::into_weakly_connected_components(g).len(), 2);
+    fn create_layers_from_reachability_chain_same_as_direct_edges() {
+        let nodes = (1..=6).collect::<Vec<_>>();
+        let chain = (1..6).map(|id| (id, id + 1)).collect::<Vec<_>>();
+        let reachable = (1..=6)
+            .flat_map(|tail| (tail + 1..=6).map(move |head| (tail, head)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            GraphLayout::transitive_reduction(&nodes, &reachable),
+            Ok(chain.clone())
+        );
+        assert_eq!(
+            GraphLayout::create_layers_from_reachability(&nodes, &reachable, 40),
+            Ok(GraphLayout::create_layers(&nodes, &chain, 40, false).unwrap())
+        );
+        assert_eq!(
+            GraphLayout::transitive_reduction(&nodes, &[(1, 2), (2, 1)]),
+            Err(LayoutError::InvalidGraph(
+                "Reachability contains a cycle at node 2".to_string()
+            ))
+        );
     }
 
     #[test]
-    fn into_weakly_connected_components_two_components_correct_nodes_and_edges() {
-        let g = petgraph::stable_graph::StableDiGraph::<(), ()>::from_edges([
-            (0, 1),
-            (1, 2),
-            (3, 2),
+    fn create_layers_mixed_threshold_chooses_method() {
+        let nodes = (1..=11).collect::<Vec<_>>();
+        let small = [(1, 2), (1, 3)];
+        let large = [
             (4, 5),
             (4, 6),
-        ]);
-        let sgs = GraphLayout::into_weakly_connected_components(g);
-        assert_eq!(sgs.len(), 2);
-        assert!(sgs[0].contains_edge(0.into(), 1.into()));
-        assert!(sgs[0].contains_edge(1.into(), 2.into()));
-        assert!(sgs[0].contains_edge(3.into(), 2.into()));
-        assert!(sgs[1].contains_edge(4.into(), 5.into()));
-        assert!(sgs[1].contains_edge(4.into(), 6.into()));
+            (5, 7),
+            (6, 7),
+            (7, 8),
+            (7, 9),
+            (8, 10),
+            (9, 11),
+        ];
+        let edges = [small.as_slice(), &large].concat();
+        let config = crate::SugiyamaConfig::default();
+
+        let ((layouts, widths, heights), methods) = GraphLayout::create_layers_mixed(
+            &nodes,
+            &edges,
+            40,
+            false,
+            5,
+            config.clone(),
+            crate::LayoutOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(methods, [LayoutMethod::Original, LayoutMethod::Sugiyama]);
+        assert_eq!((widths.len(), heights.len()), (2, 2));
+        let (original, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        assert_eq!(layouts[0], original[0]);
+        let (sugiyama, ..) =
+            crate::create_layouts_sugiyama((4..=11).collect(), large.to_vec(), config);
+        assert_eq!(layouts[1], sugiyama[0]);
+        let positions = layouts[1].values().collect::<HashSet<_>>();
+        assert_eq!(positions.len(), 8);
+    }
+
+    #[test]
+    fn create_layers_with_options_spacing_scales_with_node_size() {
+        let nodes = (1..=7).collect::<Vec<_>>();
+        let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (4, 5), (4, 6), (7, 6)];
+        let layout = |node_size, separation_factor| {
+            let options = crate::LayoutOptions {
+                separation_factor,
+                ..Default::default()
+            };
+            GraphLayout::create_layers_with_options(&nodes, &edges, node_size, false, options)
+                .unwrap()
+                .0
+        };
+        let double = |layouts: Vec<crate::NodePositions>| {
+            layouts
+                .into_iter()
+                .map(|layout| {
+                    layout
+                        .into_iter()
+                        .map(|(id, (x, y))| (id, (2 * x, 2 * y)))
+                        .collect::<crate::NodePositions>()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(layout(80, 4), double(layout(40, 4)));
+        assert_eq!(layout(40, 4), double(layout(40, 2)));
+        assert_eq!(
+            layout(40, 4),
+            GraphLayout::create_layers(&nodes, &edges, 40, false)
+                .unwrap()
+                .0
+        );
+        let options = crate::LayoutOptions {
+            separation_factor: 0,
+            ..Default::default()
+        };
+        assert!(GraphLayout::check_options(&options).is_err());
+    }
+
+    #[test]
+    fn create_layers_with_options_symmetric_subtrees_mirrored() {
+        // 1 has two identical subtrees 2 -> 4 and 3 -> 5, 6 and 8 pull them to the side
+        let nodes = (1..=9).collect::<Vec<_>>();
+        let edges = [
+            (7, 1),
+            (7, 6),
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 5),
+            (6, 8),
+            (8, 9),
+        ];
+        let layout = |symmetric_subtrees| {
+            let options = crate::LayoutOptions {
+                symmetric_subtrees,
+                ..Default::default()
+            };
+            GraphLayout::create_layers_with_options(&nodes, &edges, 40, false, options)
+                .unwrap()
+                .0[0]
+                .clone()
+        };
+        let unchanged = layout(false);
+        assert_ne!(unchanged[&2].0 + unchanged[&3].0, 2 * unchanged[&1].0);
+
+        let mirrored = layout(true);
+        let x = |id| mirrored[&id].0;
+        assert_eq!(x(2) + x(3), 2 * x(1));
+        assert_eq!(x(4) + x(5), 2 * x(1));
+        // the subtrees are mirror images and keep their levels
+        assert_eq!(x(2) < x(1), x(4) < x(1));
+        for id in 1..=9 {
+            assert_eq!(mirrored[&id].1, unchanged[&id].1);
+        }
+        let positions = mirrored.values().collect::<HashSet<_>>();
+        assert_eq!(positions.len(), 9);
+    }
+
+    #[test]
+    fn create_layers_top_n_keeps_largest_components() {
+        // components of size 2, 3, 1, 4 and 2, the second one of size 2 is dropped by its min id
+        let nodes = (1..=12).collect::<Vec<_>>();
+        let edges = [(1, 2), (3, 4), (4, 5), (7, 8), (7, 9), (9, 10), (11, 12)];
+
+        let ((layouts, widths, heights), dropped) =
+            GraphLayout::create_layers_top_n(&nodes, &edges, 3, 40).unwrap();
+
+        let mut kept = layouts
+            .iter()
+            .map(|layout| {
+                let mut ids = layout.keys().copied().collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids
+            })
+            .collect::<Vec<_>>();
+        kept.sort_unstable();
+        assert_eq!(kept, vec![vec![1, 2], vec![3, 4, 5], vec![7, 8, 9, 10]]);
+        assert_eq!((widths.len(), heights.len()), (3, 3));
+        assert_eq!(dropped, vec![vec![6], vec![11, 12]]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn load_state_builds_same_layout() {
+        let nodes = (1..=8).collect::<Vec<_>>();
+        let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (5, 4), (6, 7), (6, 8)];
+        let graphs = GraphLayout::align_components(
+            &nodes,
+            &edges,
+            40,
+            false,
+            crate::LayoutOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(graphs.len(), 2);
+
+        for graph in graphs {
+            let state = graph.save_state();
+            let loaded = GraphLayout::load_state(&state, crate::LayoutOptions::default()).unwrap();
+            assert_eq!(loaded.build_layout(), graph.build_layout());
+            assert_eq!(loaded.save_state(), state);
+        }
+        assert!(GraphLayout::load_state("{}", crate::LayoutOptions::default()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn load_state_rejects_inconsistent_state() {
+        let graph = GraphLayout::align_components(
+            &[1, 2, 3],
+            &[(1, 2), (1, 3)],
+            40,
+            false,
+            crate::LayoutOptions::default(),
+        )
+        .unwrap()
+        .remove(0);
+        let state = serde_json::from_str::<serde_json::Value>(&graph.save_state()).unwrap();
+        let load = |change: &dyn Fn(&mut serde_json::Value)| {
+            let mut state = state.clone();
+            change(&mut state);
+            GraphLayout::load_state(&state.to_string(), crate::LayoutOptions::default())
+        };
+        let invalid = |msg: &str| Some(LayoutError::InvalidGraph(msg.to_string()));
+
+        assert!(load(&|_| {}).is_ok());
+        assert_eq!(
+            load(&|s| s["level_of_node"][0][0] = 7.into()).err(),
+            invalid("Layout state refers to unknown node 7")
+        );
+        assert_eq!(
+            load(&|s| s["index_of_node"][0][0] = 7.into()).err(),
+            invalid("Layout state refers to unknown node 7")
+        );
+        assert_eq!(
+            load(&|s| s["level_of_node"][0][1] = 5.into()).err(),
+            invalid("Layout state places node 0 on level 5, but it only has 2 levels")
+        );
+        assert_eq!(
+            load(&|s| s["index_of_node"][0][1] = 2.into()).err(),
+            invalid("Layout state places node 0 at index 2 of level 0, but it isn't there")
+        );
+        assert_eq!(
+            load(&|s| s["edges"][0] = serde_json::json!([2, 0])).err(),
+            invalid("Graph contains the cycle 1 -> 3 -> 1")
+        );
+        assert_eq!(
+            load(&|s| s["layers"] = serde_json::json!([])).err(),
+            invalid("Layout state contains edges, but no levels")
+        );
+        assert_eq!(
+            load(&|s| {
+                s["nodes"] = serde_json::json!([]);
+                s["edges"] = serde_json::json!([]);
+            })
+            .err(),
+            invalid("Layout state contains no nodes")
+        );
+        assert_eq!(
+            load(&|s| s["edges"] = serde_json::json!([])).err(),
+            invalid("Layout state contains 3 nodes, but no edges")
+        );
+        assert_eq!(
+            load(&|s| s["level_of_node"] = serde_json::json!([[0, 0], [1, 1]])).err(),
+            invalid("Layout state doesn't contain the level and index of node 2")
+        );
+        assert_eq!(
+            load(&|s| s["node_size"] = (-40).into()).err(),
+            invalid("Node size must be positive, got -40")
+        );
+        assert_eq!(
+            load(&|s| s["nodes"][0] = usize::MAX.into()).err(),
+            invalid(&format!(
+                "Layout state uses node {}, but at most {} nodes are supported",
+                usize::MAX,
+                super::MAX_STATE_NODE_BOUND
+            ))
+        );
+    }
+
+    #[test]
+    fn check_acyclic_names_cycle() {
+        let nodes = (1..=6).collect::<Vec<_>>();
+        let invalid = |msg: &str| Err(LayoutError::InvalidGraph(msg.to_string()));
+
+        assert_eq!(
+            GraphLayout::check_acyclic(&nodes, &[(1, 2), (2, 3), (1, 3), (4, 4), (5, 6)]),
+            Ok(())
+        );
+        assert_eq!(
+            GraphLayout::check_acyclic(&nodes, &[(1, 2), (2, 3), (3, 4), (4, 2), (5, 6)]),
+            invalid("Graph contains the cycle 2 -> 3 -> 4 -> 2")
+        );
     }
 }
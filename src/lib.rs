@@ -29,35 +29,87 @@ ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
 SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 */
 
+pub mod adjacency;
+pub mod analysis;
+pub mod cache;
+pub mod delta;
+pub mod error;
+pub mod export;
 pub mod graph_layout;
+pub mod node_link;
+pub mod spatial;
+pub mod storage;
+pub mod transform;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use env_logger::Env;
-use graph_layout::GraphLayout;
+use error::LayoutError;
+use graph_layout::{
+    FloatingPlacement, GraphLayout, LayoutMethod, Lcg, LevelOrdering, RankPassOrder,
+};
 use log::{debug, info};
 use pyo3::prelude::*;
-use rust_sugiyama::configure::{C_MINIMIZATION_DEFAULT, RANKING_TYPE_DEFAULT};
+use rust_sugiyama::configure::{
+    CrossingMinimization, C_MINIMIZATION_DEFAULT, RANKING_TYPE_DEFAULT,
+};
+use storage::NodeStorage;
 
 pub type NodePositions = HashMap<usize, (isize, isize)>;
+/// The layouts of all components of a graph, together with their widths and heights.
+pub type Layouts = (Vec<NodePositions>, Vec<usize>, Vec<usize>);
+/// Positions of the nodes, scaled to the unit of the caller, see [transform::scale_layout].
+pub type ScaledPositions = HashMap<usize, (f64, f64)>;
+/// The scaled layouts of all components, together with their widths, heights and the scaled
+/// vertex size, see [create_layouts_original_scaled].
+pub type ScaledLayouts = (Vec<ScaledPositions>, Vec<usize>, Vec<usize>, f64);
+/// A graph given as a list of nodes and a list of edges.
+pub type EdgeListGraph = (Vec<u32>, Vec<(u32, u32)>);
+/// The layouts of all components as flat arrays of ids, x- and y-coordinates and component
+/// offsets, see [transform::layouts_to_arrays].
+pub type LayoutArrays = (Vec<u32>, Vec<isize>, Vec<isize>, Vec<usize>);
+/// [LayoutArrays], followed by the level of each node and its index within the level, see
+/// [transform::layouts_to_level_arrays].
+pub type LayoutLevelArrays = (
+    Vec<u32>,
+    Vec<isize>,
+    Vec<isize>,
+    Vec<usize>,
+    Vec<usize>,
+    Vec<usize>,
+);
+/// The (in-degree, out-degree) of every node, see [GraphLayout::node_degrees].
+pub type NodeDegrees = HashMap<u32, (usize, usize)>;
+
+/// The default [LayoutOptions::separation_factor].
+const DEFAULT_SEPARATION_FACTOR: isize = 4;
+
+/// Value of the `crossing_minimization` of a [SugiyamaConfig] that tries both heuristics and
+/// keeps the better one for each level.
+const ADAPTIVE_C_MINIMIZATION: &str = "adaptive";
+/// Default of [LayoutOptions::trivial_threshold].
+const TRIVIAL_THRESHOLD_DEFAULT: usize = 2;
 
 /// Can be used to configure Sugiyama's algorithm.
 ///
 /// Seef [rust_sugiyama::configure::Config] for more information.
+/// The sizes and the spacing are validated when they are set, so a config is always valid.
 #[pyclass]
 #[derive(Clone)]
 pub struct SugiyamaConfig {
     /// Size of the vertices
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     vertex_size: isize,
     /// use dummy vertices
     #[pyo3(get, set)]
     dummy_vertices: bool,
     /// size of dummy vertices
-    #[pyo3(get, set)]
+    #[pyo3(get)]
     dummy_size: f64,
     /// Which heuristic to use for crossing minimization.
-    /// permitted values are: `barycenter` and `median`.
+    /// permitted values are: `barycenter`, `median` and `adaptive`. `adaptive` tries both
+    /// heuristics for each level and keeps the ordering with fewer crossings with the level
+    /// before it, see [LevelOrdering::Adaptive].
     #[pyo3(get, set)]
     crossing_minimization: String,
     /// Use the transpose function during crossing minimization.
@@ -71,6 +123,15 @@ pub struct SugiyamaConfig {
     /// - `down`: move vertices as far down as possible
     #[pyo3(get, set)]
     layering_type: String,
+    /// Shuffle the vertices and edges with this seed before laying them out, so different seeds
+    /// lead to different orderings. Without a seed, they are laid out in ascending order, so the
+    /// layouts are reproducible by default and don't depend on the order of the input.
+    #[pyo3(get, set)]
+    seed: Option<u128>,
+    /// Horizontal distance between neighboring vertices. Defaults to `4 * vertex_size`, so wide
+    /// vertices can be placed closer together by setting it explicitly.
+    #[pyo3(get)]
+    vertex_spacing: Option<usize>,
 }
 
 #[pymethods]
@@ -83,7 +144,10 @@ impl SugiyamaConfig {
             crossing_minimization=rust_sugiyama::configure::C_MINIMIZATION_DEFAULT.into(),
             transpose=false,
             layering_type=rust_sugiyama::configure::RANKING_TYPE_DEFAULT.into(),
+            seed=None,
+            vertex_spacing=None,
             ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         vertex_size: isize,
         dummy_vertices: bool,
@@ -91,15 +155,70 @@ impl SugiyamaConfig {
         crossing_minimization: &str,
         transpose: bool,
         layering_type: &str,
-    ) -> Self {
-        Self {
+        seed: Option<u128>,
+        vertex_spacing: Option<usize>,
+    ) -> PyResult<Self> {
+        let config = Self {
             vertex_size,
             dummy_vertices,
             dummy_size,
             crossing_minimization: crossing_minimization.to_string(),
             transpose,
             layering_type: layering_type.to_string(),
+            seed,
+            vertex_spacing,
+        };
+        config.check()?;
+        Ok(config)
+    }
+
+    #[setter]
+    fn set_vertex_size(&mut self, vertex_size: isize) -> PyResult<()> {
+        Ok(self.update(|config| config.vertex_size = vertex_size)?)
+    }
+
+    #[setter]
+    fn set_dummy_size(&mut self, dummy_size: f64) -> PyResult<()> {
+        Ok(self.update(|config| config.dummy_size = dummy_size)?)
+    }
+
+    #[setter]
+    fn set_vertex_spacing(&mut self, vertex_spacing: Option<usize>) -> PyResult<()> {
+        Ok(self.update(|config| config.vertex_spacing = vertex_spacing)?)
+    }
+}
+
+impl SugiyamaConfig {
+    /// Check that the sizes and the spacing are positive and finite.
+    fn check(&self) -> Result<(), LayoutError> {
+        GraphLayout::check_node_size(self.vertex_size)?;
+        if self.vertex_spacing == Some(0) {
+            return Err(LayoutError::InvalidGraph(
+                "Vertex spacing must be positive, got 0".to_string(),
+            ));
+        }
+        if !(self.dummy_size.is_finite() && self.dummy_size > 0.) {
+            return Err(LayoutError::InvalidGraph(format!(
+                "Dummy size must be positive and finite, got {}",
+                self.dummy_size
+            )));
         }
+        Ok(())
+    }
+
+    /// Apply `change` to the config, unless it makes the config invalid, see
+    /// [SugiyamaConfig::check].
+    fn update(&mut self, change: impl FnOnce(&mut Self)) -> Result<(), LayoutError> {
+        let mut config = self.clone();
+        change(&mut config);
+        config.check()?;
+        *self = config;
+        Ok(())
+    }
+
+    /// The horizontal distance between neighboring vertices, see [SugiyamaConfig::vertex_spacing].
+    fn vertex_spacing(&self) -> usize {
+        self.vertex_spacing.unwrap_or(self.vertex_size as usize * 4)
     }
 }
 
@@ -112,6 +231,235 @@ impl Default for SugiyamaConfig {
             crossing_minimization: <&'static str>::from(C_MINIMIZATION_DEFAULT).to_string(),
             transpose: false,
             layering_type: <&str>::from(RANKING_TYPE_DEFAULT).to_string(),
+            seed: None,
+            vertex_spacing: None,
+        }
+    }
+}
+
+/// Additional options for the original method of Temanejo.
+///
+/// See [GraphLayout::create_layers_with_options].
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    /// A previous layout of the graph. Nodes contained in it start out ordered by their previous
+    /// x-coordinate within their level, new nodes are appended. Used to keep the order of nodes
+    /// stable between successive layouts.
+    #[pyo3(get, set)]
+    pub seed_order_from: Option<NodePositions>,
+    /// Insert a synthetic super source above all sources and a synthetic super sink below all
+    /// sinks. Their ids can be obtained with [super_endpoint_ids].
+    #[pyo3(get, set)]
+    pub super_endpoints: bool,
+    /// How the level and index of each node are stored while creating the layout.
+    #[pyo3(get, set)]
+    pub storage: NodeStorage,
+    /// The order in which nodes are moved between levels after the initial ranking.
+    #[pyo3(get, set)]
+    pub rank_pass_order: RankPassOrder,
+    /// After reducing the crossings, move the nodes with the highest degree towards the center of
+    /// their level, as long as this doesn't add crossings.
+    #[pyo3(get, set)]
+    pub center_hubs: bool,
+    /// Where to place nodes without neighbors in the adjacent levels.
+    #[pyo3(get, set)]
+    pub floating_placement: FloatingPlacement,
+    /// Keep the ratio of width to height within (min_ratio, max_ratio), by splitting wide levels
+    /// and merging levels which aren't connected to each other.
+    #[pyo3(get, set)]
+    pub aspect_ratio_bounds: Option<(f64, f64)>,
+    /// Move all nodes without outgoing edges to the last level, like `global_tasks_in_first_row`
+    /// does for nodes without incoming edges.
+    #[pyo3(get, set)]
+    pub sinks_in_last_row: bool,
+    /// Shuffle the levels with this seed before minimizing crossings, so different seeds restart
+    /// crossing reduction from different orderings.
+    #[pyo3(get, set)]
+    pub seed: Option<u64>,
+    /// Lock nodes to a level, e.g. taken from an external ranking. Locked nodes are never moved
+    /// to another level, but are still reordered within their level. The levels need to be
+    /// consistent with the edges, see [GraphLayout::check_locked_levels].
+    #[pyo3(get, set)]
+    pub locked_levels: HashMap<u32, usize>,
+    /// Override the vertical gap above specific levels, e.g. to give an important level more
+    /// room. Levels which aren't contained use the default gap of
+    /// `separation_factor * vertex_size`.
+    #[pyo3(get, set)]
+    pub level_spacing: HashMap<usize, isize>,
+    /// Use screen coordinates, i.e. the first level is at `y = 0` and y increases downward.
+    /// Otherwise y decreases downward.
+    #[pyo3(get, set)]
+    pub screen_coords: bool,
+    /// Place the nodes of a longest path of the graph in the same column, so the most important
+    /// flow forms a straight line.
+    #[pyo3(get, set)]
+    pub straighten_critical_path: bool,
+    /// Round all coordinates to multiples of this grid size, see [transform::snap_layout].
+    #[pyo3(get, set)]
+    pub snap_to_grid: Option<isize>,
+    /// Pin these nodes to the first level in the given order, e.g. the declared inputs of a
+    /// dataflow diagram, which form the first column after [transform::to_left_right]. All other
+    /// nodes of their components are placed below them. Inputs may only have incoming edges from
+    /// other inputs, see [GraphLayout::check_inputs].
+    #[pyo3(get, set)]
+    pub inputs: Vec<u32>,
+    /// Lay out only one of each set of components with the same shape, i.e. the same edges
+    /// between their nodes when sorted by id, and reuse its coordinates for the others. This
+    /// saves time for graphs with many identical components, e.g. traces.
+    #[pyo3(get, set)]
+    pub dedup_components: bool,
+    /// Components with at most this many nodes are placed by a simple ranking, with the nodes of
+    /// each level in a centered row ordered by id, instead of running the full alignment. This is faster
+    /// for graphs with many tiny components.
+    #[pyo3(get, set)]
+    pub trivial_threshold: usize,
+    /// Reduce the vertical gaps between the levels proportionally, so the layout is at most this
+    /// high. The gaps are never reduced below `vertex_size`, so very tall layouts may still exceed
+    /// it. Only the y-coordinates are affected.
+    #[pyo3(get, set)]
+    pub target_height: Option<isize>,
+    /// Additional edges, which constrain the layout like the other edges, but aren't part of the
+    /// graph, e.g. to keep a node below another one. As the exporters only draw the edges passed
+    /// to them, they stay invisible.
+    #[pyo3(get, set)]
+    pub invisible_edges: Vec<(u32, u32)>,
+    /// Place the levels at these y-coordinates instead of spacing them uniformly, e.g. to align
+    /// them with timestamps. Levels without a y-coordinate keep their gap to the level above.
+    #[pyo3(get, set)]
+    pub level_y: HashMap<usize, isize>,
+    /// Break ties between the nodes, which may be assigned a level at the same time, by visiting
+    /// the ones with the smaller key first, e.g. to order them by priority. Nodes without a key
+    /// have a key of `0`. Nodes visited earlier are placed further left within their level.
+    #[pyo3(get, set)]
+    pub visit_priority: HashMap<u32, i64>,
+    /// Place the first node of each pair left of the second one, if they end up in the same
+    /// level, e.g. to follow the order of an external tool. The order is established before
+    /// reducing crossings and restored afterwards, see [GraphLayout::check_order_constraints].
+    #[pyo3(get, set)]
+    pub order_constraints: Vec<(u32, u32)>,
+    /// The distance between neighboring nodes of a level and the default gap between levels, as
+    /// a multiple of the vertex size. Doubling the vertex size doubles all distances.
+    #[pyo3(get, set)]
+    pub separation_factor: isize,
+    /// Place the two subtrees below a node symmetrically around it, if they have the same shape,
+    /// see [GraphLayout::mirror_symmetric_subtrees].
+    #[pyo3(get, set)]
+    pub symmetric_subtrees: bool,
+}
+
+#[pymethods]
+impl LayoutOptions {
+    #[new]
+    #[pyo3(signature = (
+        seed_order_from=None,
+        super_endpoints=false,
+        storage=NodeStorage::HashMap,
+        rank_pass_order=RankPassOrder::UpThenDown,
+        center_hubs=false,
+        floating_placement=FloatingPlacement::Keep,
+        aspect_ratio_bounds=None,
+        sinks_in_last_row=false,
+        seed=None,
+        locked_levels=HashMap::new(),
+        level_spacing=HashMap::new(),
+        screen_coords=false,
+        straighten_critical_path=false,
+        snap_to_grid=None,
+        inputs=Vec::new(),
+        dedup_components=false,
+        trivial_threshold=TRIVIAL_THRESHOLD_DEFAULT,
+        target_height=None,
+        invisible_edges=Vec::new(),
+        level_y=HashMap::new(),
+        visit_priority=HashMap::new(),
+        order_constraints=Vec::new(),
+        separation_factor=DEFAULT_SEPARATION_FACTOR,
+        symmetric_subtrees=false
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        seed_order_from: Option<NodePositions>,
+        super_endpoints: bool,
+        storage: NodeStorage,
+        rank_pass_order: RankPassOrder,
+        center_hubs: bool,
+        floating_placement: FloatingPlacement,
+        aspect_ratio_bounds: Option<(f64, f64)>,
+        sinks_in_last_row: bool,
+        seed: Option<u64>,
+        locked_levels: HashMap<u32, usize>,
+        level_spacing: HashMap<usize, isize>,
+        screen_coords: bool,
+        straighten_critical_path: bool,
+        snap_to_grid: Option<isize>,
+        inputs: Vec<u32>,
+        dedup_components: bool,
+        trivial_threshold: usize,
+        target_height: Option<isize>,
+        invisible_edges: Vec<(u32, u32)>,
+        level_y: HashMap<usize, isize>,
+        visit_priority: HashMap<u32, i64>,
+        order_constraints: Vec<(u32, u32)>,
+        separation_factor: isize,
+        symmetric_subtrees: bool,
+    ) -> Self {
+        Self {
+            seed_order_from,
+            super_endpoints,
+            storage,
+            rank_pass_order,
+            center_hubs,
+            floating_placement,
+            aspect_ratio_bounds,
+            sinks_in_last_row,
+            seed,
+            locked_levels,
+            level_spacing,
+            screen_coords,
+            straighten_critical_path,
+            snap_to_grid,
+            inputs,
+            dedup_components,
+            trivial_threshold,
+            target_height,
+            invisible_edges,
+            level_y,
+            visit_priority,
+            order_constraints,
+            separation_factor,
+            symmetric_subtrees,
+        }
+    }
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self {
+            seed_order_from: None,
+            super_endpoints: false,
+            storage: NodeStorage::default(),
+            rank_pass_order: RankPassOrder::default(),
+            center_hubs: false,
+            floating_placement: FloatingPlacement::default(),
+            aspect_ratio_bounds: None,
+            sinks_in_last_row: false,
+            seed: None,
+            locked_levels: HashMap::new(),
+            level_spacing: HashMap::new(),
+            screen_coords: false,
+            straighten_critical_path: false,
+            snap_to_grid: None,
+            inputs: Vec::new(),
+            dedup_components: false,
+            trivial_threshold: TRIVIAL_THRESHOLD_DEFAULT,
+            target_height: None,
+            invisible_edges: Vec::new(),
+            level_y: HashMap::new(),
+            visit_priority: HashMap::new(),
+            order_constraints: Vec::new(),
+            separation_factor: DEFAULT_SEPARATION_FACTOR,
+            symmetric_subtrees: false,
         }
     }
 }
@@ -120,7 +468,7 @@ impl From<SugiyamaConfig> for rust_sugiyama::configure::Config {
     fn from(config: SugiyamaConfig) -> Self {
         Self {
             minimum_length: rust_sugiyama::configure::MINIMUM_LENGTH_DEFAULT,
-            vertex_spacing: config.vertex_size as usize * 4,
+            vertex_spacing: config.vertex_spacing(),
             dummy_size: config.dummy_size,
             dummy_vertices: config.dummy_vertices,
             c_minimization: config
@@ -142,18 +490,639 @@ impl From<SugiyamaConfig> for rust_sugiyama::configure::Config {
 /// The layout is created by arranging the nodes of the graph in level and performing some operations them in order to produce a visualization
 /// of the graph.
 /// This version uses the original method of Temanejo to calculate the coordinates.
+/// Neighboring nodes of a level, as well as consecutive levels, are `4 * vertex_size` apart, the
+/// factor can be changed with [LayoutOptions::separation_factor]. The widths and heights count
+/// nodes and levels, not pixels. Each layout can be rendered upright with [export::layout_to_svg].
+/// Raises a `ValueError` if `vertex_size` isn't positive or if the graph contains a cycle.
 #[pyfunction]
 pub fn create_layouts_original(
     nodes: Vec<u32>,
     edges: Vec<(u32, u32)>,
     vertex_size: isize,
     global_tasks_in_first_row: bool,
-) -> (Vec<NodePositions>, Vec<usize>, Vec<usize>) {
+) -> PyResult<Layouts> {
+    let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).try_init();
+    info!(target: "temanejo", "Original method: Got {} vertices and {} edges. Vertex size: {}", nodes.len(), edges.len(), vertex_size);
+    debug!(target: "temanejo", "Vertices {:?}\nEdges: {:?}", nodes, edges);
+    log_dangling_nodes(&edges);
+
+    GraphLayout::check_node_size(vertex_size)?;
+
+    Ok(GraphLayout::create_layers(
+        &nodes,
+        &edges,
+        vertex_size,
+        global_tasks_in_first_row,
+    )?)
+}
+
+/// Create the layouts for each weakly connected component contained in edges.
+///
+/// Works like [create_layouts_original], with additional options to control the layout.
+/// See [LayoutOptions] for details.
+/// Returns an error if [LayoutOptions::locked_levels] are inconsistent with the edges, or if a
+/// size or spacing isn't positive.
+#[pyfunction]
+pub fn create_layouts_original_with_options(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<Layouts> {
+    let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).try_init();
+    info!(target: "temanejo", "Original method: Got {} vertices and {} edges. Vertex size: {}", nodes.len(), edges.len(), vertex_size);
+    debug!(target: "temanejo", "Vertices {:?}\nEdges: {:?}\nOptions: {:?}", nodes, edges, options);
+    log_dangling_nodes(&edges);
+
+    check_original_inputs(&nodes, &edges, vertex_size, &options)?;
+
+    Ok(GraphLayout::create_layers_with_options(
+        &nodes,
+        &edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?)
+}
+
+/// Returns the sorted ids of the nodes which only have self loops.
+///
+/// Self loops are dropped before creating the layout, so these nodes are placed alone in a layout
+/// of their own.
+#[pyfunction]
+pub fn dangling_nodes(edges: Vec<(u32, u32)>) -> Vec<u32> {
+    GraphLayout::dangling_nodes(&edges)
+}
+
+/// Check the vertex size and the options passed to the original method, see the `check_`
+/// functions of [GraphLayout].
+pub(crate) fn check_original_inputs(
+    nodes: &[u32],
+    edges: &[(u32, u32)],
+    vertex_size: isize,
+    options: &LayoutOptions,
+) -> Result<(), LayoutError> {
+    GraphLayout::check_node_size(vertex_size)?;
+    GraphLayout::check_options(options)?;
+    let constrained_edges = [edges, &options.invisible_edges].concat();
+    GraphLayout::check_acyclic(nodes, &constrained_edges)?;
+    GraphLayout::check_locked_levels(nodes, &constrained_edges, &options.locked_levels)?;
+    GraphLayout::check_inputs(nodes, &constrained_edges, &options.inputs)?;
+    GraphLayout::check_order_constraints(
+        nodes,
+        edges,
+        &options.order_constraints,
+        &options.locked_levels,
+    )
+}
+
+fn log_dangling_nodes(edges: &[(u32, u32)]) {
+    let dangling = GraphLayout::dangling_nodes(edges);
+    if !dangling.is_empty() {
+        info!(target: "temanejo", "Nodes {:?} only have self loops, they are placed on their own", dangling);
+    }
+}
+
+/// Create the layouts for each weakly connected component contained in edges, following the hints
+/// of an external layout tool.
+///
+/// The hints lock nodes to levels and order nodes within their level, see
+/// [GraphLayout::parse_external_hints] for their format. They are added to the locked levels and
+/// order constraints of `options`. Raises a `ValueError` if the hints are malformed or conflict
+/// with each other or the graph.
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    hints,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_with_hints(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    hints: &str,
+    global_tasks_in_first_row: bool,
+    mut options: LayoutOptions,
+) -> PyResult<Layouts> {
+    let (locked_levels, order_constraints) = GraphLayout::parse_external_hints(hints)?;
+    for (id, level) in locked_levels {
+        if let Some(locked) = options.locked_levels.insert(id, level) {
+            if locked != level {
+                return Err(LayoutError::InvalidGraph(format!(
+                    "Node {id} is hinted to be in level {level}, but locked to level {locked}"
+                ))
+                .into());
+            }
+        }
+    }
+    options.order_constraints.extend(order_constraints);
+
+    create_layouts_original_with_options(
+        nodes,
+        edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )
+}
+
+/// Create the layouts for each weakly connected component, choosing the method by the size of the
+/// component.
+///
+/// Components with at least `sugiyama_threshold` nodes are laid out with Sugiyama's method and
+/// `config`, the others with the original method and `options`, see
+/// [GraphLayout::create_layers_mixed]. Returns the layouts, together with the method used for each
+/// of them. Raises a `ValueError` like [create_layouts_original_with_options].
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    sugiyama_threshold,
+    config=SugiyamaConfig::default(),
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_mixed(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    sugiyama_threshold: usize,
+    config: SugiyamaConfig,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, Vec<LayoutMethod>)> {
+    check_original_inputs(&nodes, &edges, vertex_size, &options)?;
+
+    Ok(GraphLayout::create_layers_mixed(
+        &nodes,
+        &edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        sugiyama_threshold,
+        config,
+        options,
+    )?)
+}
+
+/// Create the layouts for each weakly connected component, given which nodes can be reached from
+/// each other instead of the edges.
+///
+/// `reachable` contains a pair `(a, b)` if `b` can be reached from `a`, e.g. as reported by an
+/// analysis tool. The direct edges are recovered from its transitive reduction, see
+/// [GraphLayout::create_layers_from_reachability]. Raises a `ValueError` if the reachability
+/// contains a cycle or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn create_layouts_original_from_reachability(
+    nodes: Vec<u32>,
+    reachable: Vec<(u32, u32)>,
+    vertex_size: isize,
+) -> PyResult<Layouts> {
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::create_layers_from_reachability(
+        &nodes,
+        &reachable,
+        vertex_size,
+    )?)
+}
+
+/// Create the layouts for each weakly connected component contained in edges, collapsing the
+/// components with fewer than `min_component_size` nodes.
+///
+/// Works like [create_layouts_original_with_options], but only lays out the larger components.
+/// The ids of the collapsed components are returned instead, so they can be listed in a legend,
+/// see [GraphLayout::create_layers_collapsed].
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    min_component_size,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_collapsed(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    min_component_size: usize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, Vec<Vec<u32>>)> {
+    check_original_inputs(&nodes, &edges, vertex_size, &options)?;
+
+    Ok(GraphLayout::create_layers_collapsed(
+        &nodes,
+        &edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        min_component_size,
+        options,
+    )?)
+}
+
+/// Create the layouts for the `n` largest weakly connected components contained in edges.
+///
+/// The ids of the dropped components are returned as well, see [GraphLayout::create_layers_top_n].
+#[pyfunction]
+pub fn create_layouts_original_top_n(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    n: usize,
+) -> PyResult<(Layouts, Vec<Vec<u32>>)> {
+    check_original_inputs(&nodes, &edges, vertex_size, &LayoutOptions::default())?;
+
+    Ok(GraphLayout::create_layers_top_n(
+        &nodes,
+        &edges,
+        n,
+        vertex_size,
+    )?)
+}
+
+/// Create the layouts for each weakly connected component contained in edges and flag the large
+/// ones.
+///
+/// Works like [create_layouts_original_with_options], but also returns whether each component
+/// has more than `large_threshold` nodes, see [analysis::large_components].
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    large_threshold,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_flagged(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    large_threshold: usize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, Vec<bool>)> {
+    let layouts = create_layouts_original_with_options(
+        nodes,
+        edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?;
+    let large = analysis::large_components(layouts.0.clone(), large_threshold);
+    Ok((layouts, large))
+}
+
+/// Create the layouts for each weakly connected component contained in edges, together with the
+/// degrees of the nodes.
+///
+/// Works like [create_layouts_original_with_options], but also returns the (in-degree,
+/// out-degree) of every node, e.g. for drawing hubs bigger. The degrees are taken from the graph
+/// which is laid out, see [GraphLayout::node_degrees].
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_with_degrees(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, NodeDegrees)> {
+    let layouts = create_layouts_original_with_options(
+        nodes.clone(),
+        edges.clone(),
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?;
+    Ok((layouts, GraphLayout::node_degrees(&nodes, &edges)))
+}
+
+/// Create the layouts for each weakly connected component contained in edges, with coordinates in
+/// the unit of the caller.
+///
+/// Works like [create_layouts_original_with_options], but multiplies all coordinates and the
+/// vertex size by `scale`, see [transform::scale_layout]. The widths and heights count nodes, so
+/// they are returned unchanged. Raises a `ValueError` if `scale` isn't positive and finite.
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    scale,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_scaled(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    scale: f64,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<ScaledLayouts> {
+    GraphLayout::check_scale(scale)?;
+    let (layouts, widths, heights) = create_layouts_original_with_options(
+        nodes,
+        edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?;
+    let layouts = layouts
+        .into_iter()
+        .map(|layout| transform::scale_layout(layout, scale))
+        .collect();
+    Ok((layouts, widths, heights, vertex_size as f64 * scale))
+}
+
+/// Create the layouts for each weakly connected component contained in edges, which may contain
+/// cycles.
+///
+/// Works like [create_layouts_original_with_options], but first reverses the back edges found by
+/// [analysis::back_edges], so the graph becomes acyclic. Returns the layouts, together with the
+/// routes of the back edges of each component, which run through the right margin of the
+/// component, see [analysis::route_back_edges].
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_with_back_edges(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, Vec<analysis::EdgeWaypoints>)> {
+    let back_edges = analysis::back_edges(nodes.clone(), edges.clone());
+    let forward_edges = edges
+        .into_iter()
+        .map(|(tail, head)| match back_edges.contains(&(tail, head)) {
+            true => (head, tail),
+            false => (tail, head),
+        })
+        .collect();
+    let layouts = create_layouts_original_with_options(
+        nodes,
+        forward_edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?;
+    let routes = layouts
+        .0
+        .iter()
+        .map(|layout| analysis::route_back_edges(layout.clone(), back_edges.clone(), vertex_size))
+        .collect();
+    Ok((layouts, routes))
+}
+
+/// Create the layouts for each weakly connected component contained in edges, split into their
+/// levels.
+///
+/// Works like [create_layouts_original_with_options], but returns the positions of each component
+/// as one map per level, ordered from top to bottom, see [transform::split_levels]. Meant for
+/// front-ends revealing a layout level by level.
+#[pyfunction]
+#[pyo3(signature = (
+    nodes,
+    edges,
+    vertex_size,
+    global_tasks_in_first_row=false,
+    options=LayoutOptions::default()
+))]
+pub fn create_layouts_original_by_level(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+    options: LayoutOptions,
+) -> PyResult<Vec<Vec<NodePositions>>> {
+    let screen_coords = options.screen_coords;
+    let (layouts, _, _) = create_layouts_original_with_options(
+        nodes,
+        edges,
+        vertex_size,
+        global_tasks_in_first_row,
+        options,
+    )?;
+    Ok(layouts
+        .into_iter()
+        .map(|layout| transform::split_levels(layout, screen_coords))
+        .collect())
+}
+
+/// Create the layouts for each weakly connected component contained in edges, choosing
+/// `global_tasks_in_first_row` automatically.
+///
+/// Works like [create_layouts_original_with_options], but lays out the graph with and without
+/// `global_tasks_in_first_row` and keeps the layouts with the lower
+/// [layout_score](analysis::layout_score). Returns the layouts and the chosen value.
+#[pyfunction]
+#[pyo3(signature = (nodes, edges, vertex_size, options=LayoutOptions::default()))]
+pub fn create_layouts_original_auto(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    options: LayoutOptions,
+) -> PyResult<(Layouts, bool)> {
     let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).try_init();
     info!(target: "temanejo", "Original method: Got {} vertices and {} edges. Vertex size: {}", nodes.len(), edges.len(), vertex_size);
+    debug!(target: "temanejo", "Vertices {:?}\nEdges: {:?}\nOptions: {:?}", nodes, edges, options);
+    log_dangling_nodes(&edges);
+
+    check_original_inputs(&nodes, &edges, vertex_size, &options)?;
+
+    let (layouts, global_tasks_in_first_row) =
+        GraphLayout::create_layers_auto_first_row(&nodes, &edges, vertex_size, options)?;
+    info!(target: "temanejo", "Chose global_tasks_in_first_row={}", global_tasks_in_first_row);
+    Ok((layouts, global_tasks_in_first_row))
+}
+
+/// Create the layouts of a graph, which may contain cycles, by condensing each strongly connected
+/// component into a super node.
+///
+/// Returns the layouts, keyed by the ids of the super nodes, together with the members of each
+/// super node. See [GraphLayout::create_layers_condensed] for details. Raises a `ValueError` if
+/// an edge refers to an unknown node or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn create_layouts_condensed(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+) -> PyResult<(Layouts, HashMap<u32, Vec<u32>>)> {
+    Ok(GraphLayout::create_layers_condensed(
+        &nodes,
+        &edges,
+        vertex_size,
+    )?)
+}
+
+/// Create the layouts of a preview of a graph, which contains at most `max_nodes` nodes.
+///
+/// Returns the layouts together with the ids of the nodes they contain. See
+/// [GraphLayout::create_layers_sampled] for how the nodes are chosen.
+#[pyfunction]
+pub fn create_layouts_sampled(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    max_nodes: usize,
+    vertex_size: isize,
+) -> PyResult<(Layouts, Vec<u32>)> {
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::create_layers_sampled(
+        &nodes,
+        &edges,
+        max_nodes,
+        vertex_size,
+    )?)
+}
+
+/// Create the layout of a graph, which consists of a single weakly connected component.
+///
+/// Works like [create_layouts_original] for a single component, but doesn't split up the graph.
+/// Raises a `ValueError` if the graph is not connected or contains a cycle.
+#[pyfunction]
+pub fn create_layout_for_component(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+) -> PyResult<(NodePositions, usize, usize)> {
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::create_layers_for_component(
+        &nodes,
+        &edges,
+        vertex_size,
+        global_tasks_in_first_row,
+    )?)
+}
+
+/// Calculate the level of each node for each weakly connected component contained in edges.
+///
+/// The levels are the ones used by [create_layouts_original], starting with `0` at the top.
+/// Use [transform::level_spans] to encode them compactly. Raises a `ValueError` if the graph
+/// contains a cycle.
+#[pyfunction]
+pub fn create_levels(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    global_tasks_in_first_row: bool,
+) -> PyResult<Vec<HashMap<u32, usize>>> {
+    Ok(GraphLayout::create_levels(
+        &nodes,
+        &edges,
+        global_tasks_in_first_row,
+    )?)
+}
+
+/// Returns the smallest and largest level of the nodes reachable from each node, including itself.
+///
+/// The levels are the ones returned by [create_levels]. Meant for drawing brackets around
+/// collapsed subtrees. Raises a `ValueError` if the graph contains a cycle.
+#[pyfunction]
+pub fn subtree_level_span(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+) -> PyResult<HashMap<u32, (usize, usize)>> {
+    Ok(GraphLayout::subtree_level_span(&nodes, &edges)?)
+}
+
+/// Returns the ids of all nodes in reading order, i.e. top to bottom and left to right.
+///
+/// Meant for screen readers or generating a narration of the graph. The order follows the
+/// layouts created by [create_layouts_original_with_options] with the same options.
+/// Raises a `ValueError` if the graph contains a cycle.
+#[pyfunction]
+#[pyo3(signature = (nodes, edges, options=LayoutOptions::default()))]
+pub fn reading_order(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    options: LayoutOptions,
+) -> PyResult<Vec<u32>> {
+    Ok(GraphLayout::reading_order(&nodes, &edges, options)?)
+}
+
+/// Returns the neighbors of each node, which influenced its position within its level.
+///
+/// See [GraphLayout::placement_influences] for details. Raises a `ValueError` if the graph
+/// contains a cycle or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn placement_influences(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+) -> PyResult<HashMap<u32, Vec<u32>>> {
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::placement_influences(
+        &nodes,
+        &edges,
+        vertex_size,
+    )?)
+}
+
+/// Create approximate layouts for each weakly connected component contained in edges.
+///
+/// Meant for previews, e.g. while the graph is being edited. The layout is computed a lot faster
+/// than with [create_layouts_original], but has more crossings and isn't centered.
+/// Raises a `ValueError` if the graph contains a cycle or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn preview_layout(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+) -> PyResult<Layouts> {
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::create_preview_layers(
+        &nodes,
+        &edges,
+        vertex_size,
+    )?)
+}
+
+/// Returns the ids of the (super source, super sink), which are inserted into the layout if
+/// [LayoutOptions::super_endpoints] is set, so they can be styled distinctly.
+#[pyfunction]
+pub fn super_endpoint_ids(nodes: Vec<u32>) -> (u32, u32) {
+    GraphLayout::super_endpoint_ids(&nodes)
+}
+
+/// Create the layouts for each weakly connected component of a bipartite graph.
+///
+/// Every node needs to be either a source or a sink. Sources are put in the first level, sinks in
+/// the second level and only the crossings between the two levels are reduced.
+/// Raises a `ValueError` if the graph is not bipartite or if `vertex_size` isn't positive.
+#[pyfunction]
+pub fn create_layouts_bipartite(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    vertex_size: isize,
+) -> PyResult<Layouts> {
+    let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).try_init();
+    info!(target: "temanejo", "Bipartite method: Got {} vertices and {} edges. Vertex size: {}", nodes.len(), edges.len(), vertex_size);
     debug!(target: "temanejo", "Vertices {:?}\nEdges: {:?}", nodes, edges);
 
-    GraphLayout::create_layers(&nodes, &edges, vertex_size, global_tasks_in_first_row)
+    GraphLayout::check_node_size(vertex_size)?;
+    Ok(GraphLayout::create_layers_bipartite(
+        &nodes,
+        &edges,
+        vertex_size,
+    )?)
 }
 
 /// Create the layouts for each weakly connected component contained in edges.
@@ -161,12 +1130,36 @@ pub fn create_layouts_original(
 /// A layout contains the position of each node (HashMap of NodeIndex and (x, y)) the height of the layout and the maximum width of the layers.
 /// The layout is created by arranging the nodes of the graph in level and performing some operations them in order to produce a visualization
 /// This version uses Suiyama's method to calculate the coordinates.
+/// The ids don't need to start at 1 like in networkx, e.g. 0 based ids are kept as they are.
+/// Repeated requests are answered from the layout cache, if it is enabled with
+/// [cache::set_layout_cache_capacity].
 #[pyfunction]
 pub fn create_layouts_sugiyama(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> (Vec<NodePositions>, Vec<usize>, Vec<usize>) {
+    if !cache::is_enabled() {
+        return create_layouts_sugiyama_uncached(nodes, edges, config);
+    }
+    let key = cache::CacheKey::new(&nodes, &edges, &config);
+    if let Some(layouts) = cache::lookup(&key) {
+        return layouts;
+    }
+    let layouts = create_layouts_sugiyama_uncached(nodes, edges, config);
+    cache::store(key, layouts.clone());
+    layouts
+}
+
+/// Works like [create_layouts_sugiyama], but always computes the layouts.
+fn create_layouts_sugiyama_uncached(
     mut nodes: Vec<u32>,
     mut edges: Vec<(u32, u32)>,
     config: SugiyamaConfig,
-) -> (Vec<NodePositions>, Vec<usize>, Vec<usize>) {
+) -> Layouts {
+    if config.crossing_minimization == ADAPTIVE_C_MINIMIZATION {
+        return create_layouts_sugiyama_adaptive(nodes, edges, config);
+    }
     let _ = env_logger::Builder::from_env(Env::default().default_filter_or("trace")).try_init();
     info!(target: "temanejo", "Sugiyama's method: Got {} vertices and {} edges. Vertex size: {}", nodes.len(), edges.len(), config.vertex_size);
     debug!(target: "temanejo", "Vertices {:?}\nEdges: {:?}", nodes, edges);
@@ -174,11 +1167,30 @@ pub fn create_layouts_sugiyama(
     let mut width_list = Vec::new();
     let mut height_list = Vec::new();
 
-    // decrement edges and nodes by one since networkx graph is 1 based.
-    nodes.iter_mut().for_each(|v| *v -= 1);
+    // sort the nodes and edges like the cache key, so the layout doesn't depend on their order
+    // and cached layouts are the same as fresh ones, parallel edges are laid out as one,
+    // exporters still get all of them
+    nodes.sort_unstable();
+    edges.sort_unstable();
+    edges.dedup();
+    if let Some(seed) = config.seed {
+        // fold the seed into the 64 bit state of the generator
+        let mut rng = Lcg(seed as u64 ^ (seed >> 64) as u64);
+        rng.shuffle(&mut nodes);
+        rng.shuffle(&mut edges);
+    }
+    // shift the ids, so the smallest one is 0, which works for the 1 based ids of networkx as
+    // well as for 0 based ids
+    let offset = nodes
+        .iter()
+        .chain(edges.iter().flat_map(|(t, h)| [t, h]))
+        .min()
+        .copied()
+        .unwrap_or(0);
+    nodes.iter_mut().for_each(|v| *v -= offset);
     edges.iter_mut().for_each(|(t, h)| {
-        *t -= 1;
-        *h -= 1;
+        *t -= offset;
+        *h -= offset;
     });
 
     let layouts = rust_sugiyama::from_vertices_and_edges(&nodes, &edges)
@@ -189,18 +1201,346 @@ pub fn create_layouts_sugiyama(
         width_list.push(width);
         height_list.push(height);
         layout_list.push(HashMap::<usize, (isize, isize)>::from_iter(
-            layout.into_iter().map(|(id, coords)| (id + 1, coords)),
+            layout
+                .into_iter()
+                .map(|(id, coords)| (id + offset as usize, coords)),
         ));
     }
 
     (layout_list, width_list, height_list)
 }
 
+/// Lay out the graph with the barycenter heuristic, then reorder the levels of each layout with
+/// [LevelOrdering::Adaptive], which chooses the heuristic per level.
+///
+/// [rust_sugiyama] applies one heuristic to all levels, so the levels are reordered by
+/// [graph_layout::sweep_levels] afterwards. Only the edges between neighboring levels are taken
+/// into account. A reordered layout is only kept if it has fewer crossings, so it is never worse
+/// than the barycenter layout.
+fn create_layouts_sugiyama_adaptive(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> Layouts {
+    let config = SugiyamaConfig {
+        crossing_minimization: <&str>::from(CrossingMinimization::Barycenter).to_string(),
+        ..config
+    };
+    let (layouts, widths, heights) = create_layouts_sugiyama(nodes, edges.clone(), config);
+    let layouts = layouts
+        .into_iter()
+        .map(|layout| {
+            let reordered = reorder_levels(&layout, &edges);
+            if analysis::count_layout_crossings(&reordered, &edges)
+                < analysis::count_layout_crossings(&layout, &edges)
+            {
+                reordered
+            } else {
+                layout
+            }
+        })
+        .collect();
+    (layouts, widths, heights)
+}
+
+/// Reorder the nodes of each level of a layout with [LevelOrdering::Adaptive]. The levels are
+/// swept in descending order of their y-coordinate and each level keeps its x-coordinates, which
+/// are handed out to the nodes in their new order.
+fn reorder_levels(layout: &NodePositions, edges: &[(u32, u32)]) -> NodePositions {
+    let mut levels = BTreeMap::<isize, Vec<(isize, usize)>>::new();
+    for (id, (x, y)) in layout {
+        levels.entry(*y).or_default().push((*x, *id));
+    }
+    let levels = levels
+        .into_iter()
+        .rev()
+        .map(|(y, mut level)| {
+            level.sort_unstable();
+            (y, level)
+        })
+        .collect::<Vec<_>>();
+    let mut orders = levels
+        .iter()
+        .map(|(_, level)| level.iter().map(|(_, id)| *id as u32).collect())
+        .collect::<Vec<_>>();
+    graph_layout::sweep_levels(&mut orders, edges, LevelOrdering::Adaptive);
+
+    levels
+        .iter()
+        .zip(orders)
+        .flat_map(|((y, level), order)| {
+            level
+                .iter()
+                .zip(order)
+                .map(move |((x, _), id)| (id as usize, (*x, *y)))
+        })
+        .collect()
+}
+
+/// Create the layouts for each weakly connected component of a graph given as adjacency list.
+///
+/// Works like [create_layouts_original]. Every target needs to be a key of the adjacency list,
+/// otherwise a `ValueError` is raised.
+#[pyfunction]
+pub fn create_layouts_original_adj(
+    adjacency: HashMap<u32, Vec<u32>>,
+    vertex_size: isize,
+    global_tasks_in_first_row: bool,
+) -> PyResult<Layouts> {
+    let (nodes, edges) = adjacency::adjacency_to_edge_list(&adjacency)?;
+    create_layouts_original(nodes, edges, vertex_size, global_tasks_in_first_row)
+}
+
+/// Create the layouts for each weakly connected component of a graph given as adjacency list.
+///
+/// Works like [create_layouts_sugiyama]. Every target needs to be a key of the adjacency list,
+/// otherwise a `ValueError` is raised.
+#[pyfunction]
+pub fn create_layouts_sugiyama_adj(
+    adjacency: HashMap<u32, Vec<u32>>,
+    config: SugiyamaConfig,
+) -> PyResult<Layouts> {
+    let (nodes, edges) = adjacency::adjacency_to_edge_list(&adjacency)?;
+    Ok(create_layouts_sugiyama(nodes, edges, config))
+}
+
+/// Create the layouts for each weakly connected component contained in edges and return them as
+/// flat arrays.
+///
+/// Works like [create_layouts_sugiyama], but returns parallel lists of ids, x- and y-coordinates
+/// and the offset of each component, see [transform::layouts_to_arrays]. These convert cheaply to
+/// numpy arrays, which avoids the overhead of a dictionary per component for large graphs.
+#[pyfunction]
+pub fn create_layouts_sugiyama_arrays(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> LayoutArrays {
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges, config);
+    transform::layouts_to_arrays(&layouts)
+}
+
+/// Create the layouts for each weakly connected component contained in edges and return them as
+/// flat arrays, including the level and index of each node.
+///
+/// Works like [create_layouts_sugiyama_arrays], but additionally returns the level of each node
+/// and its index within the level, parallel to the ids, see [transform::layouts_to_level_arrays].
+#[pyfunction]
+pub fn create_layouts_sugiyama_level_arrays(
+    nodes: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+    config: SugiyamaConfig,
+) -> LayoutLevelArrays {
+    let (layouts, _, _) = create_layouts_sugiyama(nodes, edges, config);
+    transform::layouts_to_level_arrays(&layouts)
+}
+
 #[pymodule]
 #[allow(deprecated)]
 fn rs_graph_layout(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<SugiyamaConfig>()?;
+    m.add_class::<LayoutOptions>()?;
+    m.add_class::<NodeStorage>()?;
+    m.add_class::<RankPassOrder>()?;
+    m.add_class::<FloatingPlacement>()?;
+    m.add_class::<LayoutMethod>()?;
     m.add_function(wrap_pyfunction!(create_layouts_original, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_with_options, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_auto, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_flagged, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_with_degrees, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_with_hints, m)?)?;
+    m.add_function(wrap_pyfunction!(dangling_nodes, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_scaled, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_sugiyama_level_arrays, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_collapsed, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_top_n, m)?)?;
+    m.add_function(wrap_pyfunction!(
+        create_layouts_original_from_reachability,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(
+        create_layouts_original_with_back_edges,
+        m
+    )?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_by_level, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layout_for_component, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_condensed, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_sampled, m)?)?;
+    m.add_function(wrap_pyfunction!(super_endpoint_ids, m)?)?;
+    m.add_function(wrap_pyfunction!(preview_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(create_levels, m)?)?;
+    m.add_function(wrap_pyfunction!(subtree_level_span, m)?)?;
+    m.add_function(wrap_pyfunction!(placement_influences, m)?)?;
+    m.add_function(wrap_pyfunction!(reading_order, m)?)?;
     m.add_function(wrap_pyfunction!(create_layouts_sugiyama, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_bipartite, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_original_adj, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_sugiyama_adj, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_sugiyama_arrays, m)?)?;
+    m.add_function(wrap_pyfunction!(node_link::from_node_link_json, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::height_critical_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::node_degrees, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::overlaps_at_scale, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::profile_configs, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::nodes_in_rect, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::layout_candidates, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::edge_paths, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::edge_paths_capped, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::edge_segments, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::edge_waypoints, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::assert_waypoints_monotone, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::layout_signature, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::edge_slopes, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::overlapping_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::large_components, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::max_concurrency, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::back_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::route_back_edges, m)?)?;
+    m.add_function(wrap_pyfunction!(analysis::highest_crossing_node, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::set_layout_cache_capacity, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::clear_layout_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache::layout_cache_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(delta::layout_delta, m)?)?;
+    m.add_function(wrap_pyfunction!(delta::ordering_transform, m)?)?;
+    m.add_function(wrap_pyfunction!(delta::stability_score, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_svg, m)?)?;
+    m.add_function(wrap_pyfunction!(export::layout_to_svg, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_svg_labeled, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_svg_bundled, m)?)?;
+    m.add_function(wrap_pyfunction!(export::component_colors, m)?)?;
+    m.add_function(wrap_pyfunction!(create_layouts_mixed, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_html, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_tikz, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_dot, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_graphml, m)?)?;
+    m.add_function(wrap_pyfunction!(export::to_elk_json, m)?)?;
+    m.add_function(wrap_pyfunction!(export::layout_batch_to_dir, m)?)?;
+    #[cfg(feature = "image")]
+    m.add_function(wrap_pyfunction!(export::render_png, m)?)?;
+    m.add_class::<transform::Orientation>()?;
+    m.add_function(wrap_pyfunction!(transform::layout_dimensions, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::to_left_right, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::transpose_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::snap_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::fit_aspect_ratio, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::normalize_x, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::pin_left, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::level_spans, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::decode_level_spans, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::split_levels, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::scale_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::scale_layout_rounded, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::translate_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::normalize_layout, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::mirror_layout, m)?)?;
+    m.add_class::<transform::LayoutPipeline>()?;
+    m.add_class::<spatial::SpatialIndex>()?;
+    m.add_function(wrap_pyfunction!(spatial::build_spatial_index, m)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        analysis::{count_layout_crossings, max_concurrency},
+        create_layouts_sugiyama,
+        delta::layout_delta,
+        error::LayoutError,
+        graph_layout::GraphLayout,
+        placement_influences, reading_order, reorder_levels, subtree_level_span, NodePositions,
+    };
+
+    #[test]
+    fn reorder_levels_chooses_the_heuristic_per_level() {
+        let edges = [
+            (1, 6),
+            (1, 7),
+            (1, 8),
+            (2, 6),
+            (2, 7),
+            (3, 8),
+            (4, 7),
+            (5, 11),
+            (6, 10),
+            (6, 11),
+            (8, 10),
+            (9, 11),
+        ];
+        let levels = [vec![1, 2, 3, 4], vec![5, 6, 7, 8, 9], vec![10, 11]];
+        let layout = levels
+            .iter()
+            .enumerate()
+            .flat_map(|(y, level)| {
+                level
+                    .iter()
+                    .enumerate()
+                    .map(move |(x, id)| (*id, (x as isize * 160, -(y as isize) * 160)))
+            })
+            .collect::<NodePositions>();
+
+        let reordered = reorder_levels(&layout, &edges);
+
+        assert_eq!(count_layout_crossings(&layout, &edges), 8);
+        assert_eq!(count_layout_crossings(&reordered, &edges), 6);
+        // 7 and 8 are swapped by the barycenter ordering, 10 and 11 by the median ordering
+        assert_eq!((reordered[&7], reordered[&8]), (layout[&8], layout[&7]));
+        assert_eq!((reordered[&10], reordered[&11]), (layout[&11], layout[&10]));
+        for id in [1, 2, 3, 4, 5, 6, 9] {
+            assert_eq!(reordered[&id], layout[&id]);
+        }
+    }
+
+    #[test]
+    fn create_layouts_sugiyama_zero_based_ids() {
+        let nodes = (0..=6).collect::<Vec<_>>();
+        let edges = vec![(0, 1), (0, 2), (1, 3), (2, 3), (4, 5)];
+
+        let (layouts, widths, heights) = create_layouts_sugiyama(nodes, edges, Default::default());
+
+        assert_eq!((layouts.len(), widths.len(), heights.len()), (3, 3, 3));
+        let mut ids = layouts
+            .iter()
+            .flat_map(|layout| layout.keys().copied())
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, (0..=6).collect::<Vec<_>>());
+        let layout = layouts
+            .iter()
+            .find(|layout| layout.contains_key(&0))
+            .unwrap();
+        assert_eq!(layout[&1].1, layout[&2].1);
+        assert_ne!(layout[&0].1, layout[&1].1);
+        assert_ne!(layout[&1].1, layout[&3].1);
+    }
+
+    #[test]
+    fn create_layouts_sugiyama_largest_ids() {
+        let nodes = vec![u32::MAX - 1, u32::MAX];
+        let edges = vec![(u32::MAX - 1, u32::MAX)];
+
+        let (layouts, ..) = create_layouts_sugiyama(nodes, edges, Default::default());
+
+        assert_eq!(layouts.len(), 1);
+        assert!(layouts[0].contains_key(&(u32::MAX as usize)));
+        assert!(layouts[0].contains_key(&(u32::MAX as usize - 1)));
+    }
+
+    #[test]
+    fn cyclic_graphs_are_rejected_instead_of_panicking() {
+        let nodes = vec![1, 2, 3, 4];
+        let edges = vec![(1, 2), (2, 3), (3, 4), (4, 2)];
+
+        assert_eq!(
+            GraphLayout::create_layers(&nodes, &edges, 40, false),
+            Err(LayoutError::InvalidGraph(
+                "Graph contains the cycle 2 -> 3 -> 4 -> 2".to_string()
+            ))
+        );
+        assert!(subtree_level_span(nodes.clone(), edges.clone()).is_err());
+        assert!(reading_order(nodes.clone(), edges.clone(), Default::default()).is_err());
+        assert!(placement_influences(nodes.clone(), edges.clone(), 40).is_err());
+        assert!(max_concurrency(nodes.clone(), edges.clone()).is_err());
+        assert!(layout_delta(NodePositions::new(), vec![], nodes, edges, 40, false).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Conversion of graphs given in networkx' node-link format.
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::{error::LayoutError, EdgeListGraph};
+
+/// Parse a graph in the format of networkx' `node_link_data` into a list of nodes and a list of
+/// edges, as expected by the functions creating layouts.
+///
+/// The edges are read from `"links"`, or from `"edges"` if there are no links. Integer ids, which
+/// fit into 32 bits, are kept as they are. Other ids, e.g. strings, are numbered in their order in
+/// `"nodes"`, following the largest integer id, or starting at `1` if there is none. Raises a
+/// `ValueError` if the JSON is malformed, an id occurs more than once or a link refers to a node,
+/// which is not part of `"nodes"`.
+#[pyfunction]
+pub fn from_node_link_json(json: &str) -> PyResult<EdgeListGraph> {
+    let invalid = |msg: String| LayoutError::InvalidGraph(msg);
+    let data = serde_json::from_str::<Value>(json)
+        .map_err(|err| invalid(format!("Invalid node-link JSON: {err}")))?;
+
+    let ids = data["nodes"]
+        .as_array()
+        .ok_or_else(|| invalid("Node-link JSON has no list of nodes".to_string()))?
+        .iter()
+        .map(|node| {
+            node.get("id")
+                .cloned()
+                .ok_or_else(|| invalid(format!("Node {node} has no id")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let links = data
+        .get("links")
+        .or_else(|| data.get("edges"))
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid("Node-link JSON has no list of links".to_string()))?;
+
+    let integer_id = |id: &Value| id.as_u64().and_then(|id| u32::try_from(id).ok());
+    let mut next_id = ids.iter().filter_map(integer_id).max().unwrap_or(0);
+    let mut node_ids = HashMap::new();
+    for id in &ids {
+        let node = match integer_id(id) {
+            Some(node) => node,
+            None => {
+                next_id = next_id.checked_add(1).ok_or_else(|| {
+                    invalid(format!("Node {id} can't be numbered after node {next_id}"))
+                })?;
+                next_id
+            }
+        };
+        // keys of json objects are strings, so use the string representation of the id
+        if node_ids.insert(id.to_string(), node).is_some() {
+            return Err(invalid(format!("Node {id} occurs more than once")).into());
+        }
+    }
+
+    let mut edges = Vec::new();
+    for link in links {
+        let endpoint = |key: &str| {
+            link.get(key)
+                .and_then(|id| node_ids.get(&id.to_string()))
+                .copied()
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "Link {link} refers to a {key}, which is not part of the nodes"
+                    ))
+                })
+        };
+        edges.push((endpoint("source")?, endpoint("target")?));
+    }
+
+    let mut nodes = node_ids.into_values().collect::<Vec<_>>();
+    nodes.sort();
+    Ok((nodes, edges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_node_link_json;
+    use crate::{create_layouts_sugiyama, SugiyamaConfig};
+
+    #[test]
+    fn from_node_link_json_integer_ids() {
+        let json = r#"{
+            "directed": true,
+            "multigraph": false,
+            "graph": {},
+            "nodes": [{"id": 1}, {"id": 2}, {"id": 3}],
+            "links": [{"source": 1, "target": 2}, {"source": 1, "target": 3}]
+        }"#;
+
+        let (nodes, edges) = from_node_link_json(json).unwrap();
+        assert_eq!(nodes, vec![1, 2, 3]);
+        assert_eq!(edges, vec![(1, 2), (1, 3)]);
+
+        let (layouts, _, _) = create_layouts_sugiyama(nodes, edges, SugiyamaConfig::default());
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].len(), 3);
+    }
+
+    #[test]
+    fn from_node_link_json_string_ids_mapped_to_positions() {
+        let json = r#"{
+            "nodes": [{"id": "a"}, {"id": "b"}, {"id": "c"}],
+            "edges": [{"source": "c", "target": "a"}]
+        }"#;
+
+        assert_eq!(
+            from_node_link_json(json).unwrap(),
+            (vec![1, 2, 3], vec![(3, 1)])
+        );
+        assert!(from_node_link_json(
+            r#"{"nodes": [{"id": "a"}], "links": [{"source": "a", "target": "b"}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn from_node_link_json_keeps_zero_based_and_sparse_ids() {
+        // nx.node_link_data(nx.path_graph(3, create_using=nx.DiGraph))
+        let json = r#"{
+            "directed": true,
+            "multigraph": false,
+            "graph": {},
+            "nodes": [{"id": 0}, {"id": 1}, {"id": 2}],
+            "links": [{"source": 0, "target": 1}, {"source": 1, "target": 2}]
+        }"#;
+
+        let (nodes, edges) = from_node_link_json(json).unwrap();
+        assert_eq!(nodes, vec![0, 1, 2]);
+        assert_eq!(edges, vec![(0, 1), (1, 2)]);
+        let (layouts, _, _) = create_layouts_sugiyama(nodes, edges, SugiyamaConfig::default());
+        assert_eq!(layouts.len(), 1);
+        assert_eq!(layouts[0].len(), 3);
+
+        assert_eq!(
+            from_node_link_json(
+                r#"{"nodes": [{"id": 10}, {"id": 5}], "links": [{"source": 5, "target": 10}]}"#
+            )
+            .unwrap(),
+            (vec![5, 10], vec![(5, 10)])
+        );
+        // string ids are numbered after the integer ones
+        assert_eq!(
+            from_node_link_json(
+                r#"{"nodes": [{"id": "a"}, {"id": 7}], "links": [{"source": "a", "target": 7}]}"#
+            )
+            .unwrap(),
+            (vec![7, 8], vec![(8, 7)])
+        );
+    }
+
+    #[test]
+    fn from_node_link_json_rejects_duplicate_ids() {
+        let json = |nodes: &str| format!(r#"{{"nodes": {nodes}, "links": []}}"#);
+
+        assert_eq!(
+            from_node_link_json(&json(r#"[{"id": 1}, {"id": 2}, {"id": 1}]"#))
+                .err()
+                .map(|err| err.to_string()),
+            Some("ValueError: Node 1 occurs more than once".to_string())
+        );
+        assert!(from_node_link_json(&json(r#"[{"id": "a"}, {"id": "a"}]"#)).is_err());
+        // an integer and a string with the same digits are different nodes
+        assert_eq!(
+            from_node_link_json(&json(r#"[{"id": 1}, {"id": "1"}]"#)).unwrap(),
+            (vec![1, 2], vec![])
+        );
+    }
+}
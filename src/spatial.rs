@@ -0,0 +1,220 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! A spatial index over the positions of a layout, for fast lookups in interactive views.
+use pyo3::prelude::*;
+
+use super::NodePositions;
+
+/// The maximum number of nodes stored in a leaf of the quadtree, unless all of them share the
+/// same position.
+const QUADTREE_LEAF_CAPACITY: usize = 8;
+
+/// A rectangle given by `(min_x, min_y, max_x, max_y)`, including its borders.
+type Rect = (isize, isize, isize, isize);
+
+/// A quadtree over the positions of nodes.
+///
+/// Each tree covers a rectangle, which is split into four quadrants, as long as it contains too
+/// many nodes.
+#[derive(Debug, Clone)]
+struct QuadTree {
+    bounds: Rect,
+    /// The nodes of a leaf, empty if the tree is split.
+    nodes: Vec<(u32, (isize, isize))>,
+    children: Vec<QuadTree>,
+}
+
+impl QuadTree {
+    fn new(bounds: Rect, nodes: Vec<(u32, (isize, isize))>) -> Self {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        if nodes.len() <= QUADTREE_LEAF_CAPACITY || (min_x == max_x && min_y == max_y) {
+            return Self {
+                bounds,
+                nodes,
+                children: Vec::new(),
+            };
+        }
+
+        let mid_x = min_x + (max_x - min_x) / 2;
+        let mid_y = min_y + (max_y - min_y) / 2;
+        let children = [
+            (min_x, min_y, mid_x, mid_y),
+            (mid_x + 1, min_y, max_x, mid_y),
+            (min_x, mid_y + 1, mid_x, max_y),
+            (mid_x + 1, mid_y + 1, max_x, max_y),
+        ]
+        .into_iter()
+        // quadrants of a rectangle with a width or height of one are empty
+        .filter(|(min_x, min_y, max_x, max_y)| min_x <= max_x && min_y <= max_y)
+        .map(|quadrant| {
+            let nodes = nodes
+                .iter()
+                .filter(|(_, position)| contains(quadrant, *position))
+                .copied()
+                .collect();
+            QuadTree::new(quadrant, nodes)
+        })
+        .collect();
+
+        Self {
+            bounds,
+            nodes: Vec::new(),
+            children,
+        }
+    }
+
+    /// Collect the ids of all nodes positioned within `rect`.
+    fn query(&self, rect: Rect, found: &mut Vec<u32>) {
+        let (min_x, min_y, max_x, max_y) = rect;
+        let (bounds_min_x, bounds_min_y, bounds_max_x, bounds_max_y) = self.bounds;
+        if min_x > bounds_max_x
+            || max_x < bounds_min_x
+            || min_y > bounds_max_y
+            || max_y < bounds_min_y
+        {
+            return;
+        }
+        found.extend(
+            self.nodes
+                .iter()
+                .filter(|(_, position)| contains(rect, *position))
+                .map(|(id, _)| *id),
+        );
+        for child in &self.children {
+            child.query(rect, found);
+        }
+    }
+}
+
+fn contains((min_x, min_y, max_x, max_y): Rect, (x, y): (isize, isize)) -> bool {
+    (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+}
+
+/// An index over the nodes of a layout, which answers point and rectangle queries in logarithmic
+/// instead of linear time.
+///
+/// Each node covers a box of `node_size` around its position. Create it with
+/// [build_spatial_index] once per layout and reuse it for all queries.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    tree: QuadTree,
+    node_size: isize,
+}
+
+#[pymethods]
+impl SpatialIndex {
+    /// Returns the id of the node whose box contains the point, e.g. the node under the cursor.
+    ///
+    /// If the point lies on the border of several boxes, the smallest id is returned.
+    pub fn query_point(&self, x: isize, y: isize) -> Option<u32> {
+        self.query_rect(x, y, x, y).first().copied()
+    }
+
+    /// Returns the sorted ids of all nodes whose box overlaps the rectangle, including its
+    /// borders.
+    ///
+    /// This is the same as [crate::analysis::nodes_in_rect] with the node size of the index.
+    pub fn query_rect(&self, min_x: isize, min_y: isize, max_x: isize, max_y: isize) -> Vec<u32> {
+        let margin = self.node_size / 2;
+        let mut found = Vec::new();
+        self.tree.query(
+            (
+                min_x - margin,
+                min_y - margin,
+                max_x + margin,
+                max_y + margin,
+            ),
+            &mut found,
+        );
+        found.sort();
+        found
+    }
+}
+
+/// Build a [SpatialIndex] over the nodes of a layout.
+#[pyfunction]
+pub fn build_spatial_index(layout: NodePositions, node_size: isize) -> SpatialIndex {
+    let nodes = layout
+        .into_iter()
+        .map(|(id, position)| (id as u32, position))
+        .collect::<Vec<_>>();
+    let bounds = nodes.iter().fold(
+        (isize::MAX, isize::MAX, isize::MIN, isize::MIN),
+        |(min_x, min_y, max_x, max_y), (_, (x, y))| {
+            (min_x.min(*x), min_y.min(*y), max_x.max(*x), max_y.max(*y))
+        },
+    );
+
+    SpatialIndex {
+        tree: QuadTree::new(bounds, nodes),
+        node_size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_spatial_index;
+    use crate::{analysis::nodes_in_rect, graph_layout::GraphLayout};
+
+    #[test]
+    fn spatial_index_same_as_linear_scan() {
+        let nodes = (1..=120).collect::<Vec<_>>();
+        let edges = (1..=120)
+            .flat_map(|id| [(id, id + 1), (id, id * 2), (id, id + 7)])
+            .filter(|(_, head)| *head <= 120)
+            .collect::<Vec<_>>();
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let layout = layouts[0].clone();
+        assert_eq!(layout.len(), nodes.len());
+
+        let index = build_spatial_index(layout.clone(), 40);
+
+        let (min_x, max_x) = (-200, layout.values().map(|(x, _)| *x).max().unwrap() + 200);
+        let (min_y, max_y) = (layout.values().map(|(_, y)| *y).min().unwrap() - 200, 200);
+        for x in (min_x..max_x).step_by(37) {
+            for y in (min_y..max_y).step_by(53) {
+                let expected = nodes_in_rect(layout.clone(), x, y, x, y, Some(40));
+                assert_eq!(index.query_point(x, y), expected.first().copied());
+                for (width, height) in [(0, 0), (90, 30), (400, 700)] {
+                    assert_eq!(
+                        index.query_rect(x, y, x + width, y + height),
+                        nodes_in_rect(layout.clone(), x, y, x + width, y + height, Some(40))
+                    );
+                }
+            }
+        }
+        // positions of nodes are hit
+        for (id, (x, y)) in &layout {
+            assert_eq!(index.query_point(*x, *y), Some(*id as u32));
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Storage strategies for the level and index of each node during the layout.
+use std::collections::{BTreeMap, HashMap};
+
+use petgraph::stable_graph::NodeIndex;
+use pyo3::prelude::*;
+
+/// Which map is used to store the level and index of each node while creating a layout.
+///
+/// Both produce the same layouts, they only differ in performance.
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NodeStorage {
+    /// Store the nodes in a [HashMap].
+    #[default]
+    HashMap,
+    /// Store the nodes in a [BTreeMap], which iterates the nodes in a deterministic order.
+    BTreeMap,
+    /// Store the nodes in a [Vec] indexed by the node, avoiding the cost of hashing.
+    ///
+    /// Requires the `fast_storage` feature, without it the nodes are stored in a [HashMap].
+    Vec,
+}
+
+/// Maps a node to a level or an index, backed by the map selected via [NodeStorage].
+#[derive(Debug)]
+pub(crate) enum NodeMap {
+    Hash(HashMap<NodeIndex, usize>),
+    BTree(BTreeMap<NodeIndex, usize>),
+    #[cfg(feature = "fast_storage")]
+    Vec(Vec<Option<usize>>),
+}
+
+impl NodeMap {
+    /// Create an empty map for nodes with indices up to `node_bound`.
+    ///
+    /// The bound is only used to preallocate the [NodeStorage::Vec] variant, which grows if larger
+    /// nodes are inserted.
+    #[cfg_attr(not(feature = "fast_storage"), allow(unused_variables))]
+    pub(crate) fn new(storage: NodeStorage, node_bound: usize) -> Self {
+        match storage {
+            NodeStorage::HashMap => Self::Hash(HashMap::new()),
+            NodeStorage::BTreeMap => Self::BTree(BTreeMap::new()),
+            #[cfg(feature = "fast_storage")]
+            NodeStorage::Vec => Self::Vec(vec![None; node_bound]),
+            #[cfg(not(feature = "fast_storage"))]
+            NodeStorage::Vec => Self::Hash(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, node: &NodeIndex) -> Option<usize> {
+        match self {
+            Self::Hash(map) => map.get(node).copied(),
+            Self::BTree(map) => map.get(node).copied(),
+            #[cfg(feature = "fast_storage")]
+            Self::Vec(values) => values.get(node.index()).copied().flatten(),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, node: NodeIndex, value: usize) -> Option<usize> {
+        match self {
+            Self::Hash(map) => map.insert(node, value),
+            Self::BTree(map) => map.insert(node, value),
+            #[cfg(feature = "fast_storage")]
+            Self::Vec(values) => {
+                if node.index() >= values.len() {
+                    values.resize(node.index() + 1, None);
+                }
+                values[node.index()].replace(value)
+            }
+        }
+    }
+}
@@ -0,0 +1,718 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Transformations which are applied to a layout after it has been computed.
+use std::collections::{HashMap, HashSet};
+
+use pyo3::prelude::*;
+
+use super::{
+    error::LayoutError, graph_layout::GraphLayout, LayoutArrays, LayoutLevelArrays, NodePositions,
+    ScaledPositions,
+};
+
+/// The direction in which the levels of a layout are arranged.
+#[pyclass]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Levels are stacked from top to bottom, the orientation produced by the layout functions.
+    TopDown,
+    /// Levels are arranged from left to right.
+    LeftRight,
+}
+
+/// Calculate the width and height of a layout, including the size of the nodes.
+///
+/// An empty layout has a width and height of `0`.
+#[pyfunction]
+pub fn layout_dimensions(layout: NodePositions, node_size: isize) -> (isize, isize) {
+    match bounds(&layout) {
+        Some((min_x, min_y, max_x, max_y)) => {
+            (max_x - min_x + node_size, max_y - min_y + node_size)
+        }
+        None => (0, 0),
+    }
+}
+
+/// Swap the x- and y-coordinate of each node, i.e. mirror the layout at the diagonal.
+///
+/// In contrast to [to_left_right], the signs are kept, so this is its own inverse.
+#[pyfunction]
+pub fn transpose_layout(layout: NodePositions) -> NodePositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (y, x)))
+        .collect()
+}
+
+/// Rotate a top-down layout, so that its levels are arranged from left to right.
+///
+/// The first level ends up at the left, the nodes of a level are arranged from top to bottom.
+#[pyfunction]
+pub fn to_left_right(layout: NodePositions) -> NodePositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (-y, -x)))
+        .collect()
+}
+
+/// Choose the orientation of a top-down layout, which best matches a target aspect ratio.
+///
+/// The aspect ratio is width / height, e.g. `16. / 9.` for a wide panel. Both orientations are
+/// compared by how far their ratio is off the target on a logarithmic scale, so being twice as
+/// wide counts the same as being twice as narrow.
+/// Returns the layout in the chosen orientation, together with the orientation. Raises a
+/// `ValueError` if `target_ratio` isn't positive and finite.
+#[pyfunction]
+pub fn fit_aspect_ratio(
+    layout: NodePositions,
+    node_size: isize,
+    target_ratio: f64,
+) -> Result<(NodePositions, Orientation), LayoutError> {
+    GraphLayout::check_target_ratio(target_ratio)?;
+    let distance = |layout: &NodePositions| {
+        let (width, height) = layout_dimensions(layout.clone(), node_size);
+        ((width as f64 / height as f64).ln() - target_ratio.ln()).abs()
+    };
+
+    let left_right = to_left_right(layout.clone());
+    if distance(&left_right) < distance(&layout) {
+        Ok((left_right, Orientation::LeftRight))
+    } else {
+        Ok((layout, Orientation::TopDown))
+    }
+}
+
+/// Express the x-coordinate of each node as a fraction of the width of its level.
+///
+/// The nodes of a level are the nodes sharing the same y-coordinate. The leftmost node of a level
+/// gets an x of `0`, the rightmost node an x of `1`. A node which is alone in its level is placed
+/// at `0.5`. The y-coordinates are kept as they are.
+#[pyfunction]
+pub fn normalize_x(layout: NodePositions) -> HashMap<usize, (f64, isize)> {
+    let mut level_bounds = HashMap::<isize, (isize, isize)>::new();
+    for (x, y) in layout.values() {
+        let (min_x, max_x) = level_bounds.entry(*y).or_insert((*x, *x));
+        *min_x = (*min_x).min(*x);
+        *max_x = (*max_x).max(*x);
+    }
+
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| {
+            let (min_x, max_x) = level_bounds[&y];
+            let x = if min_x == max_x {
+                0.5
+            } else {
+                (x - min_x) as f64 / (max_x - min_x) as f64
+            };
+            (id, (x, y))
+        })
+        .collect()
+}
+
+/// Multiply each coordinate by `scale`, converting the integer grid of a layout to the unit used
+/// by the caller, e.g. points or inches.
+#[pyfunction]
+pub fn scale_layout(layout: NodePositions, scale: f64) -> ScaledPositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (x as f64 * scale, y as f64 * scale)))
+        .collect()
+}
+
+/// Multiply each coordinate by `scale` and round it to the nearest integer, staying on the integer
+/// grid of the layout, in contrast to [scale_layout].
+#[pyfunction]
+pub fn scale_layout_rounded(layout: NodePositions, scale: f64) -> NodePositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| {
+            let scale = |value: isize| (value as f64 * scale).round() as isize;
+            (id, (scale(x), scale(y)))
+        })
+        .collect()
+}
+
+/// Flatten the layouts of all components like [layouts_to_arrays], additionally returning the
+/// level of each node and its index within the level.
+///
+/// The levels are numbered from top to bottom, see [split_levels], the nodes of a level from left
+/// to right.
+pub fn layouts_to_level_arrays(layouts: &[NodePositions]) -> LayoutLevelArrays {
+    let (ids, xs, ys, offsets) = layouts_to_arrays(layouts);
+    let mut level_and_index = HashMap::new();
+    for (component, layout) in layouts.iter().enumerate() {
+        for (level, nodes) in split_levels(layout.clone(), false).into_iter().enumerate() {
+            let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+            nodes.sort_by_key(|(id, (x, _))| (*x, *id));
+            for (index, (id, _)) in nodes.into_iter().enumerate() {
+                level_and_index.insert((component, id), (level, index));
+            }
+        }
+    }
+
+    let (levels, indices) = offsets
+        .windows(2)
+        .enumerate()
+        .flat_map(|(component, range)| {
+            ids[range[0]..range[1]]
+                .iter()
+                .map(move |id| (component, *id as usize))
+        })
+        .map(|node| level_and_index[&node])
+        .unzip();
+    (ids, xs, ys, offsets, levels, indices)
+}
+
+/// Move each node by `dx` and `dy`.
+#[pyfunction]
+pub fn translate_layout(layout: NodePositions, dx: isize, dy: isize) -> NodePositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (x + dx, y + dy)))
+        .collect()
+}
+
+/// Move the layout, so that the top left corner of its bounding box lies at the origin, i.e. the
+/// smallest x- and y-coordinates become `0`.
+#[pyfunction]
+pub fn normalize_layout(layout: NodePositions) -> NodePositions {
+    match bounds(&layout) {
+        Some((min_x, min_y, ..)) => translate_layout(layout, -min_x, -min_y),
+        None => layout,
+    }
+}
+
+/// Mirror the layout at the y-axis, i.e. negate the x-coordinate of each node, keeping the levels.
+#[pyfunction]
+pub fn mirror_layout(layout: NodePositions) -> NodePositions {
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (-x, y)))
+        .collect()
+}
+
+/// A single step of a [LayoutPipeline], applying the function of the same name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LayoutTransform {
+    Normalize,
+    Translate(isize, isize),
+    Scale(f64),
+    Snap(isize),
+    Transpose,
+    Mirror,
+    LeftRight,
+    PinLeft,
+}
+
+/// A sequence of transformations, which are applied to a layout in the order they were added.
+///
+/// Each method returns a new pipeline with the step appended, so they can be chained, e.g.
+/// `LayoutPipeline().normalize().scale(0.5).snap(10).apply(layout)`.
+#[pyclass]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutPipeline {
+    steps: Vec<LayoutTransform>,
+}
+
+impl LayoutPipeline {
+    fn then(&self, step: LayoutTransform) -> Self {
+        let mut steps = self.steps.clone();
+        steps.push(step);
+        Self { steps }
+    }
+}
+
+#[pymethods]
+impl LayoutPipeline {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append [normalize_layout].
+    pub fn normalize(&self) -> Self {
+        self.then(LayoutTransform::Normalize)
+    }
+
+    /// Append [translate_layout].
+    pub fn translate(&self, dx: isize, dy: isize) -> Self {
+        self.then(LayoutTransform::Translate(dx, dy))
+    }
+
+    /// Append [scale_layout_rounded].
+    pub fn scale(&self, scale: f64) -> Self {
+        self.then(LayoutTransform::Scale(scale))
+    }
+
+    /// Append [snap_layout].
+    pub fn snap(&self, grid: isize) -> Self {
+        self.then(LayoutTransform::Snap(grid))
+    }
+
+    /// Append [transpose_layout].
+    pub fn transpose(&self) -> Self {
+        self.then(LayoutTransform::Transpose)
+    }
+
+    /// Append [mirror_layout].
+    pub fn mirror(&self) -> Self {
+        self.then(LayoutTransform::Mirror)
+    }
+
+    /// Append [to_left_right].
+    pub fn left_right(&self) -> Self {
+        self.then(LayoutTransform::LeftRight)
+    }
+
+    /// Append [pin_left].
+    pub fn pin_left(&self) -> Self {
+        self.then(LayoutTransform::PinLeft)
+    }
+
+    /// Apply all steps to the layout, in the order they were added.
+    pub fn apply(&self, layout: NodePositions) -> NodePositions {
+        self.steps.iter().fold(layout, |layout, step| match *step {
+            LayoutTransform::Normalize => normalize_layout(layout),
+            LayoutTransform::Translate(dx, dy) => translate_layout(layout, dx, dy),
+            LayoutTransform::Scale(scale) => scale_layout_rounded(layout, scale),
+            LayoutTransform::Snap(grid) => snap_layout(layout, grid),
+            LayoutTransform::Transpose => transpose_layout(layout),
+            LayoutTransform::Mirror => mirror_layout(layout),
+            LayoutTransform::LeftRight => to_left_right(layout),
+            LayoutTransform::PinLeft => pin_left(layout),
+        })
+    }
+}
+
+/// Shift each level, so that its leftmost node sits at x = 0.
+///
+/// In contrast to the centering done while creating the layout, this operates on the final
+/// coordinates, so equivalent levels line up at the same origin across different runs.
+#[pyfunction]
+pub fn pin_left(layout: NodePositions) -> NodePositions {
+    let mut level_min_x = HashMap::<isize, isize>::new();
+    for (x, y) in layout.values() {
+        let min_x = level_min_x.entry(*y).or_insert(*x);
+        *min_x = (*min_x).min(*x);
+    }
+
+    layout
+        .into_iter()
+        .map(|(id, (x, y))| (id, (x - level_min_x[&y], y)))
+        .collect()
+}
+
+/// Round each coordinate to the nearest multiple of `grid`.
+///
+/// If two nodes end up at the same position, the one which was further right, or further down if
+/// both were at the same x, is moved right by `grid` until its position is free. A `grid` smaller
+/// than `1` leaves the layout as it is.
+#[pyfunction]
+pub fn snap_layout(layout: NodePositions, grid: isize) -> NodePositions {
+    if grid < 1 {
+        return layout;
+    }
+    let snap = |value: isize| (value + grid / 2).div_euclid(grid) * grid;
+
+    let mut nodes = layout.into_iter().collect::<Vec<_>>();
+    nodes.sort_by_key(|(id, (x, y))| (*x, -*y, *id));
+    let mut occupied = HashSet::new();
+    nodes
+        .into_iter()
+        .map(|(id, (x, y))| {
+            let mut position = (snap(x), snap(y));
+            while !occupied.insert(position) {
+                position.0 += grid;
+            }
+            (id, position)
+        })
+        .collect()
+}
+
+/// Run-length encode the level of each node.
+///
+/// The ids are sorted and each run of consecutive ids which share the same level is turned into a
+/// span of (first id, last id, level). This is a lot more compact than the full map, if the ids of
+/// a graph are assigned level by level.
+#[pyfunction]
+pub fn level_spans(levels: HashMap<u32, usize>) -> Vec<(u32, u32, usize)> {
+    let mut levels = levels.into_iter().collect::<Vec<_>>();
+    levels.sort();
+
+    let mut spans: Vec<(u32, u32, usize)> = Vec::new();
+    for (id, level) in levels {
+        match spans.last_mut() {
+            Some((_, last, span_level)) if *last + 1 == id && *span_level == level => *last = id,
+            _ => spans.push((id, id, level)),
+        }
+    }
+    spans
+}
+
+/// Decode the spans created by [level_spans] into the level of each node.
+#[pyfunction]
+pub fn decode_level_spans(spans: Vec<(u32, u32, usize)>) -> HashMap<u32, usize> {
+    spans
+        .into_iter()
+        .flat_map(|(first, last, level)| (first..=last).map(move |id| (id, level)))
+        .collect()
+}
+
+/// Split a layout into one map per level, e.g. to reveal the levels one after another.
+///
+/// The nodes of a level are the nodes sharing the same y-coordinate. The levels are ordered from
+/// top to bottom, i.e. by decreasing y, or by increasing y if the layout uses `screen_coords`.
+#[pyfunction]
+#[pyo3(signature = (layout, screen_coords=false))]
+pub fn split_levels(layout: NodePositions, screen_coords: bool) -> Vec<NodePositions> {
+    let mut levels = HashMap::<isize, NodePositions>::new();
+    for (id, (x, y)) in layout {
+        levels.entry(y).or_default().insert(id, (x, y));
+    }
+
+    let mut levels = levels.into_iter().collect::<Vec<_>>();
+    levels.sort_by_key(|(y, _)| if screen_coords { *y } else { -*y });
+    levels.into_iter().map(|(_, level)| level).collect()
+}
+
+/// Flatten the layouts of all components into parallel arrays of ids, x- and y-coordinates.
+///
+/// The nodes of each component are sorted by their id. The last array contains the offset of each
+/// component, followed by the total number of nodes, so the nodes of component `i` are found in
+/// `offsets[i]..offsets[i + 1]`.
+pub fn layouts_to_arrays(layouts: &[NodePositions]) -> LayoutArrays {
+    let (mut ids, mut xs, mut ys) = (Vec::new(), Vec::new(), Vec::new());
+    let mut offsets = vec![0];
+    for layout in layouts {
+        let mut nodes = layout.iter().collect::<Vec<_>>();
+        nodes.sort();
+        for (id, (x, y)) in nodes {
+            ids.push(*id as u32);
+            xs.push(*x);
+            ys.push(*y);
+        }
+        offsets.push(ids.len());
+    }
+    (ids, xs, ys, offsets)
+}
+
+/// Returns the minimum and maximum coordinates of a layout as (min_x, min_y, max_x, max_y), or
+/// `None` if the layout is empty.
+fn bounds(layout: &NodePositions) -> Option<(isize, isize, isize, isize)> {
+    let mut positions = layout.values();
+    let &(x, y) = positions.next()?;
+    Some(
+        positions.fold((x, y, x, y), |(min_x, min_y, max_x, max_y), &(x, y)| {
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        decode_level_spans, fit_aspect_ratio, layout_dimensions, layouts_to_arrays, level_spans,
+        normalize_layout, normalize_x, pin_left, scale_layout, scale_layout_rounded, snap_layout,
+        split_levels, transpose_layout, LayoutPipeline, Orientation,
+    };
+    use crate::{graph_layout::GraphLayout, NodePositions};
+
+    #[test]
+    fn fit_aspect_ratio_tall_chain_becomes_left_right() {
+        let nodes = (1..=8).collect::<Vec<_>>();
+        let edges = (1..8).map(|n| (n, n + 1)).collect::<Vec<_>>();
+        let (layouts, _, _) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let (width, height) = layout_dimensions(layouts[0].clone(), 40);
+        assert!(height > width);
+
+        let (layout, orientation) = fit_aspect_ratio(layouts[0].clone(), 40, 16. / 9.).unwrap();
+
+        assert_eq!(orientation, Orientation::LeftRight);
+        let (width, height) = layout_dimensions(layout.clone(), 40);
+        assert!(width > height);
+        // the chain now runs from left to right
+        assert!((1..8).all(|n| layout[&n].0 < layout[&(n + 1)].0));
+    }
+
+    #[test]
+    fn normalize_x_leftmost_is_zero_rightmost_is_one() {
+        let layout = NodePositions::from([
+            (1, (160, 0)),
+            (2, (40, -160)),
+            (3, (120, -160)),
+            (4, (360, -160)),
+        ]);
+
+        let normalized = normalize_x(layout);
+
+        assert_eq!(normalized[&1], (0.5, 0));
+        assert!(normalized[&2].0.abs() < f64::EPSILON);
+        assert!((normalized[&3].0 - 0.25).abs() < f64::EPSILON);
+        assert!((normalized[&4].0 - 1.).abs() < f64::EPSILON);
+        assert_eq!(normalized[&4].1, -160);
+    }
+
+    #[test]
+    fn pin_left_minimum_x_per_level_is_zero() {
+        let nodes = (1..=7).collect::<Vec<_>>();
+        let edges = [(1, 2), (1, 3), (1, 4), (2, 5), (6, 5), (4, 7)];
+        let (layouts, _, _) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        let pinned = pin_left(layouts[0].clone());
+
+        let mut min_x = HashMap::new();
+        for (x, y) in pinned.values() {
+            let min = min_x.entry(*y).or_insert(*x);
+            *min = (*min).min(*x);
+        }
+        assert!(min_x.values().all(|x| *x == 0));
+        // the spacing within a level is kept
+        for (id, (x, y)) in &layouts[0] {
+            assert_eq!(pinned[id].1, *y);
+            for (other, (other_x, other_y)) in &layouts[0] {
+                if other_y == y {
+                    assert_eq!(pinned[id].0 - pinned[other].0, x - other_x);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fit_aspect_ratio_keeps_matching_orientation() {
+        let nodes = (1..=8).collect::<Vec<_>>();
+        let edges = (1..8).map(|n| (n, n + 1)).collect::<Vec<_>>();
+        let (layouts, _, _) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        let (layout, orientation) = fit_aspect_ratio(layouts[0].clone(), 40, 9. / 16.).unwrap();
+
+        assert_eq!(orientation, Orientation::TopDown);
+        assert_eq!(layout, layouts[0]);
+    }
+
+    #[test]
+    fn fit_aspect_ratio_empty_layout_and_invalid_ratio() {
+        assert_eq!(layout_dimensions(NodePositions::new(), 40), (0, 0));
+        assert_eq!(normalize_layout(NodePositions::new()), NodePositions::new());
+        assert_eq!(
+            fit_aspect_ratio(NodePositions::new(), 40, 1.).unwrap(),
+            (NodePositions::new(), Orientation::TopDown)
+        );
+
+        let layout = NodePositions::from([(1, (0, 0))]);
+        for ratio in [0., -1., f64::NAN, f64::INFINITY] {
+            assert!(fit_aspect_ratio(layout.clone(), 40, ratio).is_err());
+        }
+    }
+
+    #[test]
+    fn level_spans_decode_to_levels() {
+        // three levels of five nodes each, every node is connected to the next one in its column
+        // and the first column connects all of them
+        let nodes = (1..=15).collect::<Vec<_>>();
+        let mut edges = (1..=10).map(|id| (id, id + 5)).collect::<Vec<_>>();
+        edges.extend((2..=5).map(|id| (1, id + 5)));
+
+        let levels = GraphLayout::create_levels(&nodes, &edges, false).unwrap();
+
+        assert_eq!(levels.len(), 1);
+        let spans = level_spans(levels[0].clone());
+        assert_eq!(spans, vec![(1, 5, 0), (6, 10, 1), (11, 15, 2)]);
+        assert_eq!(decode_level_spans(spans), levels[0]);
+    }
+
+    #[test]
+    fn transpose_layout_swaps_coordinates() {
+        let layout = NodePositions::from([(1, (0, 0)), (2, (-160, -160)), (3, (160, -320))]);
+
+        let transposed = transpose_layout(layout.clone());
+
+        assert_eq!(
+            transposed,
+            NodePositions::from([(1, (0, 0)), (2, (-160, -160)), (3, (-320, 160))])
+        );
+        assert_eq!(transpose_layout(transposed), layout);
+    }
+
+    #[test]
+    fn snap_layout_multiples_of_grid_and_distinct() {
+        let layout = NodePositions::from([
+            (1, (0, 0)),
+            (2, (1, -13)),
+            (3, (2, -14)),
+            (4, (21, 4)),
+            (5, (-12, -30)),
+        ]);
+
+        let snapped = snap_layout(layout, 8);
+
+        assert_eq!(snapped.len(), 5);
+        for (x, y) in snapped.values() {
+            assert_eq!(x % 8, 0);
+            assert_eq!(y % 8, 0);
+        }
+        let positions = snapped.values().collect::<std::collections::HashSet<_>>();
+        assert_eq!(positions.len(), 5);
+        // 2 and 3 both snap to (0, -16), 3 is further right and gets nudged
+        assert_eq!(snapped[&2], (0, -16));
+        assert_eq!(snapped[&3], (8, -16));
+    }
+
+    #[test]
+    fn layouts_to_arrays_reproduce_layouts() {
+        let nodes = (1..=9).collect::<Vec<_>>();
+        let edges = vec![(1, 2), (1, 3), (2, 4), (3, 4), (5, 6), (7, 8), (7, 9)];
+        let (layouts, _, _) =
+            crate::create_layouts_sugiyama(nodes.clone(), edges.clone(), Default::default());
+
+        let (ids, xs, ys, offsets) =
+            crate::create_layouts_sugiyama_arrays(nodes, edges, Default::default());
+
+        assert_eq!(offsets.len(), layouts.len() + 1);
+        let rebuilt = offsets
+            .windows(2)
+            .map(|component| {
+                (component[0]..component[1])
+                    .map(|i| (ids[i] as usize, (xs[i], ys[i])))
+                    .collect::<NodePositions>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(rebuilt, layouts);
+        assert_eq!(layouts_to_arrays(&rebuilt).3, offsets);
+    }
+
+    #[test]
+    fn split_levels_flatten_to_layout() {
+        let nodes = [1, 2, 3, 4, 5, 6];
+        let edges = [(1, 2), (1, 3), (3, 4), (5, 6)];
+        let (layouts, _, heights) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        for (layout, height) in layouts.into_iter().zip(heights) {
+            let levels = split_levels(layout.clone(), false);
+
+            assert_eq!(levels.len(), height);
+            let flattened = levels
+                .iter()
+                .flatten()
+                .map(|(id, position)| (*id, *position));
+            assert_eq!(flattened.collect::<NodePositions>(), layout);
+            // the levels are ordered from top to bottom
+            let ys = levels
+                .iter()
+                .map(|level| level.values().next().unwrap().1)
+                .collect::<Vec<_>>();
+            assert!(ys.windows(2).all(|ys| ys[0] > ys[1]));
+        }
+    }
+
+    #[test]
+    fn scale_layout_half_scale_halves_coordinates() {
+        let nodes = (1..=6).collect::<Vec<_>>();
+        let edges = [(1, 2), (1, 3), (2, 4), (3, 4), (5, 6)];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+
+        for layout in layouts {
+            let scaled = scale_layout(layout.clone(), 0.5);
+            assert_eq!(scaled.len(), layout.len());
+            for (id, (x, y)) in layout {
+                assert_eq!(scaled[&id], (x as f64 / 2., y as f64 / 2.));
+            }
+        }
+    }
+
+    #[test]
+    fn layout_pipeline_same_as_sequence() {
+        let nodes = (1..=8).collect::<Vec<_>>();
+        let edges = [
+            (1, 2),
+            (1, 3),
+            (2, 4),
+            (3, 4),
+            (4, 5),
+            (4, 6),
+            (6, 7),
+            (7, 8),
+        ];
+        let (layouts, ..) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+        let layout = layouts[0].clone();
+
+        let pipeline = LayoutPipeline::new().normalize().scale(0.3).snap(25);
+        let by_hand = snap_layout(
+            scale_layout_rounded(normalize_layout(layout.clone()), 0.3),
+            25,
+        );
+
+        assert_eq!(pipeline.apply(layout.clone()), by_hand);
+        assert_eq!(LayoutPipeline::new().apply(layout.clone()), layout);
+    }
+
+    #[test]
+    fn layouts_to_level_arrays_reconstruct_ordering() {
+        let nodes = (1..=9).collect::<Vec<_>>();
+        let edges = vec![(1, 2), (1, 3), (2, 4), (3, 4), (5, 6), (7, 8), (7, 9)];
+        let (layouts, ..) =
+            crate::create_layouts_sugiyama(nodes.clone(), edges.clone(), Default::default());
+
+        let (ids, xs, ys, offsets, levels, indices) =
+            crate::create_layouts_sugiyama_level_arrays(nodes, edges, Default::default());
+
+        assert_eq!(
+            (ids.clone(), xs, ys, offsets.clone()),
+            layouts_to_arrays(&layouts)
+        );
+        assert_eq!(levels.len(), ids.len());
+        assert_eq!(indices.len(), ids.len());
+        for (component, layout) in layouts.into_iter().enumerate() {
+            let mut ordering = Vec::<Vec<usize>>::new();
+            for i in offsets[component]..offsets[component + 1] {
+                if ordering.len() <= levels[i] {
+                    ordering.resize(levels[i] + 1, Vec::new());
+                }
+                if ordering[levels[i]].len() <= indices[i] {
+                    ordering[levels[i]].resize(indices[i] + 1, 0);
+                }
+                ordering[levels[i]][indices[i]] = ids[i] as usize;
+            }
+
+            let expected = split_levels(layout, false)
+                .into_iter()
+                .map(|level| {
+                    let mut level = level.into_iter().collect::<Vec<_>>();
+                    level.sort_by_key(|(_, (x, _))| *x);
+                    level.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(ordering, expected);
+        }
+    }
+}
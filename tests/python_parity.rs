@@ -0,0 +1,102 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Compares the layouts of the rust port with the ones of the original python implementation.
+// graph_layout is only used by the benchmarks
+#[allow(dead_code)]
+#[path = "../benches/original_py.rs"]
+mod original_py;
+
+use std::collections::HashMap;
+
+use original_py::graph_layout_result;
+use rs_graph_layout::graph_layout::GraphLayout;
+
+const NODE_SIZE: isize = 40;
+
+/// Check if two sets of layouts contain the same components, where the layouts of a component
+/// may only differ by a translation, e.g. because of a different origin.
+///
+/// The order of the components is ignored.
+pub fn equal_up_to_translation(
+    rust: &[HashMap<usize, (isize, isize)>],
+    python: &[HashMap<i32, (i32, i32)>],
+) -> bool {
+    rust.len() == python.len()
+        && rust.iter().all(|rust_layout| {
+            python.iter().any(|python_layout| {
+                let mut offsets = rust_layout.iter().map(|(id, (x, y))| {
+                    python_layout
+                        .get(&(*id as i32))
+                        .map(|(px, py)| (x - *px as isize, y - *py as isize))
+                });
+                let first = offsets.next().flatten();
+                rust_layout.len() == python_layout.len()
+                    && first.is_some()
+                    && offsets.all(|offset| offset == first)
+            })
+        })
+}
+
+/// Lay out the graph with both implementations and check if they are equivalent.
+fn check_parity(edges: &[(u32, u32)], global_tasks_in_first_row: bool) {
+    let (rust, ..) =
+        GraphLayout::create_layers(&[], edges, NODE_SIZE, global_tasks_in_first_row).unwrap();
+    let python = graph_layout_result(
+        edges.to_vec(),
+        4 * NODE_SIZE as i32,
+        global_tasks_in_first_row,
+    )
+    .unwrap();
+
+    assert!(
+        equal_up_to_translation(&rust, &python),
+        "layouts of {edges:?} differ:\nrust: {rust:?}\npython: {python:?}"
+    );
+}
+
+#[test]
+#[ignore = "requires python with networkx"]
+fn small_graphs_match_python() {
+    // the order of the nodes within a level depends on the iteration order of networkx, so only
+    // graphs without choices in their ordering are compared
+    let graphs: [&[(u32, u32)]; 4] = [
+        &[(1, 2)],
+        &[(1, 2), (2, 3), (3, 4)],
+        &[(1, 2), (2, 3), (4, 5), (5, 6), (6, 7)],
+        &[(1, 2), (2, 3), (3, 4), (5, 6)],
+    ];
+
+    for edges in graphs {
+        check_parity(edges, false);
+        check_parity(edges, true);
+    }
+    check_parity(&[(1, 2), (2, 3), (1, 3)], false);
+}
@@ -0,0 +1,248 @@
+/*
+ AYUDAME/TEMANEJO toolset
+--------------------------
+
+ (C) 2024, HLRS, University of Stuttgart
+ All rights reserved.
+ This software is published under the terms of the BSD license:
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+    * Redistributions of source code must retain the above copyright
+      notice, this list of conditions and the following disclaimer.
+    * Redistributions in binary form must reproduce the above copyright
+      notice, this list of conditions and the following disclaimer in the
+      documentation and/or other materials provided with the distribution.
+    * Neither the name of the <organization> nor the
+      names of its contributors may be used to endorse or promote products
+      derived from this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND
+ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED
+WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL <COPYRIGHT HOLDER> BE LIABLE FOR ANY
+DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES
+(INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES;
+LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND
+ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT
+(INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS
+SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+*/
+//! Checks invariants of the original layout method on many randomly generated DAGs.
+//!
+//! Every failure reports the seed of the graph, so it can be reproduced by running
+//! [check_seed] with it.
+use std::{
+    collections::{HashMap, HashSet},
+    panic::{self, PanicHookInfo},
+    sync::Arc,
+    thread,
+};
+
+use rs_graph_layout::{
+    analysis::{assert_waypoints_monotone, edge_waypoints, route_back_edges},
+    graph_layout::GraphLayout,
+    SugiyamaConfig,
+};
+
+const SEEDS: u64 = 500;
+const MAX_NODES: u64 = 30;
+const NODE_SIZE: isize = 40;
+
+/// A hook which is called when a thread panics, see [panic::set_hook].
+type PanicHook = dyn Fn(&PanicHookInfo) + Sync + Send;
+
+/// Silences the panics of one thread until it is dropped, then restores the previous hook.
+///
+/// The hook is global, so the panics of other threads, e.g. of tests running in parallel, are
+/// still passed on to the previous hook.
+struct SilencedPanics(Arc<PanicHook>);
+
+impl SilencedPanics {
+    fn of_current_thread() -> Self {
+        let previous: Arc<PanicHook> = panic::take_hook().into();
+        let hook = previous.clone();
+        let silenced = thread::current().id();
+        panic::set_hook(Box::new(move |info| {
+            if thread::current().id() != silenced {
+                hook(info);
+            }
+        }));
+        Self(previous)
+    }
+}
+
+impl Drop for SilencedPanics {
+    fn drop(&mut self) {
+        let previous = self.0.clone();
+        panic::set_hook(Box::new(move |info| previous(info)));
+    }
+}
+
+/// A linear congruential generator, so the generated graphs are reproducible.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+}
+
+/// Generate a random DAG with 1 based node ids. Edges always point from a lower to a higher id.
+fn random_dag(seed: u64) -> (Vec<u32>, Vec<(u32, u32)>) {
+    let mut rng = Lcg(seed);
+    let node_count = rng.next() % MAX_NODES + 1;
+    let edge_probability = rng.next() % 40 + 5;
+    let nodes = (1..=node_count as u32).collect::<Vec<_>>();
+    let mut edges = Vec::new();
+    for tail in 1..=node_count as u32 {
+        for head in tail + 1..=node_count as u32 {
+            if rng.next() % 100 < edge_probability {
+                edges.push((tail, head));
+            }
+        }
+    }
+    (nodes, edges)
+}
+
+fn check_seed(seed: u64, global_tasks_in_first_row: bool) -> Result<(), String> {
+    let (nodes, edges) = random_dag(seed);
+    let (layouts, _, _) = panic::catch_unwind(|| {
+        GraphLayout::create_layers(&nodes, &edges, NODE_SIZE, global_tasks_in_first_row).unwrap()
+    })
+    .map_err(|_| "create_layers panicked".to_string())?;
+
+    // every input node has a position
+    let mut positioned = HashSet::new();
+    for layout in &layouts {
+        for id in layout.keys() {
+            if !positioned.insert(*id) {
+                return Err(format!("node {id} is part of multiple components"));
+            }
+        }
+    }
+    for node in &nodes {
+        if !positioned.contains(&(*node as usize)) {
+            return Err(format!("node {node} has no position"));
+        }
+    }
+
+    // no two nodes of a component overlap, nodes of the same level are at least a node apart
+    for layout in &layouts {
+        let mut levels = HashMap::<isize, Vec<(isize, usize)>>::new();
+        for (id, (x, y)) in layout {
+            levels.entry(*y).or_default().push((*x, *id));
+        }
+        for (y, level) in &mut levels {
+            level.sort_unstable();
+            for pair in level.windows(2) {
+                let ((left_x, left), (right_x, right)) = (pair[0], pair[1]);
+                if right_x - left_x < NODE_SIZE {
+                    return Err(format!(
+                        "nodes {left} and {right} at y = {y} are only {} apart",
+                        right_x - left_x
+                    ));
+                }
+            }
+        }
+    }
+
+    // edges point downwards
+    for (tail, head) in &edges {
+        let layout = layouts
+            .iter()
+            .find(|layout| layout.contains_key(&(*tail as usize)))
+            .unwrap();
+        let (tail_y, head_y) = (layout[&(*tail as usize)].1, layout[&(*head as usize)].1);
+        if tail_y <= head_y {
+            return Err(format!(
+                "edge ({tail}, {head}) doesn't point downwards: {tail_y} -> {head_y}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn random_dags_satisfy_layout_invariants() {
+    // don't print the backtraces of caught panics for every failing seed
+    let silenced = SilencedPanics::of_current_thread();
+    let failures = (0..SEEDS)
+        .flat_map(|seed| [(seed, false), (seed, true)])
+        .filter_map(|(seed, first_row)| {
+            check_seed(seed, first_row)
+                .err()
+                .map(|err| format!("seed {seed} (global_tasks_in_first_row: {first_row}): {err}"))
+        })
+        .collect::<Vec<_>>();
+    drop(silenced);
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn random_dags_have_monotone_waypoints() {
+    // a route which turns back up is reported
+    let kinked = HashMap::from([
+        ((1, 2), vec![(0, 0), (0, -160), (0, 0), (0, -320)]),
+        ((1, 3), vec![(0, 0), (80, -160), (0, -320)]),
+    ]);
+    assert_eq!(assert_waypoints_monotone(kinked), vec![(1, 2)]);
+
+    let failures = (0..SEEDS)
+        .flat_map(|seed| [(seed, false), (seed, true)])
+        .filter_map(|(seed, align_dummies)| {
+            let (nodes, edges) = random_dag(seed);
+            let waypoints = edge_waypoints(nodes, edges, SugiyamaConfig::default(), align_dummies);
+            let kinked = assert_waypoints_monotone(waypoints);
+            (!kinked.is_empty()).then(|| {
+                format!("seed {seed} (align_dummies: {align_dummies}): kinked edges {kinked:?}")
+            })
+        })
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn random_back_edges_have_monotone_waypoints() {
+    let failures = (0..SEEDS)
+        .filter_map(|seed| {
+            let (nodes, edges) = random_dag(seed);
+            // reverse every third edge, the others are laid out as usual
+            let back = edges
+                .iter()
+                .step_by(3)
+                .map(|(tail, head)| (*head, *tail))
+                .collect::<Vec<_>>();
+            let (layouts, _, _) = GraphLayout::create_layers(&nodes, &edges, 40, false).unwrap();
+            let mut routed = 0;
+            for layout in layouts {
+                let max_x = layout.values().map(|(x, _)| *x).max().unwrap();
+                let waypoints = route_back_edges(layout.clone(), back.clone(), 40);
+                routed += waypoints.len();
+                for ((tail, head), points) in &waypoints {
+                    let ends = (points[0], points[points.len() - 1]);
+                    if ends != (layout[&(*tail as usize)], layout[&(*head as usize)]) {
+                        return Some(format!("seed {seed}: ({tail}, {head}) misses its nodes"));
+                    }
+                    if points[1..points.len() - 1].iter().any(|(x, _)| *x <= max_x) {
+                        return Some(format!("seed {seed}: ({tail}, {head}) crosses the layout"));
+                    }
+                }
+                let kinked = assert_waypoints_monotone(waypoints);
+                if !kinked.is_empty() {
+                    return Some(format!("seed {seed}: kinked back edges {kinked:?}"));
+                }
+            }
+            (routed != back.len()).then(|| format!("seed {seed}: only routed {routed} back edges"))
+        })
+        .collect::<Vec<_>>();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}